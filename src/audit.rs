@@ -0,0 +1,220 @@
+//! Health report of the whole vault, behind `kofl audit`.
+
+use crate::breach::check_password;
+use crate::context::Context;
+use crate::crypto::{decrypt_secret, secret_mac};
+use crate::db::Db::Entry;
+use crate::strength::estimate_strength;
+use crate::validator::duplicate::DuplicateEntryValidator;
+use crate::validator::pwd_req::{PasswordRequirementValidator, WEAK_SCORE};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Issue {
+    /// The same secret is stored under all these entries.
+    Reused { entries: Vec<String> },
+    Weak { entry: String, reasons: Vec<String> },
+    Breached { entry: String, reason: String },
+    Stale { entry: String, days_overdue: i64 },
+    MissingUsername { entry: String },
+    MissingUrl { entry: String },
+    /// The secret could not be read, the other checks were skipped.
+    Unreadable { entry: String, reason: String },
+    /// One of the checks could not run on the entry.
+    Unchecked { entry: String, reason: String },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::Reused { entries } => write!(f, "same password for {}", entries.join(", ")),
+            Issue::Weak { entry, reasons } => write!(f, "{}: weak password, {}", entry, reasons.join(", ")),
+            Issue::Breached { entry, reason } => write!(f, "{}: breached password, {}", entry, reason),
+            Issue::Stale { entry, days_overdue } => write!(f, "{}: due for rotation, {} days overdue", entry, days_overdue),
+            Issue::MissingUsername { entry } => write!(f, "{}: no username", entry),
+            Issue::MissingUrl { entry } => write!(f, "{}: no URL", entry),
+            Issue::Unreadable { entry, reason } => write!(f, "{}: cannot be read, {}", entry, reason),
+            Issue::Unchecked { entry, reason } => write!(f, "{}: not fully checked, {}", entry, reason),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditReport {
+    pub entries: usize,
+    pub issues: Vec<Issue>,
+}
+
+/// Decrypts every entry of the vault and reports what needs attention.
+pub fn audit_vault(context: &Context) -> Result<AuditReport, String> {
+    let entries = context.db.list_entries().map_err(|e| format!("Error listing entries: {}", e))?;
+    let master_key_hash = context.kgc.borrow().get_hashed_pwd();
+
+    let mut decrypted = Vec::with_capacity(entries.len());
+    let mut unreadable = Vec::new();
+    for entry in entries {
+        let password = match decrypt_secret(&master_key_hash, &entry.password_hash) {
+            Ok(password) => password,
+            Err(e) => {
+                unreadable.push(Issue::Unreadable { entry: entry.ent_name.clone(), reason: e.to_string() });
+                continue;
+            }
+        };
+        let mac = secret_mac(&master_key_hash, &password).map_err(|e| e.to_string())?;
+        match &entry.secret_mac {
+            Some(stored) if *stored != mac => {
+                unreadable.push(Issue::Unreadable {
+                    entry: entry.ent_name.clone(),
+                    reason: "it does not decrypt to the secret that was saved".to_string(),
+                });
+                continue;
+            }
+            Some(_) => (),
+            // entries written before reuse detection get their keyed hash now
+            None => context.db.set_secret_mac(entry.id, &mac).map_err(|e| format!("Database error: {}", e))?,
+        }
+        decrypted.push((entry, password));
+    }
+
+    let mut report = audit_entries(context, &decrypted, Utc::now())?;
+    report.entries += unreadable.len();
+    report.issues.splice(0..0, unreadable);
+    Ok(report)
+}
+
+fn audit_entries(context: &Context, entries: &[(Entry, String)], now: DateTime<Utc>) -> Result<AuditReport, String> {
+    let (default_max_age, hash_file) = {
+        let mut settings = context.settings.borrow_mut();
+        (settings.get_default_max_age(context), settings.get_breach_hash_file(context))
+    };
+
+    let secrets: Vec<(&str, &str)> = entries.iter().map(|(entry, password)| (entry.ent_name.as_str(), password.as_str())).collect();
+    let mut issues: Vec<Issue> = DuplicateEntryValidator::reused(&secrets)
+        .into_iter()
+        .map(|names| Issue::Reused { entries: names.into_iter().map(String::from).collect() })
+        .collect();
+
+    for (entry, password) in entries {
+        let name = &entry.ent_name;
+        let user_inputs: Vec<&str> = [Some(name.as_str()), entry.username.as_deref(), entry.url.as_deref()].into_iter().flatten().collect();
+
+        // the rules of the entry's own policy, then the estimated strength
+        let strength = estimate_strength(password, &user_inputs);
+        let mut reasons = match PasswordRequirementValidator::policy(context, entry.policy.as_deref()) {
+            Ok(policy) => {
+                let mut reasons = policy.violations(password);
+                reasons.extend(policy.strength_violation(&strength));
+                reasons
+            }
+            Err(e) => {
+                issues.push(Issue::Unchecked { entry: name.clone(), reason: e });
+                Vec::new()
+            }
+        };
+        if reasons.is_empty() && strength.score < WEAK_SCORE {
+            reasons.push(format!("{}", strength));
+        }
+        if !reasons.is_empty() {
+            issues.push(Issue::Weak { entry: name.clone(), reasons });
+        }
+
+        match check_password(password, hash_file.as_deref()) {
+            Ok(Some(breach)) => issues.push(Issue::Breached { entry: name.clone(), reason: breach.to_string() }),
+            Ok(None) => (),
+            Err(e) => issues.push(Issue::Unchecked { entry: name.clone(), reason: e }),
+        }
+        if let Some(due) = entry.due_at(default_max_age).filter(|due| *due <= now) {
+            issues.push(Issue::Stale { entry: name.clone(), days_overdue: (now - due).num_days() });
+        }
+        if entry.username.as_deref().map_or(true, str::is_empty) {
+            issues.push(Issue::MissingUsername { entry: name.clone() });
+        }
+        if entry.url.as_deref().map_or(true, str::is_empty) {
+            issues.push(Issue::MissingUrl { entry: name.clone() });
+        }
+    }
+
+    Ok(AuditReport { entries: entries.len(), issues })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use crate::test_support::isolated_context;
+    use tempfile::TempDir;
+
+    fn entry(name: &str, username: Option<&str>, url: Option<&str>, age_days: i64) -> Entry {
+        let mut entry = Entry::new(0, name.to_string(), String::new());
+        entry.username = username.map(String::from);
+        entry.url = url.map(String::from);
+        entry.updated_at = Utc::now() - chrono::Duration::days(age_days);
+        entry
+    }
+
+    #[test]
+    #[serial]
+    fn test_audit_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let (_env, context) = isolated_context(&temp_dir);
+
+        let strong = "xK9#mQ2$vL7!pR4z";
+        let entries = vec![
+            (entry("mail", Some("me"), Some("mail.example.com"), 1), strong.to_string()),
+            (entry("forum", Some("me"), Some("forum.example.com"), 1), strong.to_string()),
+            (entry("bank", None, Some("bank.example.com"), 200), "Tr0ub4dor&3-horse!".to_string()),
+            (entry("shop", Some("me"), None, 1), "password".to_string()),
+        ];
+        let report = audit_entries(&context, &entries, Utc::now()).unwrap();
+
+        assert_eq!(report.entries, 4);
+        assert_eq!(report.issues[0], Issue::Reused { entries: vec!["forum".to_string(), "mail".to_string()] });
+        assert!(report.issues.contains(&Issue::Stale { entry: "bank".to_string(), days_overdue: 20 }));
+        assert!(report.issues.contains(&Issue::MissingUsername { entry: "bank".to_string() }));
+        assert!(report.issues.contains(&Issue::MissingUrl { entry: "shop".to_string() }));
+        assert!(report.issues.iter().any(|issue| matches!(issue, Issue::Weak { entry, .. } if entry == "shop")));
+        assert!(report.issues.iter().any(|issue| matches!(issue, Issue::Breached { entry, .. } if entry == "shop")));
+        assert!(!report.issues.iter().any(|issue| matches!(issue, Issue::Weak { entry, .. } if entry == "mail")));
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["issues"][0]["kind"], "reused");
+        assert_eq!(json["issues"][0]["entries"][1], "mail");
+    }
+
+    #[test]
+    #[serial]
+    fn test_audit_goes_on_after_failed_checks() {
+        let temp_dir = TempDir::new().unwrap();
+        let (_env, context) = isolated_context(&temp_dir);
+        context.db.set_setting_value("breach_hash_file", temp_dir.path().join("missing.txt").to_str().unwrap()).unwrap();
+
+        let mut unknown = entry("mail", Some("me"), Some("mail.example.com"), 1);
+        unknown.policy = Some("deleted".to_string());
+        let entries = vec![(unknown, "xK9#mQ2$vL7!pR4z".to_string())];
+        let report = audit_entries(&context, &entries, Utc::now()).unwrap();
+
+        let reasons: Vec<&str> = report
+            .issues
+            .iter()
+            .filter_map(|issue| match issue {
+                Issue::Unchecked { entry, reason } if entry == "mail" => Some(reason.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(reasons.len(), 2);
+        assert!(reasons[0].contains("No password policy named deleted"));
+        assert!(reasons[1].contains("Cannot read the breach hash file"));
+
+        // an entry that cannot be decrypted is reported along with the others
+        let mut broken = entry("bank", None, None, 1);
+        broken.password_hash = "not hex".to_string();
+        context.db.add_entry(broken).unwrap();
+        context.db.set_setting_value("breach_hash_file", "").unwrap();
+        let report = audit_vault(&context).unwrap();
+        assert_eq!(report.entries, 1);
+        assert!(matches!(&report.issues[0], Issue::Unreadable { entry, .. } if entry == "bank"));
+    }
+}
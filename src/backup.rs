@@ -1,6 +1,6 @@
 use crate::{
     db::Db::Entry,
-    utils::Utils::{get_backup_dir, get_home_dir},
+    utils::Utils::{get_backup_dir, get_home_dir, get_quarantine_dir},
 };
 use chrono::{DateTime, Utc};
 use log::debug;
@@ -50,6 +50,27 @@ impl Backup {
         Ok(())
     }
 
+    /// Copies the files of a backup back in place. The current files are
+    /// quarantined first so a restore can always be undone by hand.
+    pub fn restore_backup(
+        &self,
+        backup_path: &PathBuf,
+        kgc_file: &PathBuf,
+        db_file: &PathBuf,
+        checksumfile: &PathBuf,
+        with_data: bool,
+    ) -> Result<PathBuf, std::io::Error> {
+        let quarantined = quarantine_files(&[kgc_file, checksumfile, db_file])?;
+
+        fs::copy(backup_path.join(".kofl"), kgc_file)?;
+        fs::copy(backup_path.join(".kofl.checksum"), checksumfile)?;
+        if with_data {
+            fs::copy(backup_path.join("kofl.sqlite"), db_file)?;
+        }
+
+        Ok(quarantined)
+    }
+
     pub fn get_last_backup(&self) -> std::io::Result<Option<PathBuf>> {
         let mut entries = fs::read_dir(&self.backup_dir)?
             .filter_map(|e| e.ok())
@@ -76,40 +97,42 @@ impl Backup {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::{env, fs};
-    use tempfile::TempDir;
-
-    struct EnvGuard {
-        key: &'static str,
-        original: Option<String>,
-    }
+/// Copies the given files into a fresh timestamped quarantine directory.
+/// Nothing is removed, missing files are skipped.
+pub fn quarantine_files(files: &[&PathBuf]) -> Result<PathBuf, std::io::Error> {
+    let quarantine_dir = get_quarantine_dir().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "Failed to create or access quarantine directory",
+        )
+    })?;
+    quarantine_files_into(&quarantine_dir, files)
+}
 
-    impl EnvGuard {
-        fn new(key: &'static str) -> Self {
-            let original = env::var(key).ok();
-            Self { key, original }
-        }
+fn quarantine_files_into(quarantine_dir: &PathBuf, files: &[&PathBuf]) -> Result<PathBuf, std::io::Error> {
+    let dir_name = Utc::now().format("%Y-%m-%d_%H_%M_%S").to_string();
+    let target_dir = quarantine_dir.join(&dir_name);
+    fs::create_dir_all(&target_dir)?;
 
-        fn set_var(&self, value: &str) {
-            env::set_var(self.key, value);
+    for file in files {
+        if !file.exists() {
+            debug!("{:?} does not exist, nothing to quarantine", file);
+            continue;
         }
-
-        fn remove_var(&self) {
-            env::remove_var(self.key);
+        if let Some(name) = file.file_name() {
+            fs::copy(file, target_dir.join(name))?;
         }
     }
 
-    impl Drop for EnvGuard {
-        fn drop(&mut self) {
-            match &self.original {
-                Some(original) => env::set_var(self.key, original),
-                None => env::remove_var(self.key),
-            }
-        }
-    }
+    Ok(target_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::test_support::EnvGuard;
+    use tempfile::TempDir;
 
     fn create_test_files(temp_dir: &TempDir) -> (PathBuf, PathBuf, PathBuf) {
         let kgc_file = temp_dir.path().join(".kofl");
@@ -362,4 +385,30 @@ mod tests {
             fs::set_permissions(&backup_dir, fs::Permissions::from_mode(0o755)).unwrap();
         }
     }
+
+    #[test]
+    fn test_quarantine_keeps_original_files() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let quarantine_dir = temp_dir.path().join("quarantine");
+        let (kgc_file, db_file, checksum_file) = create_test_files(&temp_dir);
+        let missing = temp_dir.path().join("missing");
+
+        let result = quarantine_files_into(&quarantine_dir, &[&kgc_file, &checksum_file, &db_file, &missing]);
+        assert!(result.is_ok(), "Quarantine should succeed");
+
+        let target = result.unwrap();
+        assert!(target.starts_with(&quarantine_dir));
+        assert_eq!(
+            fs::read_to_string(target.join("kofl.sqlite")).unwrap(),
+            "test database content"
+        );
+        assert!(target.join(".kofl").exists());
+        assert!(target.join(".kofl.checksum").exists());
+        assert!(!target.join("missing").exists());
+
+        // the originals, and above all the database, are left untouched
+        assert!(kgc_file.exists());
+        assert!(db_file.exists());
+        assert!(checksum_file.exists());
+    }
 }
@@ -0,0 +1,177 @@
+//! Offline check of passwords against known breaches.
+//!
+//! The bundled list of common passwords is always checked. A local copy of the
+//! Have I Been Pwned SHA-1 hashes can be added with the `breach_hash_file`
+//! setting, either one file sorted by hash (`HASH:COUNT` lines) or a directory
+//! of range files named after the first 5 hex characters of the hash
+//! (`SUFFIX:COUNT` lines). Both are searched without loading them in memory.
+
+use crate::strength::COMMON_PASSWORDS;
+use sha1::{Digest, Sha1};
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+const RANGE_PREFIX_LENGTH: usize = 5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Breach {
+    CommonPassword,
+    /// Found in the hash file, with the number of times it was seen.
+    HashFile(u64),
+}
+
+impl std::fmt::Display for Breach {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Breach::CommonPassword => write!(f, "it is one of the most common passwords"),
+            Breach::HashFile(count) => write!(f, "it appeared {} times in known breaches", count),
+        }
+    }
+}
+
+pub fn is_common_password(password: &str) -> bool {
+    let password = password.to_lowercase();
+    COMMON_PASSWORDS.lines().any(|common| common.trim() == password)
+}
+
+fn sha1_hex(password: &str) -> String {
+    hex::encode_upper(Sha1::digest(password.as_bytes()))
+}
+
+// "HASH:COUNT", the hash compared case insensitively
+fn parse_line(line: &str) -> Option<(String, u64)> {
+    let (hash, count) = line.trim().split_once(':')?;
+    Some((hash.to_ascii_uppercase(), count.trim().parse().unwrap_or(1)))
+}
+
+/// Binary search of `target` in a file of lines sorted by their hash.
+fn search_sorted_file(path: &Path, target: &str) -> io::Result<Option<u64>> {
+    let mut file = File::open(path)?;
+    let length = file.metadata()?.len();
+
+    // invariant: every line starting before `low` sorts before the target
+    let (mut low, mut high) = (0u64, length);
+    while low < high {
+        let middle = low + (high - low) / 2;
+        let (line_start, line) = line_at_or_after(&mut file, middle)?;
+        let Some(line) = line else {
+            high = middle;
+            continue;
+        };
+        match parse_line(&line) {
+            Some((hash, count)) => match hash.as_str().cmp(target) {
+                Ordering::Equal => return Ok(Some(count)),
+                Ordering::Less => low = line_start + line.len() as u64,
+                Ordering::Greater => high = middle,
+            },
+            None => low = line_start + line.len() as u64,
+        }
+    }
+
+    // the line starting exactly at `low` has not been looked at yet
+    let (_, line) = line_at_or_after(&mut file, low)?;
+    Ok(line.and_then(|line| parse_line(&line)).filter(|(hash, _)| hash == target).map(|(_, count)| count))
+}
+
+// the first full line starting at or after `offset`, with its start offset
+fn line_at_or_after(file: &mut File, offset: u64) -> io::Result<(u64, Option<String>)> {
+    let mut start = offset;
+    if offset > 0 {
+        // the line only starts here if the previous byte ends a line
+        file.seek(SeekFrom::Start(offset - 1))?;
+        let mut previous = [0u8; 1];
+        file.read_exact(&mut previous)?;
+        if previous[0] != b'\n' {
+            let mut reader = BufReader::new(&mut *file);
+            let mut skipped = Vec::new();
+            start += reader.read_until(b'\n', &mut skipped)? as u64;
+        }
+    }
+
+    file.seek(SeekFrom::Start(start))?;
+    let mut line = String::new();
+    BufReader::new(&mut *file).read_line(&mut line)?;
+    Ok((start, if line.is_empty() { None } else { Some(line) }))
+}
+
+/// Looks the password up in a local HIBP dump, returns how often it was seen.
+pub fn lookup_hash_file(path: &Path, password: &str) -> io::Result<Option<u64>> {
+    let hash = sha1_hex(password);
+    if !path.is_dir() {
+        return search_sorted_file(path, &hash);
+    }
+
+    let (prefix, suffix) = hash.split_at(RANGE_PREFIX_LENGTH);
+    for name in [prefix.to_string(), format!("{}.txt", prefix)] {
+        let range = path.join(name);
+        if range.exists() {
+            return search_sorted_file(&range, suffix);
+        }
+    }
+    Ok(None)
+}
+
+pub fn check_password(password: &str, hash_file: Option<&Path>) -> Result<Option<Breach>, String> {
+    if is_common_password(password) {
+        return Ok(Some(Breach::CommonPassword));
+    }
+    match hash_file {
+        Some(path) => lookup_hash_file(path, password)
+            .map(|count| count.map(Breach::HashFile))
+            .map_err(|e| format!("Cannot read the breach hash file {}: {}", path.display(), e)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+    use tempfile::{NamedTempFile, TempDir};
+
+    fn sorted_hash_file(passwords: &[(&str, u64)], filler: usize) -> NamedTempFile {
+        let mut lines: Vec<String> = passwords.iter().map(|(p, c)| format!("{}:{}", sha1_hex(p), c)).collect();
+        lines.extend((0..filler).map(|i| format!("{}:1", sha1_hex(&format!("filler{}", i)))));
+        lines.sort();
+        let mut file = NamedTempFile::new().unwrap();
+        for line in lines {
+            write!(file, "{}\r\n", line).unwrap();
+        }
+        file
+    }
+
+    #[test]
+    fn test_common_passwords() {
+        assert_eq!(check_password("Password1", None).unwrap(), Some(Breach::CommonPassword));
+        assert_eq!(check_password("xK9#mQ2$vL7!pR4z", None).unwrap(), None);
+    }
+
+    #[test]
+    fn test_sorted_hash_file() {
+        let file = sorted_hash_file(&[("hunter2!", 42), ("correct horse", 7)], 500);
+        assert_eq!(lookup_hash_file(file.path(), "hunter2!").unwrap(), Some(42));
+        assert_eq!(lookup_hash_file(file.path(), "correct horse").unwrap(), Some(7));
+        assert_eq!(lookup_hash_file(file.path(), "not in there").unwrap(), None);
+        for i in [0, 1, 250, 499] {
+            assert_eq!(lookup_hash_file(file.path(), &format!("filler{}", i)).unwrap(), Some(1));
+        }
+
+        let single = sorted_hash_file(&[("only", 3)], 0);
+        assert_eq!(lookup_hash_file(single.path(), "only").unwrap(), Some(3));
+        assert_eq!(lookup_hash_file(single.path(), "other").unwrap(), None);
+    }
+
+    #[test]
+    fn test_range_directory() {
+        let dir = TempDir::new().unwrap();
+        let hash = sha1_hex("hunter2!");
+        let (prefix, suffix) = hash.split_at(RANGE_PREFIX_LENGTH);
+        fs::write(dir.path().join(prefix), format!("{}:9\n", suffix.to_lowercase())).unwrap();
+
+        assert_eq!(check_password("hunter2!", Some(dir.path())).unwrap(), Some(Breach::HashFile(9)));
+        assert_eq!(check_password("something else", Some(dir.path())).unwrap(), None);
+    }
+}
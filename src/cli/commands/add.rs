@@ -5,24 +5,27 @@ use crate::validator::registry::ValidationRegistry;
 use std::fmt;
 use crate::errors::{ErrorExecution, ErrorValidation};
 use crate::context::Context;
+use crate::crypto::{encrypt_secret, secret_mac};
 use crate::db::Db::Entry;
+use crate::cli::output::Report;
+use crate::strength::{estimate_strength, Strength};
 use chrono::prelude::*;
 use log::{debug, info, warn, error};
+use serde::Serialize;
 use sha2::Digest;
-use std::cell::Cell;
 
-
-use aes::cipher::{
-    KeyIvInit, StreamCipher,
-    generic_array::GenericArray,
-};
-use ctr::Ctr32BE;
-type Aes256Ctr = Ctr32BE<aes::Aes256>;
 pub struct AddCmd {
     pub name: String,
     pub password: String,
+    /// The password was generated, so it skips the password requirements.
     pub suggest_flag: bool,
-    pub suggested_pwd : Cell<String>,
+    pub max_age: Option<u32>,
+    /// Password policy checked on typed passwords, the default one when `None`.
+    pub policy: Option<String>,
+    pub username: Option<String>,
+    pub url: Option<String>,
+    pub notes: Option<String>,
+    pub tags: Vec<String>,
 }
 
 
@@ -33,11 +36,59 @@ impl  AddCmd {
             name,
             password,
             suggest_flag,
-            suggested_pwd: Cell::new(String::new()),
+            max_age: None,
+            policy: None,
+            username: None,
+            url: None,
+            notes: None,
+            tags: Vec::new(),
         }
     }
+
+    /// Maximum age in days before the password should be rotated, 0 never expires.
+    pub fn with_max_age(mut self, max_age: Option<u32>) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    pub fn with_policy(mut self, policy: Option<String>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// What the password should not be built from.
+    pub fn user_inputs(&self) -> Vec<&str> {
+        [Some(self.name.as_str()), self.username.as_deref(), self.url.as_deref()].into_iter().flatten().collect()
+    }
+
+    pub fn with_metadata(mut self, username: Option<String>, url: Option<String>, notes: Option<String>) -> Self {
+        self.username = username;
+        self.url = url;
+        self.notes = notes;
+        self
+    }
+
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+}
+
+#[derive(Serialize)]
+pub struct AddOutput {
+    pub name: String,
+    pub strength: Strength,
 }
 
+impl fmt::Display for AddOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Password strength: {}", self.strength)?;
+        write!(f, "Entry added successfully")
+    }
+}
+
+impl Report for AddOutput {}
+
 impl PartialEq for AddCmd {
     fn eq(&self, other: &Self) -> bool {
         if (self.name == other.name) && (self.password == other.password) {return true}
@@ -57,60 +108,40 @@ impl fmt::Debug for AddCmd {
 
 
 impl Command for AddCmd {
-    fn execute(&self, context: &Context) -> bool  {
+    type Output = AddOutput;
+
+    fn execute(&self, context: &Context) -> Result<AddOutput, String> {
         let master_key_hash = {
             let kgc = context.kgc.borrow();
             kgc.get_hashed_pwd()
         };
 
-        let master_key_bytes = match hex::decode(&master_key_hash) {
-            Ok(bytes) => bytes,
-            Err(e) => {
-                error!("Error decoding master key hash: {}", e);
-                return false;
-            }
-        };
-
-        // Create key and nonce
-        let key = GenericArray::from_slice(&master_key_bytes);
-        let nonce = GenericArray::from_slice(&[0u8; 16]); // In production, use secure random nonce
-
-        // Initialize ciph
-        let mut cipher = Aes256Ctr::new(key, nonce);
+        let strength = estimate_strength(&self.password, &self.user_inputs());
 
         // Encrypt the password
-        let mut encrypted_password;
-
-        if (self.suggest_flag) {
-            encrypted_password = self.suggested_pwd.take().into_bytes();
-
-        }else {
-            encrypted_password = self.password.clone().into_bytes();
-        }
-
-        cipher.apply_keystream(&mut encrypted_password);
-
-        // Convert to hex for storage
-        let encrypted_password_hex = hex::encode(encrypted_password);
+        let encrypted_password_hex = match encrypt_secret(&master_key_hash, &self.password) {
+            Ok(hex) => hex,
+            Err(e) => return Err(e.to_string()),
+        };
+        let secret_mac = match secret_mac(&master_key_hash, &self.password) {
+            Ok(mac) => mac,
+            Err(e) => return Err(e.to_string()),
+        };
 
         // Create new entry
-        let new_entry = Entry {
-            id: 0, // will be ignored by sqlite
-            ent_name: self.name.clone(),
-            password_hash: encrypted_password_hex,
-            timestamp: Utc::now().to_rfc3339()
-        };
+        let mut new_entry = Entry::new(0, self.name.clone(), encrypted_password_hex); // id will be ignored by sqlite
+        new_entry.max_age_days = self.max_age;
+        new_entry.username = self.username.clone();
+        new_entry.url = self.url.clone();
+        new_entry.notes = self.notes.clone();
+        new_entry.policy = self.policy.clone();
+        new_entry.secret_mac = Some(secret_mac);
 
         // Add the entry to the database if error return false
 
-        match context.db.add_entry(new_entry) {
-            Ok(_) => {
-                info!("Entry added successfully");
-            },
-            Err(e) => {
-                error!("Error adding entry: {}", e);
-                return false;
-            }
+        match context.db.add_entry_with_tags(new_entry, &self.tags) {
+            Ok(_) => (),
+            Err(e) => return Err(format!("Error adding entry: {}", e)),
         }
 
 
@@ -120,10 +151,10 @@ impl Command for AddCmd {
         &context.kgc.borrow().get_data_storage_path(), 
         &context.kgc.borrow().get_config_path().with_extension("checksum")).unwrap();
 
-        true
+        Ok(AddOutput { name: self.name.clone(), strength })
     }
 
-    fn validate(&self, context: &Context) -> bool  {
+    fn validate(&self, context: &Context) -> Result<(), String>  {
         
         let val_reg = ValidationRegistry::<AddCmd>::new();
 
@@ -132,22 +163,21 @@ impl Command for AddCmd {
             ValidationType::SessionCheck,
             ValidationType::EntryExistsCheck,
             ValidationType::PasswordRequirementCheck,
+            ValidationType::BreachCheck,
+            ValidationType::DuplicateEntryCheck,
         ];
 
 
         for a_check in val_checks {
 
             match val_reg.validators.get(&a_check).unwrap().validate(context, &self) {
-                ValidationResult::Failure(msg) => {
-                    error!("{msg}");
-                    return false;
-                },
+                ValidationResult::Failure(msg) => return Err(msg),
                 ValidationResult::Warning(msg) => warn!("{msg}"),
                 ValidationResult::Success => debug!("test passed ✅")
 
             }
         }
-        true
+        Ok(())
     }
 
     fn display(&self) {
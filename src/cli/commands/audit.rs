@@ -0,0 +1,79 @@
+use crate::audit::{audit_vault, AuditReport};
+use crate::cli::output::Report;
+use crate::cli::Command;
+use crate::context::Context;
+use crate::validator::core::{ValidationResult, ValidationType};
+use crate::validator::registry::ValidationRegistry;
+use log::{debug, error, info, warn};
+use serde::Serialize;
+use std::fmt;
+
+pub struct AuditCmd {
+    /// The audit fails when it finds more issues than this.
+    max_issues: usize,
+}
+
+impl AuditCmd {
+    pub fn new(max_issues: usize) -> Self {
+        AuditCmd { max_issues }
+    }
+}
+
+#[derive(Serialize)]
+pub struct AuditOutput {
+    #[serde(flatten)]
+    pub report: AuditReport,
+    #[serde(skip)]
+    max_issues: usize,
+}
+
+impl fmt::Display for AuditOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.report.issues.is_empty() {
+            return write!(f, "No issue found in {} entries", self.report.entries);
+        }
+        write!(f, "{} issues found in {} entries:", self.report.issues.len(), self.report.entries)?;
+        for issue in &self.report.issues {
+            write!(f, "\n  {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+impl Report for AuditOutput {
+    fn passed(&self) -> bool {
+        self.report.issues.len() <= self.max_issues
+    }
+}
+
+impl Command for AuditCmd {
+    type Output = AuditOutput;
+
+    fn execute(&self, context: &Context) -> Result<AuditOutput, String> {
+        let report = audit_vault(context)?;
+        Ok(AuditOutput { report, max_issues: self.max_issues })
+    }
+
+    fn validate(&self, context: &Context) -> Result<(), String> {
+        let val_reg = ValidationRegistry::<AuditCmd>::new();
+
+        let val_checks = vec![
+            ValidationType::MasterKeyCheck,
+            ValidationType::SessionCheck,
+        ];
+
+        for a_check in val_checks {
+            match val_reg.validators.get(&a_check).unwrap().validate(context, &self) {
+                ValidationResult::Failure(msg) => return Err(msg),
+                ValidationResult::Warning(msg) => warn!("{msg}"),
+                ValidationResult::Success => debug!("test passed ✅")
+            }
+        }
+        Ok(())
+    }
+
+    fn display(&self) {
+        debug!("Audit command");
+        ()
+    }
+}
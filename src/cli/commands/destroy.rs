@@ -1,4 +1,6 @@
+use crate::cli::output::Report;
 use crate::cli::Command;
+use crate::cli::secret::SecretSource;
 use crate::errors::{ErrorExecution, ErrorValidation};
 use crate::context::Context;
 use crate::validator::core::{ValidationResult, ValidationType};
@@ -8,7 +10,10 @@ use rand::distributions::Alphanumeric;
 use rusqlite::config;
 use sha2::{Sha256, Digest};
 use log::{debug, error, info, warn};
+use serde::Serialize;
+use std::fmt;
 use std::fs;
+use std::path::PathBuf;
 
 
 use aes::cipher::KeyIvInit;
@@ -17,23 +22,44 @@ type Aes256Ctr = Ctr32BE<aes::Aes256>;
 
 
 pub struct DestroyCmd {
-    // for now is emty 
+    master_password: SecretSource,
 }
 
 impl DestroyCmd {
     pub fn new() -> Self {
-        DestroyCmd{}
+        DestroyCmd { master_password: SecretSource::master_password(false, &None) }
     }
 
+    pub fn with_master_password(mut self, master_password: SecretSource) -> Self {
+        self.master_password = master_password;
+        self
+    }
+
+}
+
+#[derive(Serialize)]
+pub struct DestroyOutput {
+    /// Backups are kept.
+    pub removed: Vec<PathBuf>,
+}
+
+impl fmt::Display for DestroyOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "All data related to Kofl configuration have been deleted.\nRun init to start again")
+    }
 }
 
+impl Report for DestroyOutput {}
+
 impl Command for DestroyCmd {
-    fn execute(&self, context: &Context) -> bool  {
+    type Output = DestroyOutput;
+
+    fn execute(&self, context: &Context) -> Result<DestroyOutput, String> {
         warn!(
         "Note this is will delete all your data!!, Backup if needed
         ");
         
-        let master_pwd_input = rpassword::prompt_password("Enter the master password ===> ").unwrap();
+        let master_pwd_input = self.master_password.read("Enter the master password ===> ")?;
 
         let salt = context.kgc.borrow().get_salt();
         let stored_hash = context.kgc.borrow().get_hashed_pwd();
@@ -49,8 +75,7 @@ impl Command for DestroyCmd {
     
         // 4. Compare the computed hash with the stored hash.
         if computed_hash_hex != stored_hash {
-            error!("Invalid password");
-            return false;
+            return Err("Invalid password".to_string());
         }
 
         // retrieve all config path and session path
@@ -68,44 +93,30 @@ impl Command for DestroyCmd {
 
         match fs::remove_file(&data_path) {
             Ok(_) => (),
-            Err(err) => {
-                error!("Failed to remove data storage file: {}", err);
-                return false;
-            }
+            Err(err) => return Err(format!("Failed to remove data storage file: {}", err)),
         }
         match fs::remove_file(&config_path) {
             Ok(_) => (),
-            Err(err) => {
-                error!("Failed to remove kofl configuration file: {}", err);
-                return false;
-            }
+            Err(err) => return Err(format!("Failed to remove kofl configuration file: {}", err)),
         }
 
         match fs::remove_file(&session_path) {
             Ok(_) => (),
-            Err(err) => {
-                error!("Failed to remove kofl session file: {}", err);
-                return false;
-            }
+            Err(err) => return Err(format!("Failed to remove kofl session file: {}", err)),
         }
 
-        match fs::remove_file(cheksum_path) {
+        match fs::remove_file(&cheksum_path) {
             Ok(_) => (),
-            Err(err) => {
-                error!("Failed to remove checksum file: {}", err);
-                return false;
-            }
+            Err(err) => return Err(format!("Failed to remove checksum file: {}", err)),
         }
 
 
         // later to be decided if we want to remove backup or not
 
-        info!("\nAll data related to Kofl configuration have been deleted.\nRun init to start again");
-
-        true
+        Ok(DestroyOutput { removed: vec![data_path.clone(), config_path.clone(), session_path.clone(), cheksum_path] })
     }
 
-    fn validate(&self, context: &Context) -> bool  {
+    fn validate(&self, context: &Context) -> Result<(), String>  {
         
         let val_reg = ValidationRegistry::<DestroyCmd>::new();
 
@@ -118,17 +129,14 @@ impl Command for DestroyCmd {
         for a_check in val_checks {
 
             match val_reg.validators.get(&a_check).unwrap().validate(context, &self) {
-                ValidationResult::Failure(msg) => {
-                    error!("{msg}");
-                    return false;
-                },
+                ValidationResult::Failure(msg) => return Err(msg),
                 ValidationResult::Warning(msg) => warn!("{msg}"),
                 ValidationResult::Success => debug!("test passed ✅")
 
             }
         }
         
-        true
+        Ok(())
     }
 
     fn display(&self) {
@@ -0,0 +1,621 @@
+use crate::backup::Backup;
+use crate::cli::output::Report;
+use crate::cli::Command;
+use crate::cli::secret::SecretSource;
+use crate::context::Context;
+use crate::crypto::{decrypt_secret, secret_mac};
+use crate::db::Db::SCHEMA_VERSION;
+use crate::errors::ErrorIntegrity;
+use crate::settings::Setting;
+use log::{debug, error, info, warn};
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// Repair action attached to a finding, only applied with `--fix`.
+pub enum Fix {
+    Chmod(PathBuf),
+    Reseal,
+    SetSchemaVersion,
+    ResetSetting(Setting),
+}
+
+pub struct Finding {
+    pub check: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    pub fix: Option<Fix>,
+}
+
+impl Finding {
+    fn ok(check: &'static str, message: impl Into<String>) -> Self {
+        Finding { check, severity: Severity::Ok, message: message.into(), fix: None }
+    }
+
+    fn warning(check: &'static str, message: impl Into<String>, fix: Option<Fix>) -> Self {
+        Finding { check, severity: Severity::Warning, message: message.into(), fix }
+    }
+
+    fn error(check: &'static str, message: impl Into<String>, fix: Option<Fix>) -> Self {
+        Finding { check, severity: Severity::Error, message: message.into(), fix }
+    }
+}
+
+pub struct DoctorCmd {
+    pub fix: bool,
+    master_password: SecretSource,
+}
+
+impl DoctorCmd {
+    pub fn new(fix: bool) -> Self {
+        DoctorCmd { fix, master_password: SecretSource::master_password(false, &None) }
+    }
+
+    pub fn with_master_password(mut self, master_password: SecretSource) -> Self {
+        self.master_password = master_password;
+        self
+    }
+
+    // doctor opens the vault unchecked, the entries are only decrypted once
+    // the master password is verified
+    fn unlock(&self, context: &Context) -> Result<String, String> {
+        let master_pwd_input = self.master_password.read("Enter the master password ===> ")?;
+        if !context.kgc.borrow().verify_master_password(&master_pwd_input) {
+            return Err("Invalid password".to_string());
+        }
+        Ok(master_pwd_input)
+    }
+
+    fn check_config(&self, context: &Context, unlocked: &Result<String, String>, findings: &mut Vec<Finding>) {
+        let kgc = context.kgc.borrow();
+
+        if !kgc.get_config_path().exists() {
+            findings.push(Finding::warning("config", "no config file yet, run `kofl init`", None));
+            return;
+        }
+
+        let parses = match kgc.read_config_from_toml_file() {
+            Ok(_) => {
+                findings.push(Finding::ok("config", "config file parses"));
+                true
+            }
+            Err(e) => {
+                findings.push(Finding::error(
+                    "config",
+                    format!("config file does not parse ({}), run `kofl restore`", e),
+                    None,
+                ));
+                false
+            }
+        };
+
+        // the master password also authenticates the config, not just its checksum format
+        let integrity = match unlocked {
+            Ok(master_pwd_input) => kgc.verify_mac(&kgc.derive_mac_key(master_pwd_input)),
+            Err(_) => kgc.verify_integrity(),
+        };
+        match integrity {
+            Ok(_) if unlocked.is_ok() => findings.push(Finding::ok("checksum", "config authenticated with the master password")),
+            Ok(_) => findings.push(Finding::ok("checksum", "checksum present and consistent")),
+            Err(ErrorIntegrity::MissingChecksum) if !kgc.is_master_key_provided() => {
+                findings.push(Finding::ok("checksum", "vault not initialised yet, nothing to seal"))
+            }
+            Err(ErrorIntegrity::MissingChecksum) => findings.push(Finding::error(
+                "checksum",
+                "checksum file is missing",
+                Some(Fix::Reseal),
+            )),
+            Err(ErrorIntegrity::Corrupted(reason)) => findings.push(Finding::error(
+                "checksum",
+                format!("corrupted: {}", reason),
+                if parses { Some(Fix::Reseal) } else { None },
+            )),
+            Err(ErrorIntegrity::Unkeyed) => findings.push(Finding::warning(
+                "checksum",
+                "legacy unkeyed checksum, it does not authenticate the config until the next login",
+                Some(Fix::Reseal),
+            )),
+            Err(ErrorIntegrity::Tampered) => findings.push(Finding::error(
+                "checksum",
+                "config does not match its checksum, run `kofl restore` or `kofl restore --reseal` if you edited it",
+                None,
+            )),
+        }
+    }
+
+    fn check_database(&self, context: &Context, unlocked: &Result<String, String>, findings: &mut Vec<Finding>) {
+        match context.db.schema_version() {
+            Ok(version) if version == SCHEMA_VERSION => {
+                findings.push(Finding::ok("schema", format!("schema version {}", version)))
+            }
+            Ok(0) => findings.push(Finding::warning(
+                "schema",
+                "schema version is not recorded",
+                Some(Fix::SetSchemaVersion),
+            )),
+            Ok(version) if version > SCHEMA_VERSION => findings.push(Finding::error(
+                "schema",
+                format!("schema version {} is newer than this kofl ({})", version, SCHEMA_VERSION),
+                None,
+            )),
+            Ok(version) => findings.push(Finding::warning(
+                "schema",
+                format!("schema version {} is older than expected ({})", version, SCHEMA_VERSION),
+                None,
+            )),
+            Err(e) => findings.push(Finding::error("schema", format!("cannot read schema version: {}", e), None)),
+        }
+
+        match context.db.integrity_check() {
+            Ok(rows) if rows.len() == 1 && rows[0] == "ok" => {
+                findings.push(Finding::ok("sqlite", "PRAGMA integrity_check passed"))
+            }
+            Ok(rows) => findings.push(Finding::error("sqlite", rows.join("; "), None)),
+            Err(e) => findings.push(Finding::error("sqlite", format!("integrity check failed to run: {}", e), None)),
+        }
+
+        let kgc = context.kgc.borrow();
+        if !kgc.is_master_key_provided() {
+            findings.push(Finding::ok("entries", "vault not initialised yet, no entries to decrypt"));
+            return;
+        }
+        if let Err(e) = unlocked {
+            findings.push(Finding::warning("entries", format!("not checked without the master password: {}", e), None));
+            return;
+        }
+
+        let entries = match context.db.list_entries() {
+            Ok(entries) => entries,
+            Err(e) => {
+                findings.push(Finding::error("entries", format!("cannot list entries: {}", e), None));
+                return;
+            }
+        };
+
+        // the cipher has no authentication, a wrong key gives garbage rather than an error
+        // so only the keyed hash of the secret tells whether it decrypted right
+        let master_key_hash = kgc.get_hashed_pwd();
+        let (mut broken, mut unverifiable) = (Vec::new(), Vec::new());
+        for entry in &entries {
+            let secret = decrypt_secret(&master_key_hash, &entry.password_hash).ok();
+            match (secret, &entry.secret_mac) {
+                (None, _) => broken.push(entry.ent_name.clone()),
+                (Some(secret), Some(mac)) => {
+                    if secret_mac(&master_key_hash, &secret).ok().as_ref() != Some(mac) {
+                        broken.push(entry.ent_name.clone());
+                    }
+                }
+                (Some(_), None) => unverifiable.push(entry.ent_name.clone()),
+            }
+        }
+
+        if !broken.is_empty() {
+            findings.push(Finding::error(
+                "entries",
+                format!("{} entries do not decrypt with the current key: {}", broken.len(), broken.join(", ")),
+                None,
+            ));
+        } else if !unverifiable.is_empty() {
+            findings.push(Finding::warning(
+                "entries",
+                format!(
+                    "{} entries are not verifiable, they were saved without a keyed hash of the secret: {}",
+                    unverifiable.len(),
+                    unverifiable.join(", ")
+                ),
+                None,
+            ));
+        } else {
+            findings.push(Finding::ok("entries", format!("{} entries verified with the current key", entries.len())));
+        }
+    }
+
+    fn check_permissions(&self, context: &Context, findings: &mut Vec<Finding>) {
+        let kgc = context.kgc.borrow();
+        let files = [
+            kgc.get_config_path().clone(),
+            kgc.get_config_path().with_extension("checksum"),
+            kgc.get_data_storage_path().clone(),
+            context.ss.get_session_path().clone(),
+        ];
+
+        for path in files {
+            if !path.exists() {
+                continue;
+            }
+
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+
+                let mode = match fs::metadata(&path) {
+                    Ok(meta) => meta.permissions().mode() & 0o777,
+                    Err(e) => {
+                        findings.push(Finding::error("permissions", format!("{}: {}", path.display(), e), None));
+                        continue;
+                    }
+                };
+
+                if mode & 0o077 != 0 {
+                    findings.push(Finding::warning(
+                        "permissions",
+                        format!("{} is {:o}, expected 600", path.display(), mode),
+                        Some(Fix::Chmod(path.clone())),
+                    ));
+                } else {
+                    findings.push(Finding::ok("permissions", format!("{} is {:o}", path.display(), mode)));
+                }
+            }
+        }
+    }
+
+    fn check_backups(&self, findings: &mut Vec<Finding>) {
+        match Backup::new() {
+            Ok(bc) => match bc.get_last_backup() {
+                Ok(Some(path)) => findings.push(Finding::ok("backups", format!("last backup {}", path.display()))),
+                Ok(None) => findings.push(Finding::ok("backups", "backup directory reachable, no backup yet")),
+                Err(e) => findings.push(Finding::error("backups", format!("cannot read backup directory: {}", e), None)),
+            },
+            Err(e) => findings.push(Finding::error("backups", format!("backup directory unreachable: {}", e), None)),
+        }
+    }
+
+    fn check_settings(&self, context: &Context, findings: &mut Vec<Finding>) {
+        for setting in context.settings.borrow().list_settings() {
+            match context.db.get_setting_value(setting.key()) {
+                Ok(Some(value)) => match setting.validate(&value) {
+                    Ok(_) => findings.push(Finding::ok("settings", format!("{} = {}", setting.key(), value))),
+                    Err(reason) => findings.push(Finding::error(
+                        "settings",
+                        format!("{} = {} is invalid: {}", setting.key(), value, reason),
+                        Some(Fix::ResetSetting(setting)),
+                    )),
+                },
+                Ok(None) | Err(rusqlite::Error::QueryReturnedNoRows) => findings.push(Finding::ok(
+                    "settings",
+                    format!("{} not set, default {} is used", setting.key(), setting.default_value()),
+                )),
+                Err(e) => findings.push(Finding::error("settings", format!("{}: {}", setting.key(), e), None)),
+            }
+        }
+    }
+
+    fn run_checks(&self, context: &Context, unlocked: &Result<String, String>) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        self.check_config(context, unlocked, &mut findings);
+        self.check_database(context, unlocked, &mut findings);
+        self.check_permissions(context, &mut findings);
+        self.check_backups(&mut findings);
+        self.check_settings(context, &mut findings);
+        findings
+    }
+
+    fn apply_fix(&self, context: &Context, fix: &Fix, unlocked: &Result<String, String>) -> Result<String, String> {
+        match fix {
+            Fix::Chmod(path) => restrict_to_owner(path),
+            Fix::Reseal => {
+                let master_pwd_input = unlocked.as_ref().map_err(|e| e.clone())?;
+                let mut kgc = context.kgc.borrow_mut();
+                let mac_key = kgc.derive_mac_key(master_pwd_input);
+                kgc.set_mac_key(mac_key);
+                if kgc.seal() {
+                    Ok("config resealed".to_string())
+                } else {
+                    Err("config could not be resealed".to_string())
+                }
+            }
+            Fix::SetSchemaVersion => {
+                context.db.set_schema_version(SCHEMA_VERSION).map_err(|e| e.to_string())?;
+                Ok(format!("schema version set to {}", SCHEMA_VERSION))
+            }
+            Fix::ResetSetting(setting) => {
+                context
+                    .db
+                    .set_setting_value(setting.key(), setting.default_value())
+                    .map_err(|e| e.to_string())?;
+                Ok(format!("{} reset to {}", setting.key(), setting.default_value()))
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn restrict_to_owner(path: &Path) -> Result<String, String> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600)).map_err(|e| e.to_string())?;
+    Ok(format!("set {} to 600", path.display()))
+}
+
+// the permission check only runs on unix, nothing offers this fix elsewhere
+#[cfg(not(unix))]
+fn restrict_to_owner(path: &Path) -> Result<String, String> {
+    Err(format!("cannot restrict {} to its owner on this platform", path.display()))
+}
+
+#[derive(Serialize)]
+pub struct DoctorOutput {
+    pub findings: Vec<CheckOutcome>,
+    /// Worst severity left once the fixes were applied.
+    pub remaining: Severity,
+}
+
+#[derive(Serialize)]
+pub struct CheckOutcome {
+    pub check: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    /// `None` when nothing can be fixed.
+    pub fix: Option<FixOutcome>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum FixOutcome {
+    /// `--fix` was not given.
+    Available,
+    Applied { message: String },
+    Failed { message: String },
+}
+
+impl fmt::Display for CheckOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Ok => "ok",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "[{}] {}: {}", severity, self.check, self.message)?;
+        match &self.fix {
+            Some(FixOutcome::Available) => write!(f, "\n  can be fixed with `kofl doctor --fix`"),
+            Some(FixOutcome::Applied { message }) => write!(f, "\n  fixed: {}", message),
+            Some(FixOutcome::Failed { message }) => write!(f, "\n  fix failed: {}", message),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for DoctorOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for finding in &self.findings {
+            writeln!(f, "{}", finding)?;
+        }
+        match self.remaining {
+            Severity::Ok => write!(f, "No problem found."),
+            Severity::Warning => write!(f, "Some warnings remain."),
+            Severity::Error => write!(f, "Some problems remain."),
+        }
+    }
+}
+
+impl Report for DoctorOutput {
+    fn passed(&self) -> bool {
+        self.remaining != Severity::Error
+    }
+}
+
+impl Command for DoctorCmd {
+    type Output = DoctorOutput;
+
+    fn execute(&self, context: &Context) -> Result<DoctorOutput, String> {
+        let mut remaining = Severity::Ok;
+        let mut outcomes = Vec::new();
+
+        // asked once, for the entries check and the reseal fix
+        let unlocked = if context.kgc.borrow().is_master_key_provided() {
+            self.unlock(context)
+        } else {
+            Err("the vault is not initialised".to_string())
+        };
+
+        for finding in self.run_checks(context, &unlocked) {
+            let fix = match (&finding.fix, self.fix) {
+                (Some(fix), true) => match self.apply_fix(context, fix, &unlocked) {
+                    Ok(message) => Some(FixOutcome::Applied { message }),
+                    Err(message) => Some(FixOutcome::Failed { message }),
+                },
+                (Some(_), false) => Some(FixOutcome::Available),
+                (None, _) => None,
+            };
+
+            if !matches!(fix, Some(FixOutcome::Applied { .. })) {
+                remaining = remaining.max(finding.severity);
+            }
+            outcomes.push(CheckOutcome { check: finding.check, severity: finding.severity, message: finding.message, fix });
+        }
+
+        Ok(DoctorOutput { findings: outcomes, remaining })
+    }
+
+    fn validate(&self, _context: &Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn display(&self) {
+        debug!("Doctor Command");
+        ()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::commands::InitCmd;
+    use crate::crypto::encrypt_secret;
+    use crate::db::Db::Entry;
+    use crate::test_support::isolated_context;
+    use serial_test::serial;
+    use tempfile::TempDir;
+
+    const MASTER_PASSWORD: &str = "Master-Passw0rd!x";
+
+    fn password_file(temp_dir: &TempDir, password: &str) -> SecretSource {
+        let path = temp_dir.path().join(format!("master-{}", password.len()));
+        fs::write(&path, password).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        }
+        SecretSource::File(path)
+    }
+
+    fn init(context: &Context, temp_dir: &TempDir) {
+        InitCmd::new().with_master_password(password_file(temp_dir, MASTER_PASSWORD)).execute(context).unwrap();
+    }
+
+    fn doctor(context: &Context, temp_dir: &TempDir, fix: bool) -> DoctorOutput {
+        DoctorCmd::new(fix).with_master_password(password_file(temp_dir, MASTER_PASSWORD)).execute(context).unwrap()
+    }
+
+    fn finding<'a>(output: &'a DoctorOutput, check: &str) -> &'a CheckOutcome {
+        output.findings.iter().find(|outcome| outcome.check == check).unwrap()
+    }
+
+    #[test]
+    #[serial]
+    fn test_fresh_vault() {
+        let temp_dir = TempDir::new().unwrap();
+        let (_env, context) = isolated_context(&temp_dir);
+
+        // no master password is asked, the prompt would fail without a terminal
+        let output = DoctorCmd::new(false).execute(&context).unwrap();
+        assert_eq!(finding(&output, "config").message, "config file parses");
+        assert!(finding(&output, "checksum").message.contains("not initialised"));
+        assert_eq!(finding(&output, "schema").message, format!("schema version {}", SCHEMA_VERSION));
+        assert_eq!(finding(&output, "sqlite").severity, Severity::Ok);
+        assert!(finding(&output, "entries").message.contains("not initialised"));
+        assert_eq!(finding(&output, "backups").severity, Severity::Ok);
+        assert!(finding(&output, "settings").message.contains("not set"));
+        assert!(output.passed());
+
+        fs::remove_file(context.kgc.borrow().get_config_path()).unwrap();
+        let output = DoctorCmd::new(false).execute(&context).unwrap();
+        assert_eq!(finding(&output, "config").message, "no config file yet, run `kofl init`");
+    }
+
+    #[test]
+    #[serial]
+    fn test_entries_need_the_master_password() {
+        let temp_dir = TempDir::new().unwrap();
+        let (_env, context) = isolated_context(&temp_dir);
+        init(&context, &temp_dir);
+        let key = context.kgc.borrow().get_hashed_pwd();
+        let entry = |name: &str, key: &str, secret: &str| {
+            let mut entry = Entry::new(0, name.to_string(), encrypt_secret(key, secret).ok().unwrap());
+            entry.secret_mac = secret_mac(key, secret).ok();
+            entry
+        };
+        context.db.add_entry(entry("mail", &key, "v1")).unwrap();
+
+        let output = doctor(&context, &temp_dir, false);
+        assert_eq!(finding(&output, "checksum").message, "config authenticated with the master password");
+        assert_eq!(finding(&output, "entries").message, "1 entries verified with the current key");
+        assert!(output.passed());
+
+        let wrong = DoctorCmd::new(false).with_master_password(password_file(&temp_dir, "wrong")).execute(&context).unwrap();
+        let entries = finding(&wrong, "entries");
+        assert_eq!(entries.severity, Severity::Warning);
+        assert!(entries.message.contains("Invalid password"));
+
+        // saved before the keyed hash existed, it decrypts to something but nothing can vouch for it
+        context.db.add_entry(Entry::new(0, "legacy".to_string(), encrypt_secret(&key, "v1").ok().unwrap())).unwrap();
+        let entries = doctor(&context, &temp_dir, false);
+        let entries = finding(&entries, "entries");
+        assert_eq!(entries.severity, Severity::Warning);
+        assert!(entries.message.starts_with("1 entries are not verifiable"));
+        assert!(entries.message.ends_with(": legacy"));
+
+        // short secrets under another key often decrypt into valid utf-8, the hash catches them
+        let other_key = "cd".repeat(32);
+        context.db.add_entry(entry("bank", &other_key, "v1")).unwrap();
+        let output = doctor(&context, &temp_dir, false);
+        assert_eq!(finding(&output, "entries").message, "1 entries do not decrypt with the current key: bank");
+        assert!(!output.passed());
+    }
+
+    #[test]
+    #[serial]
+    fn test_checksum_findings() {
+        let temp_dir = TempDir::new().unwrap();
+        let (_env, context) = isolated_context(&temp_dir);
+        init(&context, &temp_dir);
+        let config_path = context.kgc.borrow().get_config_path().clone();
+        let checksum_path = config_path.with_extension("checksum");
+
+        fs::remove_file(&checksum_path).unwrap();
+        let output = doctor(&context, &temp_dir, false);
+        assert_eq!(finding(&output, "checksum").message, "checksum file is missing");
+        assert!(matches!(finding(&output, "checksum").fix, Some(FixOutcome::Available)));
+
+        // the reseal uses the master password given to doctor
+        let output = doctor(&context, &temp_dir, true);
+        assert!(matches!(finding(&output, "checksum").fix, Some(FixOutcome::Applied { .. })));
+        assert_eq!(finding(&doctor(&context, &temp_dir, false), "checksum").severity, Severity::Ok);
+
+        fs::write(&checksum_path, "not a checksum").unwrap();
+        assert!(finding(&doctor(&context, &temp_dir, false), "checksum").message.starts_with("corrupted"));
+
+        fs::write(&checksum_path, context.kgc.borrow().get_config_checksum()).unwrap();
+        assert!(finding(&doctor(&context, &temp_dir, false), "checksum").message.contains("legacy unkeyed"));
+
+        context.kgc.borrow().update();
+        let config = fs::read_to_string(&config_path).unwrap();
+        fs::write(&config_path, config.replace("default_user", "someone_else")).unwrap();
+        let output = doctor(&context, &temp_dir, true);
+        assert!(finding(&output, "checksum").message.contains("does not match its checksum"));
+        assert!(finding(&output, "checksum").fix.is_none());
+
+        fs::write(&config_path, "not = [toml").unwrap();
+        let output = doctor(&context, &temp_dir, false);
+        assert_eq!(finding(&output, "config").severity, Severity::Error);
+    }
+
+    #[test]
+    #[serial]
+    fn test_schema_findings() {
+        let temp_dir = TempDir::new().unwrap();
+        let (_env, context) = isolated_context(&temp_dir);
+
+        context.db.set_schema_version(SCHEMA_VERSION + 1).unwrap();
+        assert!(finding(&doctor(&context, &temp_dir, false), "schema").message.contains("is newer"));
+        context.db.set_schema_version(SCHEMA_VERSION - 1).unwrap();
+        assert!(finding(&doctor(&context, &temp_dir, false), "schema").message.contains("is older"));
+
+        context.db.set_schema_version(0).unwrap();
+        let output = doctor(&context, &temp_dir, true);
+        assert_eq!(finding(&output, "schema").message, "schema version is not recorded");
+        assert!(matches!(finding(&output, "schema").fix, Some(FixOutcome::Applied { .. })));
+        assert_eq!(context.db.schema_version().unwrap(), SCHEMA_VERSION);
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(unix)]
+    fn test_permission_and_setting_fixes() {
+        use std::os::unix::fs::PermissionsExt;
+        let temp_dir = TempDir::new().unwrap();
+        let (_env, context) = isolated_context(&temp_dir);
+        init(&context, &temp_dir);
+        let config_path = context.kgc.borrow().get_config_path().clone();
+        fs::set_permissions(&config_path, fs::Permissions::from_mode(0o644)).unwrap();
+        context.db.set_setting_value(Setting::HistoryLimit.key(), "1000").unwrap();
+
+        let output = doctor(&context, &temp_dir, false);
+        assert!(output.findings.iter().any(|outcome| outcome.check == "permissions" && outcome.message.ends_with("is 644, expected 600")));
+        assert!(output.findings.iter().any(|outcome| outcome.check == "settings" && outcome.message.starts_with("history_limit = 1000 is invalid")));
+
+        let output = doctor(&context, &temp_dir, true);
+        assert_eq!(output.remaining, Severity::Ok);
+        assert_eq!(fs::metadata(&config_path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert_eq!(context.db.get_setting_value(Setting::HistoryLimit.key()).unwrap(), Some("10".to_string()));
+    }
+}
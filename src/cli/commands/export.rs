@@ -0,0 +1,162 @@
+use crate::cli::output::Report;
+use crate::cli::Command;
+use crate::cli::secret::SecretSource;
+use crate::context::Context;
+use crate::export::json::write_json;
+use crate::export::pass::write_store;
+use crate::export::{export_entries, write_private_file, ExportFormat};
+use crate::validator::core::{ValidationResult, ValidationType};
+use crate::validator::registry::ValidationRegistry;
+use log::{debug, error, info, warn};
+use serde::Serialize;
+use std::fmt;
+use std::path::PathBuf;
+
+pub struct ExportCmd {
+    pub path: PathBuf,
+    pub format: ExportFormat,
+    /// Write the secrets in clear instead of encrypting the file with a passphrase.
+    pub unencrypted: bool,
+    passphrase: SecretSource,
+}
+
+impl ExportCmd {
+    pub fn new(path: PathBuf, format: ExportFormat, unencrypted: bool) -> Self {
+        ExportCmd { path, format, unencrypted, passphrase: SecretSource::Prompt }
+    }
+
+    pub fn with_passphrase(mut self, passphrase: SecretSource) -> Self {
+        self.passphrase = passphrase;
+        self
+    }
+
+    fn ask_passphrase(&self) -> Result<String, String> {
+        let passphrase = self.passphrase.read("Enter a passphrase for the export ===> ")?;
+        if passphrase.is_empty() {
+            return Err("The passphrase cannot be empty".to_string());
+        }
+        // a passphrase read from a file or stdin was not mistyped
+        if self.passphrase.is_prompt() {
+            let confirmed = self.passphrase.read("Enter the passphrase again ===> ")?;
+            if passphrase != confirmed {
+                return Err("The passphrases do not match".to_string());
+            }
+        }
+        Ok(passphrase)
+    }
+}
+
+#[derive(Serialize)]
+pub struct ExportOutput {
+    pub path: PathBuf,
+    pub format: ExportFormat,
+    pub entries: usize,
+    pub encrypted: bool,
+}
+
+impl fmt::Display for ExportOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} entries exported to {}", self.entries, self.path.display())
+    }
+}
+
+impl Report for ExportOutput {}
+
+impl Command for ExportCmd {
+    type Output = ExportOutput;
+
+    fn execute(&self, context: &Context) -> Result<ExportOutput, String> {
+        let passphrase = if self.unencrypted {
+            warn!("!!! The export is NOT encrypted: anyone who can read {} can read every password of the vault !!!", self.path.display());
+            warn!("!!! Delete it as soon as it is not needed anymore !!!");
+            None
+        } else {
+            Some(self.ask_passphrase()?)
+        };
+
+        let entries = export_entries(context)?;
+        let count = entries.len();
+
+        match (self.format, &passphrase) {
+            (ExportFormat::Pass, Some(passphrase)) => write_store(&self.path, &entries, passphrase)?,
+            (ExportFormat::Pass, None) => unreachable!("refused by validate"),
+            (ExportFormat::Json, passphrase) => {
+                let data = write_json(entries, passphrase.as_deref())?;
+                write_private_file(&self.path, &data).map_err(|e| format!("Cannot write {}: {}", self.path.display(), e))?;
+            }
+        }
+        Ok(ExportOutput { path: self.path.clone(), format: self.format, entries: count, encrypted: passphrase.is_some() })
+    }
+
+    fn validate(&self, context: &Context) -> Result<(), String> {
+        if self.format == ExportFormat::Pass && self.unencrypted {
+            return Err("The password store is always encrypted, --unencrypted only applies to the json format".to_string());
+        }
+        let val_reg = ValidationRegistry::<ExportCmd>::new();
+
+        let val_checks = vec![
+            ValidationType::MasterKeyCheck,
+            ValidationType::SessionCheck,
+        ];
+
+        for a_check in val_checks {
+            match val_reg.validators.get(&a_check).unwrap().validate(context, &self) {
+                ValidationResult::Failure(msg) => return Err(msg),
+                ValidationResult::Warning(msg) => warn!("{msg}"),
+                ValidationResult::Success => debug!("test passed ✅")
+            }
+        }
+        Ok(())
+    }
+
+    fn display(&self) {
+        debug!("Export command to {}", self.path.display());
+        ()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::commands::{ImportCmd, InitCmd};
+    use crate::crypto::encrypt_secret;
+    use crate::db::Db::Entry;
+    use crate::import::{Collision, ImportFormat};
+    use crate::test_support::isolated_context;
+    use serial_test::serial;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    #[serial]
+    fn test_passphrase_from_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let (env, context) = isolated_context(&temp_dir);
+        let key = "ab".repeat(32);
+        context.kgc.borrow_mut().set_master_key_hash(key.clone());
+        context.db.add_entry(Entry::new(0, "mail".to_string(), encrypt_secret(&key, "v1").ok().unwrap())).unwrap();
+
+        let passphrase_file = temp_dir.path().join("passphrase");
+        fs::write(&passphrase_file, "correct horse\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&passphrase_file, fs::Permissions::from_mode(0o600)).unwrap();
+        }
+        let export_path = temp_dir.path().join("export.json");
+        let export = ExportCmd::new(export_path.clone(), ExportFormat::Json, false)
+            .with_passphrase(SecretSource::File(passphrase_file.clone()));
+        assert!(export.execute(&context).unwrap().encrypted);
+
+        env.set_var("KOFL_HOME", temp_dir.path().join("target").to_str().unwrap());
+        let target = Context::new().unwrap();
+        InitCmd::new().with_master_password(SecretSource::File(passphrase_file.clone())).execute(&target).unwrap();
+        let import = ImportCmd::new(export_path, ImportFormat::Json, Collision::Skip)
+            .with_passphrase(SecretSource::File(passphrase_file.clone()));
+        assert_eq!(import.execute(&target).unwrap().added, 1);
+
+        let csv = ImportCmd::new(temp_dir.path().join("export.csv"), "generic-csv".parse().unwrap(), Collision::Skip)
+            .with_passphrase(SecretSource::File(passphrase_file));
+        assert!(csv.validate(&target).unwrap_err().contains("do not apply"));
+    }
+}
@@ -0,0 +1,234 @@
+use crate::cli::output::{write_lines, Report};
+use crate::cli::StandaloneCommand;
+use crate::db::Db::Database;
+use crate::generator::passphrase::{generate_passphrase, load_wordlist, PassphraseOptions};
+use crate::generator::profile::{list_profiles, load_profile, save_profile, to_spec};
+use crate::generator::{generate, CharClass, GeneratorOptions};
+use crate::settings::Setting;
+use crate::vault::VaultPaths;
+use log::{debug, error, info, warn};
+use serde::Serialize;
+use std::fmt;
+use std::path::PathBuf;
+
+/// Command line overrides applied on top of the selected profile.
+#[derive(Default)]
+pub struct GeneratorOverrides {
+    pub length: Option<usize>,
+    pub charset: Option<Vec<CharClass>>,
+    pub symbols: Option<String>,
+    pub exclude_ambiguous: bool,
+    pub required: Option<Vec<CharClass>>,
+}
+
+impl GeneratorOverrides {
+    pub fn apply(&self, mut options: GeneratorOptions) -> GeneratorOptions {
+        if let Some(length) = self.length {
+            options.length = length;
+        }
+        if let Some(charset) = &self.charset {
+            options.charset = charset.clone();
+        }
+        if let Some(symbols) = &self.symbols {
+            options.symbols = symbols.clone();
+        }
+        if self.exclude_ambiguous {
+            options.exclude_ambiguous = true;
+        }
+        if let Some(required) = &self.required {
+            options.required = Some(required.clone());
+        }
+        options
+    }
+}
+
+pub struct GenerateCmd {
+    action: GenerateAction,
+}
+
+pub enum GenerateAction {
+    Generate {
+        profile: Option<String>,
+        overrides: GeneratorOverrides,
+        count: usize,
+        save_as: Option<String>,
+    },
+    Passphrase {
+        options: PassphraseOptions,
+        /// Overrides the passphrase_wordlist setting.
+        wordlist: Option<PathBuf>,
+        count: usize,
+    },
+    ListProfiles,
+}
+
+impl GenerateCmd {
+    pub fn new(profile: Option<String>, overrides: GeneratorOverrides, count: usize, save_as: Option<String>) -> Self {
+        Self { action: GenerateAction::Generate { profile, overrides, count, save_as } }
+    }
+
+    pub fn new_passphrase(options: PassphraseOptions, wordlist: Option<PathBuf>, count: usize) -> Self {
+        Self { action: GenerateAction::Passphrase { options, wordlist, count } }
+    }
+
+    pub fn new_list_profiles() -> Self {
+        Self { action: GenerateAction::ListProfiles }
+    }
+
+    // the vault is optional, it only provides profiles and the default profile
+    fn open_vault_db() -> Option<Database> {
+        let paths = VaultPaths::resolve();
+        if !paths.data_path.exists() {
+            return None;
+        }
+        Database::new(&paths.data_path).ok()
+    }
+
+    fn default_profile(db: Option<&Database>) -> String {
+        let setting = Setting::DefaultGeneratorProfile;
+        db.and_then(|db| db.get_setting_value(setting.key()).ok().flatten())
+            .unwrap_or_else(|| setting.default_value().to_string())
+    }
+
+    fn resolve_wordlist(db: Option<&Database>, wordlist: &Option<PathBuf>) -> Result<Vec<String>, String> {
+        let path = wordlist.clone().or_else(|| {
+            db.and_then(|db| db.get_setting_value(Setting::PassphraseWordlist.key()).ok().flatten())
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from)
+        });
+        load_wordlist(path.as_deref())
+    }
+
+    fn resolve_options(db: Option<&Database>, profile: &Option<String>, overrides: &GeneratorOverrides) -> Result<GeneratorOptions, String> {
+        let name = profile.clone().unwrap_or_else(|| Self::default_profile(db));
+        let options = overrides.apply(load_profile(db, &name)?);
+        options.check()?;
+        Ok(options)
+    }
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum GenerateOutput {
+    Passwords {
+        passwords: Vec<String>,
+        entropy_bits: f64,
+        /// Profile the options were saved as.
+        saved_profile: Option<String>,
+    },
+    Profiles { profiles: Vec<ProfileValue> },
+}
+
+/// A generator profile, `error` says why it cannot be read.
+#[derive(Serialize)]
+pub struct ProfileValue {
+    pub name: String,
+    pub spec: Option<String>,
+    pub error: Option<String>,
+    pub default: bool,
+}
+
+impl fmt::Display for ProfileValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let marker = if self.default { "*" } else { " " };
+        match (&self.spec, &self.error) {
+            (Some(spec), _) => write!(f, "{} {}: {}", marker, self.name, spec),
+            (None, error) => write!(f, "{} {}: invalid ({})", marker, self.name, error.as_deref().unwrap_or_default()),
+        }
+    }
+}
+
+// only the secrets in text, so they can be piped
+impl fmt::Display for GenerateOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenerateOutput::Passwords { passwords, .. } => write_lines(f, passwords, ""),
+            GenerateOutput::Profiles { profiles } => write_lines(f, profiles, ""),
+        }
+    }
+}
+
+impl Report for GenerateOutput {}
+
+impl StandaloneCommand for GenerateCmd {
+    type Output = GenerateOutput;
+
+    fn validate(&self) -> Result<(), String> {
+        match &self.action {
+            GenerateAction::ListProfiles => Ok(()),
+            GenerateAction::Generate { profile, overrides, count, save_as } => {
+                let db = Self::open_vault_db();
+                if let Err(e) = Self::resolve_options(db.as_ref(), profile, overrides) {
+                    return Err(format!("{} ⛔", e));
+                }
+                if *count == 0 {
+                    return Err("Count must be at least 1 ⛔".to_string());
+                }
+                if save_as.is_some() && db.is_none() {
+                    return Err("Profiles are saved in the vault, run `kofl init` first ⛔".to_string());
+                }
+                Ok(())
+            }
+            GenerateAction::Passphrase { options, wordlist, count } => {
+                let db = Self::open_vault_db();
+                if let Err(e) = options.check().and(Self::resolve_wordlist(db.as_ref(), wordlist).map(|_| ())) {
+                    return Err(format!("{} ⛔", e));
+                }
+                if *count == 0 {
+                    return Err("Count must be at least 1 ⛔".to_string());
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn execute(&self) -> Result<GenerateOutput, String> {
+        let db = Self::open_vault_db();
+        match &self.action {
+            GenerateAction::ListProfiles => {
+                let default = Self::default_profile(db.as_ref());
+                let profiles = list_profiles(db.as_ref())
+                    .into_iter()
+                    .map(|(name, options)| ProfileValue {
+                        default: name == default,
+                        spec: options.as_ref().ok().map(to_spec),
+                        error: options.err(),
+                        name,
+                    })
+                    .collect();
+                Ok(GenerateOutput::Profiles { profiles })
+            }
+            GenerateAction::Generate { profile, overrides, count, save_as } => {
+                let options = Self::resolve_options(db.as_ref(), profile, overrides)?;
+
+                let passwords = (0..*count).map(|_| generate(&options)).collect::<Result<Vec<_>, _>>()?;
+                info!("{} characters, about {:.0} bits of entropy", options.length, options.entropy_bits());
+
+                let mut saved_profile = None;
+                if let (Some(name), Some(db)) = (save_as, &db) {
+                    save_profile(db, name, &options)?;
+                    info!("Saved as profile {}", name);
+                    saved_profile = Some(name.clone());
+                }
+                Ok(GenerateOutput::Passwords { passwords, entropy_bits: options.entropy_bits(), saved_profile })
+            }
+            GenerateAction::Passphrase { options, wordlist, count } => {
+                let words = Self::resolve_wordlist(db.as_ref(), wordlist)?;
+
+                let passwords = (0..*count).map(|_| generate_passphrase(options, &words)).collect::<Result<Vec<_>, _>>()?;
+                info!(
+                    "{} words from a list of {}, about {:.0} bits of entropy",
+                    options.word_count(words.len()),
+                    words.len(),
+                    options.entropy_bits(words.len())
+                );
+                Ok(GenerateOutput::Passwords { passwords, entropy_bits: options.entropy_bits(words.len()), saved_profile: None })
+            }
+        }
+    }
+
+    fn display(&self) {
+        debug!("Generate Command");
+        ()
+    }
+}
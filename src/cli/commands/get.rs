@@ -1,49 +1,119 @@
+use crate::cli::output::Report;
 use crate::cli::Command;
 use crate::errors::{ErrorExecution, ErrorValidation};
 use crate::context::Context;
+use crate::crypto::decrypt_secret;
+use crate::db::Db::Entry;
+use crate::search::{resolve_entry, Resolution};
+use crate::utils::Utils::format_timestamp;
 use log::{debug, error, info, warn};
 use sha2::Digest;
 use crate::validator::core::{CommandType, ValidationResult, ValidationType};
 use crate::validator::registry::ValidationRegistry;
 use arboard::{Clipboard};
 use arboard::Error as ClipboardError;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fmt;
 
 
 
-use aes::cipher::{
-    KeyIvInit, StreamCipher,
-    generic_array::GenericArray,
-};
-use ctr::Ctr32BE;
-type Aes256Ctr = Ctr32BE<aes::Aes256>;
-
-
 pub struct GetCmd {
-    pub ent_name: String
+    pub ent_name: String,
+    pub version: Option<u32>,
+    pub info: bool,
 }
 
 
 impl GetCmd {
-    pub fn new(ent_name: String) -> Self {
-        GetCmd{ent_name}
+    pub fn new(ent_name: String, version: Option<u32>, info: bool) -> Self {
+        GetCmd{ent_name, version, info}
+    }
+
+}
+
+/// The metadata of an entry for `--info`, or what happened to its password.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum GetOutput {
+    Info(EntryInfo),
+    Password {
+        name: String,
+        version: Option<u32>,
+        copied_to_clipboard: bool,
+    },
+}
+
+#[derive(Serialize)]
+pub struct EntryInfo {
+    pub name: String,
+    pub username: Option<String>,
+    pub url: Option<String>,
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub last_accessed_at: Option<DateTime<Utc>>,
+}
+
+// metadata only, the secret is not read so the access time is left alone
+impl From<Entry> for EntryInfo {
+    fn from(entry: Entry) -> Self {
+        EntryInfo {
+            name: entry.ent_name,
+            username: entry.username,
+            url: entry.url,
+            notes: entry.notes,
+            created_at: entry.created_at,
+            updated_at: entry.updated_at,
+            last_accessed_at: entry.last_accessed_at,
+        }
     }
 }
 
+impl fmt::Display for GetOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GetOutput::Info(info) => {
+                writeln!(f, "Entry Name: {}", info.name)?;
+                if let Some(username) = &info.username {
+                    writeln!(f, "Username: {}", username)?;
+                }
+                if let Some(url) = &info.url {
+                    writeln!(f, "URL: {}", url)?;
+                }
+                if let Some(notes) = &info.notes {
+                    writeln!(f, "Notes: {}", notes)?;
+                }
+                writeln!(f, "Created: {}", format_timestamp(&info.created_at))?;
+                writeln!(f, "Updated: {}", format_timestamp(&info.updated_at))?;
+                match &info.last_accessed_at {
+                    Some(accessed) => write!(f, "Last accessed: {}", format_timestamp(accessed)),
+                    None => write!(f, "Last accessed: never"),
+                }
+            }
+            GetOutput::Password { copied_to_clipboard: true, .. } => write!(f, "Password is copied to clipboard"),
+            GetOutput::Password { copied_to_clipboard: false, .. } => Ok(()),
+        }
+    }
+}
+
+impl Report for GetOutput {}
+
 
 impl Command for GetCmd {
+    type Output = GetOutput;
+
+    fn execute(&self, context: &Context) -> Result<GetOutput, String> {
+        // the validator already announced a prefix match
+        let entry = match resolve_entry(&context.db, &self.ent_name) {
+            Ok(Resolution::Exact(entry)) | Ok(Resolution::UniquePrefix(entry)) => entry,
+            Ok(Resolution::NotFound { .. }) => return Err("Entry not found".to_string()),
+            Err(e) => return Err(format!("Error getting entry by name: {:?}", e)),
+        };
 
-    fn execute(&self, context: &Context) -> bool {
-        let entry = context.db.get_entry_by_name(&self.ent_name)
-            .map_err(|e| match e {
-                rusqlite::Error::QueryReturnedNoRows => {
-                    error!("Entry not found");
-                    return false;
-                },
-                _ => {
-                    error!("Error getting entry by name: {:?}", e);
-                    return false;
-                },
-            }).unwrap();
+        if self.info {
+            return Ok(GetOutput::Info(entry.into()));
+        }
 
         // Get master key hash
         let master_key_hash = {
@@ -51,47 +121,28 @@ impl Command for GetCmd {
             kgc.get_hashed_pwd()
         };
 
-        // Decode the master key
-        let master_key_bytes = match  hex::decode(&master_key_hash) {
-            Ok(bytes) => bytes,
-            Err(_) => {
-                error!("Error decoding master key hash");
-                return false;
-            }
-        };
-        // Create key and nonce
-        let key = GenericArray::from_slice(&master_key_bytes);
-        let nonce = GenericArray::from_slice(&[0u8; 16]); // Must match the nonce used in AddCmd
-
-        // Initialize cipher
-        let mut cipher = Aes256Ctr::new(key, nonce);
-
-        // Decode the stored encrypted password
-
-        let mut encrypted_password = match hex::decode(&entry.password_hash) {
-            Ok(pwd) => pwd,
-            Err(_) => {
-                error!("Error decoding password hash");
-                return false;
-            }
+        // an older version comes from the history instead of the entry itself
+        let password_hash = match self.version {
+            None => entry.password_hash,
+            Some(version) => match context.db.get_history_version(entry.id, version) {
+                Ok(old) => old.password_hash,
+                Err(rusqlite::Error::QueryReturnedNoRows) => {
+                    return Err(format!("No version {} for {}, see `kofl history {}`", version, entry.ent_name, entry.ent_name));
+                },
+                Err(e) => return Err(format!("Error getting version {}: {}", version, e)),
+            },
         };
 
-        // Decrypt
-        cipher.apply_keystream(&mut encrypted_password);
-
-
-        let decrypted_password = match String::from_utf8(encrypted_password) {
+        let decrypted_password = match decrypt_secret(&master_key_hash, &password_hash) {
             Ok(pwd) => pwd,
-            Err(_) => {
-                error!("Error decrypting password");
-                return false;
-            }
+            Err(e) => return Err(format!("Error decrypting password: {}", e)),
         };
 
         // println!("Entry Name: {}", entry.ent_name);
         // println!("Password: {}", decrypted_password);
 
-        let mut clipboard = Clipboard::new().unwrap();
+        // without a display there is no clipboard to copy to
+        let copied = Clipboard::new().and_then(|mut clipboard| clipboard.set_text(decrypted_password));
        
 
         // let the_string = "Hello, world!";
@@ -105,16 +156,19 @@ impl Command for GetCmd {
         //     Err(_) => error!("undefined behaviour"),
         // }
 
-        match clipboard.set_text(decrypted_password) {
-            Ok(_) => info!("Password is copied to clipboard"),
-            Err(e) => error!("Error copying to clipboard: {}", e),
+        if let Err(e) = &copied {
+            error!("Error copying to clipboard: {}", e);
+        }
+
+        if let Err(e) = context.db.touch_entry(entry.id) {
+            warn!("Could not record the access time: {}", e);
         }
         
         // println!("Clipboard text was: {}", clipboard.get_text().unwrap());
-        true
+        Ok(GetOutput::Password { name: entry.ent_name, version: self.version, copied_to_clipboard: copied.is_ok() })
     }   
 
-    fn validate(&self, context: &Context) -> bool  {
+    fn validate(&self, context: &Context) -> Result<(), String>  {
 
         let val_reg = ValidationRegistry::<GetCmd>::new();
 
@@ -128,17 +182,14 @@ impl Command for GetCmd {
         for a_check in val_checks {
 
             match val_reg.validators.get(&a_check).unwrap().validate(context, &self) {
-                ValidationResult::Failure(msg) => {
-                    error!("{msg}");
-                    return false
-                },
+                ValidationResult::Failure(msg) => return Err(msg),
                 ValidationResult::Warning(msg) => warn!("{msg}"),
                 ValidationResult::Success => debug!("test passed ✅")
 
             }
         }
         
-        true
+        Ok(())
     }
 
     fn display(&self) {
@@ -0,0 +1,104 @@
+use crate::cli::output::{write_lines, Report};
+use crate::cli::Command;
+use crate::context::Context;
+use crate::utils::Utils::format_timestamp;
+use crate::validator::core::{ValidationResult, ValidationType};
+use crate::validator::registry::ValidationRegistry;
+use chrono::{DateTime, Utc};
+use log::{debug, error, info, warn};
+use serde::Serialize;
+use std::fmt;
+
+pub struct HistoryCmd {
+    pub ent_name: String,
+}
+
+impl HistoryCmd {
+    pub fn new(ent_name: String) -> Self {
+        HistoryCmd { ent_name }
+    }
+}
+
+/// Secrets are never part of it, use `kofl get <name> --version N`.
+#[derive(Serialize)]
+pub struct HistoryOutput {
+    pub name: String,
+    /// From the oldest, the last one is the current password.
+    pub versions: Vec<Version>,
+}
+
+#[derive(Serialize)]
+pub struct Version {
+    pub version: u32,
+    pub set_at: DateTime<Utc>,
+    /// `None` for the current password.
+    pub replaced_at: Option<DateTime<Utc>>,
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.replaced_at {
+            Some(replaced_at) => write!(f, "version {} set {} replaced {}", self.version, format_timestamp(&self.set_at), format_timestamp(replaced_at)),
+            None => write!(f, "version {} set {} (current)", self.version, format_timestamp(&self.set_at)),
+        }
+    }
+}
+
+impl fmt::Display for HistoryOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.versions.len() < 2 {
+            return write!(f, "{} has no previous version", self.name);
+        }
+        write_lines(f, &self.versions, "")
+    }
+}
+
+impl Report for HistoryOutput {}
+
+impl Command for HistoryCmd {
+    type Output = HistoryOutput;
+
+    fn execute(&self, context: &Context) -> Result<HistoryOutput, String> {
+        let entry = match context.db.get_entry_by_name(&self.ent_name) {
+            Ok(entry) => entry,
+            Err(e) => return Err(format!("Error retrieving entry by name: {}", e)),
+        };
+
+        let history = match context.db.list_history(entry.id) {
+            Ok(history) => history,
+            Err(e) => return Err(format!("Error retrieving history: {}", e)),
+        };
+
+        let current = history.last().map(|old| old.version + 1).unwrap_or(1);
+        let mut versions: Vec<Version> = history
+            .into_iter()
+            .map(|old| Version { version: old.version, set_at: old.set_at, replaced_at: Some(old.replaced_at) })
+            .collect();
+        versions.push(Version { version: current, set_at: entry.updated_at, replaced_at: None });
+        Ok(HistoryOutput { name: entry.ent_name, versions })
+    }
+
+    fn validate(&self, context: &Context) -> Result<(), String> {
+        let val_reg = ValidationRegistry::<HistoryCmd>::new();
+
+        let val_checks = vec![
+            ValidationType::MasterKeyCheck,
+            ValidationType::SessionCheck,
+            ValidationType::EntryExistsCheck,
+        ];
+
+        for a_check in val_checks {
+            match val_reg.validators.get(&a_check).unwrap().validate(context, &self) {
+                ValidationResult::Failure(msg) => return Err(msg),
+                ValidationResult::Warning(msg) => warn!("{msg}"),
+                ValidationResult::Success => debug!("test passed ✅")
+            }
+        }
+        Ok(())
+    }
+
+    fn display(&self) {
+        debug!("History command with entry name = {}", self.ent_name);
+        ()
+    }
+}
@@ -0,0 +1,170 @@
+use crate::backup::Backup;
+use crate::cli::output::Report;
+use crate::cli::Command;
+use crate::cli::secret::SecretSource;
+use crate::context::Context;
+use crate::export::json::read_json;
+use crate::export::pass::read_store;
+use crate::import::csv::parse_csv;
+use crate::import::kdbx::read_kdbx;
+use crate::import::{import_entries, Collision, ImportFormat, ImportedEntry};
+use crate::validator::core::{ValidationResult, ValidationType};
+use crate::validator::registry::ValidationRegistry;
+use log::{debug, error, info, warn};
+use serde::Serialize;
+use std::fmt;
+use std::fs::File;
+use std::path::PathBuf;
+
+pub struct ImportCmd {
+    pub path: PathBuf,
+    pub format: ImportFormat,
+    pub collision: Collision,
+    pub key_file: Option<PathBuf>,
+    /// Passphrase of an export, or password of a KeePass database.
+    passphrase: SecretSource,
+}
+
+impl ImportCmd {
+    pub fn new(path: PathBuf, format: ImportFormat, collision: Collision) -> Self {
+        ImportCmd { path, format, collision, key_file: None, passphrase: SecretSource::Prompt }
+    }
+
+    pub fn with_passphrase(mut self, passphrase: SecretSource) -> Self {
+        self.passphrase = passphrase;
+        self
+    }
+
+    pub fn with_key_file(mut self, key_file: Option<PathBuf>) -> Self {
+        self.key_file = key_file;
+        self
+    }
+
+    fn read_kdbx(&self) -> Result<Vec<Result<ImportedEntry, String>>, String> {
+        let read = |path: &PathBuf| std::fs::read(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e));
+        let data = read(&self.path)?;
+        let key_file = self.key_file.as_ref().map(read).transpose()?;
+        let password = self.passphrase.read("Enter the password of the KeePass database ===> ")?;
+        read_kdbx(&data, &password, key_file.as_deref())
+    }
+
+    fn read_json(&self) -> Result<Vec<Result<ImportedEntry, String>>, String> {
+        let data = std::fs::read(&self.path).map_err(|e| format!("Cannot read {}: {}", self.path.display(), e))?;
+        let passphrase = || self.passphrase.read("Enter the passphrase of the export ===> ");
+        let entries = read_json(&data, passphrase)?;
+        Ok(entries.into_iter().map(|entry| Ok(entry.into())).collect())
+    }
+}
+
+#[derive(Serialize)]
+pub struct ImportOutput {
+    pub added: usize,
+    pub overwritten: usize,
+    pub renamed: Vec<Renamed>,
+    pub skipped: Vec<String>,
+    /// Why the rows that could not be read, or parts of imported ones, were left out.
+    pub rejected: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct Renamed {
+    pub from: String,
+    pub to: String,
+}
+
+impl fmt::Display for ImportOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for reason in &self.rejected {
+            writeln!(f, "Not imported, {}", reason)?;
+        }
+        for renamed in &self.renamed {
+            writeln!(f, "{} imported as {}", renamed.from, renamed.to)?;
+        }
+        for name in &self.skipped {
+            writeln!(f, "{} already exists, skipped", name)?;
+        }
+        write!(
+            f,
+            "Import done: {} added, {} overwritten, {} renamed, {} skipped",
+            self.added,
+            self.overwritten,
+            self.renamed.len(),
+            self.skipped.len()
+        )
+    }
+}
+
+impl Report for ImportOutput {}
+
+impl Command for ImportCmd {
+    type Output = ImportOutput;
+
+    fn execute(&self, context: &Context) -> Result<ImportOutput, String> {
+        let rows = match self.format {
+            ImportFormat::Csv(format) => File::open(&self.path)
+                .map_err(|e| format!("Cannot open {}: {}", self.path.display(), e))
+                .and_then(|file| parse_csv(format, file)),
+            ImportFormat::Kdbx => self.read_kdbx(),
+            ImportFormat::Json => self.read_json(),
+            ImportFormat::Pass => self
+                .passphrase
+                .read("Enter the passphrase of the password store ===> ")
+                .and_then(|passphrase| read_store(&self.path, &passphrase)),
+        }?;
+
+        let (entries, rejected): (Vec<_>, Vec<_>) = rows.into_iter().partition(Result::is_ok);
+        let entries = entries.into_iter().map(Result::unwrap).collect();
+        let rejected: Vec<String> = rejected.into_iter().map(Result::unwrap_err).collect();
+
+        // the vault as it was before, in case the import is not what was expected
+        let backup = Backup::new().and_then(|bc| {
+            bc.create_new_backup(
+                &context.kgc.borrow().get_config_path(),
+                &context.kgc.borrow().get_data_storage_path(),
+                &context.kgc.borrow().get_config_path().with_extension("checksum"),
+            )
+        });
+        if let Err(e) = backup {
+            return Err(format!("Cannot back the vault up before the import: {}", e));
+        }
+
+        let summary = match import_entries(context, entries, self.collision) {
+            Ok(summary) => summary,
+            Err(e) => return Err(format!("Nothing was imported: {}", e)),
+        };
+
+        Ok(ImportOutput {
+            added: summary.added,
+            overwritten: summary.overwritten,
+            renamed: summary.renamed.into_iter().map(|(from, to)| Renamed { from, to }).collect(),
+            skipped: summary.skipped,
+            rejected: rejected.into_iter().chain(summary.cleared_policies).collect(),
+        })
+    }
+
+    fn validate(&self, context: &Context) -> Result<(), String> {
+        if matches!(self.format, ImportFormat::Csv(_)) && !self.passphrase.is_prompt() {
+            return Err("CSV files are not encrypted, --passphrase-stdin and --passphrase-file do not apply".to_string());
+        }
+        let val_reg = ValidationRegistry::<ImportCmd>::new();
+
+        let val_checks = vec![
+            ValidationType::MasterKeyCheck,
+            ValidationType::SessionCheck,
+        ];
+
+        for a_check in val_checks {
+            match val_reg.validators.get(&a_check).unwrap().validate(context, &self) {
+                ValidationResult::Failure(msg) => return Err(msg),
+                ValidationResult::Warning(msg) => warn!("{msg}"),
+                ValidationResult::Success => debug!("test passed ✅")
+            }
+        }
+        Ok(())
+    }
+
+    fn display(&self) {
+        debug!("Import command from {}", self.path.display());
+        ()
+    }
+}
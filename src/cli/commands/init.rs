@@ -1,4 +1,6 @@
+use crate::cli::output::Report;
 use crate::cli::Command;
+use crate::cli::secret::SecretSource;
 use crate::errors::{ErrorExecution, ErrorValidation};
 use crate::context::Context;
 use crate::session::Session;
@@ -8,6 +10,9 @@ use rand::{thread_rng, Rng};
 use rand::distributions::Alphanumeric;
 use sha2::{Sha256, Digest};
 use log::{debug, error, info, warn};
+use serde::Serialize;
+use std::fmt;
+use std::path::PathBuf;
 
 
 use aes::cipher::KeyIvInit;
@@ -16,28 +21,60 @@ type Aes256Ctr = Ctr32BE<aes::Aes256>;
 
 
 pub struct InitCmd {
-    // for now is emty 
+    master_password: SecretSource,
 }
 
 impl InitCmd {
     pub fn new() -> Self {
-        InitCmd{}
+        InitCmd { master_password: SecretSource::master_password(false, &None) }
+    }
+
+    pub fn with_master_password(mut self, master_password: SecretSource) -> Self {
+        self.master_password = master_password;
+        self
     }
 
 }
 
+#[derive(Serialize)]
+pub struct InitOutput {
+    pub config_path: PathBuf,
+    pub data_path: PathBuf,
+}
+
+impl fmt::Display for InitOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Master password set successfully!")?;
+        writeln!(f, "Kofl is now ready to use.")?;
+        writeln!(f)?;
+        writeln!(f, "Default settings have been applied:")?;
+        writeln!(f, "- Session duration: 30 minutes")?;
+        writeln!(f, "- Clipboard timeout: 10 seconds")?;
+        writeln!(f)?;
+        writeln!(f, "To customize these settings, run:")?;
+        writeln!(f, "  kofl settings list   # view all settings")?;
+        write!(f, "  kofl settings set    # change a setting")
+    }
+}
+
+impl Report for InitOutput {}
+
 impl Command for InitCmd {
-        fn execute(&self, context: &Context) -> bool  {
+        type Output = InitOutput;
+
+        fn execute(&self, context: &Context) -> Result<InitOutput, String> {
 
             // let's create a function that will promot the user to set crutial settings    
 
 
-            let master_pwd  = rpassword::prompt_password("type a master password ==> ").unwrap();
-            let master_pwd_confirmed = rpassword::prompt_password("type the master password again ==> ").unwrap();
+            let master_pwd = self.master_password.read("type a master password ==> ")?;
 
-            if master_pwd != master_pwd_confirmed {
-                error!("Password mismatch");
-                return false;
+            // a password read from a file or stdin was not mistyped
+            if self.master_password.is_prompt() {
+                let master_pwd_confirmed = rpassword::prompt_password("type the master password again ==> ").unwrap();
+                if master_pwd != master_pwd_confirmed {
+                    return Err("Password mismatch".to_string());
+                }
             }
 
             let salt:String = thread_rng()
@@ -63,6 +100,8 @@ impl Command for InitCmd {
                 kgc.set_salt(salt.clone());
                 kgc.set_master_key_hash(hashed_password_hex); // Assuming you have a method to set the hashed password
                 kgc.set_master_key_provided(true); // Assuming you have a method to set this flag
+                let mac_key = kgc.derive_mac_key(&master_pwd);
+                kgc.set_mac_key(mac_key);
             }
 
             context.kgc.borrow().update();
@@ -74,20 +113,11 @@ impl Command for InitCmd {
         
             new_session.write_session_config_to_toml_file();
 
-            info!("Master password set successfully!");
-            info!("Kofl is now ready to use.");
-
-            info!("Default settings have been applied:");
-            info!("- Session duration: 30 minutes");
-            info!("- Clipboard timeout: 10 seconds");
-            info!("");
-            info!("{}", "To customize these settings, run:");
-            info!("  kofl settings list   # view all settings");
-            info!("  kofl settings set    # change a setting");
-            true
+            let kgc = context.kgc.borrow();
+            Ok(InitOutput { config_path: kgc.get_config_path().clone(), data_path: kgc.get_data_storage_path().clone() })
         }
 
-    fn validate(&self, context: &Context) -> bool  {
+    fn validate(&self, context: &Context) -> Result<(), String>  {
 
         let val_reg = ValidationRegistry::<InitCmd>::new();
 
@@ -99,17 +129,14 @@ impl Command for InitCmd {
         for a_check in val_checks {
 
             match val_reg.validators.get(&a_check).unwrap().validate(context, &self) {
-                ValidationResult::Failure(msg) => {
-                    error!("{msg}");
-                    return false;
-                },
+                ValidationResult::Failure(msg) => return Err(msg),
                 ValidationResult::Warning(msg) => warn!("{msg}"),
                 ValidationResult::Success => debug!("test passed ✅")
 
             }
         }
         
-        true
+        Ok(())
 
     }
 
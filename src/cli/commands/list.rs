@@ -0,0 +1,150 @@
+use crate::cli::output::{write_lines, Report};
+use crate::cli::Command;
+use crate::context::Context;
+use crate::db::Db::Entry;
+use crate::expiry::stale_entries;
+use crate::utils::Utils::format_timestamp;
+use crate::validator::core::{ValidationResult, ValidationType};
+use crate::validator::registry::ValidationRegistry;
+use chrono::{DateTime, Utc};
+use log::{debug, error, info, warn};
+use serde::Serialize;
+use std::fmt;
+
+pub struct ListCmd {
+    pub stale: bool,
+    pub tag: Option<String>,
+    pub folder: Option<String>,
+}
+
+impl ListCmd {
+    pub fn new(stale: bool, tag: Option<String>, folder: Option<String>) -> Self {
+        ListCmd { stale, tag, folder }
+    }
+
+    fn matching_entries(&self, context: &Context) -> Result<Vec<Entry>, rusqlite::Error> {
+        let mut entries = match (&self.folder, &self.tag) {
+            (Some(folder), _) => context.db.list_entries_in_folder(folder)?,
+            (None, Some(tag)) => context.db.list_entries_with_tag(tag)?,
+            (None, None) => context.db.list_entries()?,
+        };
+
+        if let (Some(_), Some(tag)) = (&self.folder, &self.tag) {
+            let mut tagged = Vec::new();
+            for entry in entries {
+                if context.db.list_tags(entry.id)?.contains(tag) {
+                    tagged.push(entry);
+                }
+            }
+            entries = tagged;
+        }
+
+        if self.stale {
+            let stale: Vec<u32> = stale_entries(context)?.iter().map(|entry| entry.id).collect();
+            entries.retain(|entry| stale.contains(&entry.id));
+        }
+        Ok(entries)
+    }
+}
+
+#[derive(Serialize)]
+pub struct ListOutput {
+    pub entries: Vec<ListedEntry>,
+    /// Shown instead of the entries when there is none.
+    #[serde(skip)]
+    empty_message: &'static str,
+}
+
+#[derive(Serialize)]
+pub struct ListedEntry {
+    pub name: String,
+    pub tags: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub last_accessed_at: Option<DateTime<Utc>>,
+    /// `None` when the entry never expires.
+    pub rotate_by: Option<DateTime<Utc>>,
+}
+
+impl fmt::Display for ListedEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let never = || "never".to_string();
+        write!(f, "{}", self.name)?;
+        if !self.tags.is_empty() {
+            write!(f, "  [{}]", self.tags.join(", "))?;
+        }
+        write!(
+            f,
+            "  created {}  updated {}  accessed {}  rotate by {}",
+            format_timestamp(&self.created_at),
+            format_timestamp(&self.updated_at),
+            self.last_accessed_at.as_ref().map(format_timestamp).unwrap_or_else(never),
+            self.rotate_by.as_ref().map(format_timestamp).unwrap_or_else(never)
+        )
+    }
+}
+
+impl fmt::Display for ListOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_lines(f, &self.entries, self.empty_message)
+    }
+}
+
+impl Report for ListOutput {}
+
+impl Command for ListCmd {
+    type Output = ListOutput;
+
+    fn execute(&self, context: &Context) -> Result<ListOutput, String> {
+        let entries = match self.matching_entries(context) {
+            Ok(entries) => entries,
+            Err(e) => return Err(format!("Error listing entries: {}", e)),
+        };
+
+        let empty_message = if self.stale {
+            "No entry is due for rotation"
+        } else if self.tag.is_some() || self.folder.is_some() {
+            "No matching entry"
+        } else {
+            "No entry yet, add one with `kofl add <name>`"
+        };
+
+        let default_max_age = context.settings.borrow_mut().get_default_max_age(context);
+
+        let entries = entries
+            .into_iter()
+            .map(|entry| ListedEntry {
+                rotate_by: entry.due_at(default_max_age),
+                tags: context.db.list_tags(entry.id).unwrap_or_default(),
+                name: entry.ent_name,
+                created_at: entry.created_at,
+                updated_at: entry.updated_at,
+                last_accessed_at: entry.last_accessed_at,
+            })
+            .collect();
+        Ok(ListOutput { entries, empty_message })
+    }
+
+    fn validate(&self, context: &Context) -> Result<(), String> {
+        let val_reg = ValidationRegistry::<ListCmd>::new();
+
+        let val_checks = vec![
+            ValidationType::MasterKeyCheck,
+            ValidationType::SessionCheck,
+        ];
+
+        for a_check in val_checks {
+            match val_reg.validators.get(&a_check).unwrap().validate(context, &self) {
+                ValidationResult::Failure(msg) => return Err(msg),
+                ValidationResult::Warning(msg) => warn!("{msg}"),
+                ValidationResult::Success => debug!("test passed ✅")
+            }
+        }
+        Ok(())
+    }
+
+    fn display(&self) {
+        debug!("List command");
+        ()
+    }
+}
@@ -1,10 +1,15 @@
+use crate::cli::output::Report;
 use crate::cli::Command;
-use crate::errors::{ErrorExecution, ErrorValidation};
+use crate::cli::secret::SecretSource;
+use crate::errors::{ErrorExecution, ErrorIntegrity, ErrorValidation};
 use crate::context::Context;
 use crate::session::Session;
 use crate::validator::core::{ValidationResult, ValidationType};
 use crate::validator::registry::ValidationRegistry;
+use chrono::{DateTime, Utc};
 use log::{debug, error, info, warn};
+use serde::Serialize;
+use std::fmt;
 use rand::{thread_rng, Rng};
 use rand::distributions::Alphanumeric;
 use sha2::{Sha256, Digest};
@@ -16,21 +21,40 @@ type Aes256Ctr = Ctr32BE<aes::Aes256>;
 
 
 pub struct LogInCmd {
-    // for now is emty 
+    master_password: SecretSource,
 }
 
 impl LogInCmd {
     pub fn new() -> Self {
-        LogInCmd{}
-    }   
+        LogInCmd { master_password: SecretSource::master_password(false, &None) }
+    }
+
+    pub fn with_master_password(mut self, master_password: SecretSource) -> Self {
+        self.master_password = master_password;
+        self
+    }
 
 }
 
-impl Command for LogInCmd {
+#[derive(Serialize)]
+pub struct LoginOutput {
+    pub user: String,
+    pub session_expires_at: DateTime<Utc>,
+}
+
+impl fmt::Display for LoginOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Login successful! New session created.")
+    }
+}
+
+impl Report for LoginOutput {}
 
+impl Command for LogInCmd {
+    type Output = LoginOutput;
 
-    fn execute(&self, context: &Context) -> bool {
-        let master_pwd_input = rpassword::prompt_password("Enter the master password ===> ").unwrap();
+    fn execute(&self, context: &Context) -> Result<LoginOutput, String> {
+        let master_pwd_input = self.master_password.read("Enter the master password ===> ")?;
 
         let salt = context.kgc.borrow().get_salt();
         let stored_hash = context.kgc.borrow().get_hashed_pwd();
@@ -46,25 +70,34 @@ impl Command for LogInCmd {
     
         // 4. Compare the computed hash with the stored hash.
         if computed_hash_hex != stored_hash {
-            error!("Invalid password");
-            return false;
+            return Err("Invalid password".to_string());
         }
-    
-        
+
+        // the config can only be authenticated now that we know the master password
+        {
+            let mut kgc = context.kgc.borrow_mut();
+            let mac_key = kgc.derive_mac_key(&master_pwd_input);
+            match kgc.verify_mac(&mac_key) {
+                Ok(()) => (),
+                // the legacy checksum matched the config and the master password is right, upgrade it once
+                Err(ErrorIntegrity::Unkeyed) => info!("Sealing the config with the master password instead of its legacy checksum."),
+                Err(e) => return Err(format!("{} Refusing to open a vault whose config cannot be authenticated.", e)),
+            }
+            kgc.set_mac_key(mac_key);
+            kgc.seal();
+        }
+
 
         let user_login = context.kgc.borrow().get_user_login().clone();
-        let new_session = Session::new(user_login, true);
+        let new_session = Session::new(user_login.clone(), true);
         
         new_session.write_session_config_to_toml_file();
     
-    
-        info!("Login successful! New session created.");
-    
-        true
+        Ok(LoginOutput { user: user_login, session_expires_at: new_session.get_expires_at() })
     }
     
 
-    fn validate(&self, context: &Context) -> bool  {
+    fn validate(&self, context: &Context) -> Result<(), String>  {
         let val_reg = ValidationRegistry::<LogInCmd>::new();
 
         let val_checks = vec![
@@ -76,17 +109,14 @@ impl Command for LogInCmd {
         for a_check in val_checks {
 
             match val_reg.validators.get(&a_check).unwrap().validate(context, &self) {
-                ValidationResult::Failure(msg) => {
-                    error!("{msg}");
-                    return false;
-                },
+                ValidationResult::Failure(msg) => return Err(msg),
                 ValidationResult::Warning(msg) => warn!("{msg}"),
                 ValidationResult::Success => debug!("test passed ✅")
 
             }
         }
         
-        true
+        Ok(())
     }
 
     fn display(&self) {
@@ -95,3 +125,60 @@ impl Command for LogInCmd {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config::KoflGlobalConfig;
+    use crate::test_support::IsolatedEnv;
+    use crate::vault::VaultPaths;
+    use serial_test::serial;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use tempfile::TempDir;
+
+    fn password_file(dir: &Path, name: &str, password: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, format!("{}\n", password)).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        }
+        path
+    }
+
+    #[test]
+    #[serial]
+    fn test_legacy_vault_is_sealed_at_login() {
+        let temp_dir = TempDir::new().unwrap();
+        let _env = IsolatedEnv::new(temp_dir.path());
+        let home = temp_dir.path();
+
+        // a vault left in the home directory by a version without the keyed checksum
+        let mut legacy = KoflGlobalConfig::new();
+        legacy.set_config_path(home.join(".kofl"));
+        legacy.set_data_storage_path(home.join("kofl.sqlite"));
+        legacy.set_salt("legacy_salt".to_string());
+        legacy.set_master_key_hash(hex::encode(Sha256::digest(b"Legacy-Passw0rd!legacy_salt")));
+        legacy.set_master_key_provided(true);
+        legacy.write_config_to_toml_file();
+        fs::write(home.join(".kofl.checksum"), legacy.get_config_checksum()).unwrap();
+
+        let context = Context::new().unwrap();
+        let checksum_path = VaultPaths::resolve().config_path.with_extension("checksum");
+        assert!(!home.join(".kofl").exists());
+        assert_eq!(context.kgc.borrow().verify_integrity(), Err(ErrorIntegrity::Unkeyed));
+        assert_eq!(context.kgc.borrow().verify_on_load(), Ok(()));
+
+        // a wrong password leaves the legacy checksum alone
+        let login = LogInCmd::new().with_master_password(SecretSource::File(password_file(home, "wrong", "Wrong-Passw0rd!")));
+        assert!(login.execute(&context).is_err());
+        assert!(!fs::read_to_string(&checksum_path).unwrap().starts_with("hmac-sha256:"));
+
+        let login = LogInCmd::new().with_master_password(SecretSource::File(password_file(home, "master", "Legacy-Passw0rd!")));
+        login.execute(&context).unwrap();
+        assert!(fs::read_to_string(&checksum_path).unwrap().starts_with("hmac-sha256:"));
+        let kgc = context.kgc.borrow();
+        assert_eq!(kgc.verify_mac(&kgc.derive_mac_key("Legacy-Passw0rd!")), Ok(()));
+    }
+}
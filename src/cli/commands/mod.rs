@@ -5,10 +5,34 @@ mod login;
 mod destroy;
 mod update;
 mod settings;
+mod restore;
+mod doctor;
+mod vault;
+mod history;
+mod rollback;
+mod list;
+mod audit;
+mod tag;
+mod search;
+mod generate;
+mod import;
+mod export;
 pub use add::AddCmd;
 pub use get::GetCmd;
 pub use init::InitCmd;
 pub use login::LogInCmd;
 pub use destroy::DestroyCmd;
 pub use update::UpdateCmd;
-pub use settings::SettingsCmd;
\ No newline at end of file
+pub use settings::SettingsCmd;
+pub use restore::RestoreCmd;
+pub use doctor::DoctorCmd;
+pub use vault::VaultCmd;
+pub use history::HistoryCmd;
+pub use rollback::RollbackCmd;
+pub use list::ListCmd;
+pub use audit::AuditCmd;
+pub use tag::TagCmd;
+pub use search::SearchCmd;
+pub use generate::{GenerateCmd, GeneratorOverrides};
+pub use import::ImportCmd;
+pub use export::ExportCmd;
@@ -0,0 +1,167 @@
+use crate::backup::Backup;
+use crate::cli::output::Report;
+use crate::cli::Command;
+use crate::cli::secret::SecretSource;
+use crate::context::Context;
+use log::{debug, error, info, warn};
+use serde::Serialize;
+use std::fmt;
+use std::path::PathBuf;
+
+pub struct RestoreCmd {
+    pub backup_path: Option<PathBuf>,
+    pub with_data: bool,
+    pub reseal: bool,
+    master_password: SecretSource,
+}
+
+impl RestoreCmd {
+    pub fn new(backup_path: Option<PathBuf>, with_data: bool, reseal: bool) -> Self {
+        RestoreCmd { backup_path, with_data, reseal, master_password: SecretSource::master_password(false, &None) }
+    }
+
+    pub fn with_master_password(mut self, master_password: SecretSource) -> Self {
+        self.master_password = master_password;
+        self
+    }
+
+    // the user vouches for the current config, authenticate it again
+    fn reseal_current_config(&self, context: &Context) -> Result<RestoreOutput, String> {
+        let master_pwd_input = self.master_password.read("Enter the master password ===> ")?;
+
+        let mut kgc = context.kgc.borrow_mut();
+
+        if !kgc.verify_master_password(&master_pwd_input) {
+            return Err("Invalid password".to_string());
+        }
+
+        let mac_key = kgc.derive_mac_key(&master_pwd_input);
+        kgc.set_mac_key(mac_key);
+        if !kgc.seal() {
+            return Err("The config could not be sealed".to_string());
+        }
+
+        Ok(RestoreOutput { resealed: true, restored_from: None, quarantine: None, with_data: false })
+    }
+}
+
+#[derive(Serialize)]
+pub struct RestoreOutput {
+    /// The current config was trusted again instead of restoring a backup.
+    pub resealed: bool,
+    pub restored_from: Option<PathBuf>,
+    /// Where the replaced files were kept.
+    pub quarantine: Option<PathBuf>,
+    pub with_data: bool,
+}
+
+impl fmt::Display for RestoreOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let (Some(restored_from), Some(quarantine)) = (&self.restored_from, &self.quarantine) {
+            writeln!(f, "Restored from {}", restored_from.display())?;
+            return write!(f, "Previous files were kept in {}", quarantine.display());
+        }
+        write!(f, "The current config is trusted again.")
+    }
+}
+
+impl Report for RestoreOutput {}
+
+impl Command for RestoreCmd {
+    type Output = RestoreOutput;
+
+    fn execute(&self, context: &Context) -> Result<RestoreOutput, String> {
+        if self.reseal {
+            return self.reseal_current_config(context);
+        }
+
+        let bc = match Backup::new() {
+            Ok(bc) => bc,
+            Err(e) => return Err(format!("Error accessing backups: {}", e)),
+        };
+
+        let backup_path = match &self.backup_path {
+            Some(path) => path.clone(),
+            None => match bc.get_last_backup() {
+                Ok(Some(path)) => path,
+                Ok(None) => return Err("No backup found, use `kofl restore --reseal` to trust the current config.".to_string()),
+                Err(e) => return Err(format!("Error reading backups: {}", e)),
+            },
+        };
+
+        let kgc = context.kgc.borrow();
+        let config_path = kgc.get_config_path();
+        let checksum_path = config_path.with_extension("checksum");
+
+        if self.with_data {
+            warn!("The database will be replaced, entries added after this backup are only kept in quarantine.");
+        }
+
+        match bc.restore_backup(&backup_path, config_path, kgc.get_data_storage_path(), &checksum_path, self.with_data) {
+            Ok(quarantine_dir) => Ok(RestoreOutput {
+                resealed: false,
+                restored_from: Some(backup_path),
+                quarantine: Some(quarantine_dir),
+                with_data: self.with_data,
+            }),
+            Err(e) => Err(format!("Failed to restore backup: {}", e)),
+        }
+    }
+
+    fn validate(&self, _context: &Context) -> Result<(), String> {
+        if let Some(path) = &self.backup_path {
+            if !path.join(".kofl").exists() || !path.join(".kofl.checksum").exists() {
+                return Err(format!("{} does not look like a kofl backup ⛔", path.display()));
+            }
+        }
+        Ok(())
+    }
+
+    fn display(&self) {
+        debug!("Restore Command");
+        ()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::commands::InitCmd;
+    use crate::errors::ErrorIntegrity;
+    use crate::test_support::isolated_context;
+    use serial_test::serial;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn password_file(temp_dir: &TempDir, password: &str) -> SecretSource {
+        let path = temp_dir.path().join(format!("master-{}", password.len()));
+        fs::write(&path, password).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        }
+        SecretSource::File(path)
+    }
+
+    #[test]
+    #[serial]
+    fn test_reseal_reads_the_given_password() {
+        let temp_dir = TempDir::new().unwrap();
+        let (_env, context) = isolated_context(&temp_dir);
+        InitCmd::new().with_master_password(password_file(&temp_dir, "Master-Passw0rd!x")).execute(&context).unwrap();
+
+        let config_path = context.kgc.borrow().get_config_path().clone();
+        let edited = fs::read_to_string(&config_path).unwrap().replace("master_key_provided", "\nmaster_key_provided");
+        fs::write(&config_path, edited).unwrap();
+        let mac_key = context.kgc.borrow().derive_mac_key("Master-Passw0rd!x");
+        assert_eq!(context.kgc.borrow().verify_mac(&mac_key), Err(ErrorIntegrity::Tampered));
+
+        let wrong = RestoreCmd::new(None, false, true).with_master_password(password_file(&temp_dir, "Wrong"));
+        assert_eq!(wrong.execute(&context).err().as_deref(), Some("Invalid password"));
+
+        let reseal = RestoreCmd::new(None, false, true).with_master_password(password_file(&temp_dir, "Master-Passw0rd!x"));
+        assert!(reseal.execute(&context).unwrap().resealed);
+        assert_eq!(context.kgc.borrow().verify_mac(&mac_key), Ok(()));
+    }
+}
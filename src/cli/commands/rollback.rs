@@ -0,0 +1,116 @@
+use crate::backup::Backup;
+use crate::cli::output::Report;
+use crate::cli::Command;
+use crate::context::Context;
+use crate::crypto::{decrypt_secret, secret_mac};
+use crate::db::Db::Entry;
+use crate::validator::core::{ValidationResult, ValidationType};
+use crate::validator::registry::ValidationRegistry;
+use chrono::prelude::*;
+use log::{debug, error, info, warn};
+use serde::Serialize;
+use std::fmt;
+
+pub struct RollbackCmd {
+    pub ent_name: String,
+    pub version: u32,
+}
+
+impl RollbackCmd {
+    pub fn new(ent_name: String, version: u32) -> Self {
+        RollbackCmd { ent_name, version }
+    }
+}
+
+#[derive(Serialize)]
+pub struct RollbackOutput {
+    pub name: String,
+    /// The version restored, the replaced password is kept as a new version.
+    pub version: u32,
+}
+
+impl fmt::Display for RollbackOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} rolled back to version {}", self.name, self.version)
+    }
+}
+
+impl Report for RollbackOutput {}
+
+impl Command for RollbackCmd {
+    type Output = RollbackOutput;
+
+    fn execute(&self, context: &Context) -> Result<RollbackOutput, String> {
+        let current = match context.db.get_entry_by_name(&self.ent_name) {
+            Ok(entry) => entry,
+            Err(e) => return Err(format!("Error retrieving entry by name: {}", e)),
+        };
+
+        let old = match context.db.get_history_version(current.id, self.version) {
+            Ok(old) => old,
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                return Err(format!("No version {} for {}, see `kofl history {}`", self.version, self.ent_name, self.ent_name));
+            }
+            Err(e) => return Err(format!("Error getting version {}: {}", self.version, e)),
+        };
+
+        // the keyed hash follows the restored secret
+        let master_key_hash = context.kgc.borrow().get_hashed_pwd();
+        let restored_mac = match decrypt_secret(&master_key_hash, &old.password_hash)
+            .and_then(|secret| secret_mac(&master_key_hash, &secret))
+        {
+            Ok(mac) => mac,
+            Err(e) => return Err(format!("Error restoring version {}: {}", self.version, e)),
+        };
+
+        let restored_entry = Entry {
+            password_hash: old.password_hash,
+            secret_mac: Some(restored_mac),
+            updated_at: Utc::now(),
+            ..current.clone()
+        };
+
+        // the password being replaced is archived like any other update
+        let history_limit = context.settings.borrow_mut().get_history_limit(context);
+        match context.db.update_entry_with_history(&current, restored_entry, history_limit) {
+            Ok(_) => (),
+            Err(e) => return Err(format!("Error restoring version {}: {}", self.version, e)),
+        }
+
+        let bc = match Backup::new() {
+            Ok(bc) => bc,
+            Err(e) => return Err(format!("Version {} restored but no backup was made: {}", self.version, e)),
+        };
+
+        let kgc = context.kgc.borrow();
+        if let Err(e) = bc.create_new_backup(kgc.get_config_path(), kgc.get_data_storage_path(), &kgc.get_config_path().with_extension("checksum")) {
+            return Err(format!("Version {} restored but no backup was made: {}", self.version, e));
+        }
+
+        Ok(RollbackOutput { name: current.ent_name, version: self.version })
+    }
+
+    fn validate(&self, context: &Context) -> Result<(), String> {
+        let val_reg = ValidationRegistry::<RollbackCmd>::new();
+
+        let val_checks = vec![
+            ValidationType::MasterKeyCheck,
+            ValidationType::SessionCheck,
+            ValidationType::EntryExistsCheck,
+        ];
+
+        for a_check in val_checks {
+            match val_reg.validators.get(&a_check).unwrap().validate(context, &self) {
+                ValidationResult::Failure(msg) => return Err(msg),
+                ValidationResult::Warning(msg) => warn!("{msg}"),
+                ValidationResult::Success => debug!("test passed ✅")
+            }
+        }
+        Ok(())
+    }
+
+    fn display(&self) {
+        debug!("Rollback command with entry name = {} and version = {}", self.ent_name, self.version);
+        ()
+    }
+}
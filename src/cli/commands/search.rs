@@ -0,0 +1,102 @@
+use crate::cli::output::{write_lines, Report};
+use crate::cli::Command;
+use crate::context::Context;
+use crate::search::search;
+use crate::validator::core::{ValidationResult, ValidationType};
+use crate::validator::registry::ValidationRegistry;
+use log::{debug, error, info, warn};
+use serde::Serialize;
+use std::fmt;
+
+pub struct SearchCmd {
+    pub query: String,
+}
+
+impl SearchCmd {
+    pub fn new(query: String) -> Self {
+        SearchCmd { query }
+    }
+}
+
+#[derive(Serialize)]
+pub struct SearchOutput {
+    pub query: String,
+    pub matches: Vec<SearchMatch>,
+}
+
+#[derive(Serialize)]
+pub struct SearchMatch {
+    pub name: String,
+    pub username: Option<String>,
+    pub url: Option<String>,
+    pub tags: Vec<String>,
+}
+
+impl fmt::Display for SearchMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut details = Vec::new();
+        if let Some(username) = &self.username {
+            details.push(username.clone());
+        }
+        if let Some(url) = &self.url {
+            details.push(url.clone());
+        }
+        if !self.tags.is_empty() {
+            details.push(format!("[{}]", self.tags.join(", ")));
+        }
+        write!(f, "{}  {}", self.name, details.join("  "))
+    }
+}
+
+impl fmt::Display for SearchOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_lines(f, &self.matches, &format!("No entry matches {}", self.query))
+    }
+}
+
+impl Report for SearchOutput {}
+
+impl Command for SearchCmd {
+    type Output = SearchOutput;
+
+    fn execute(&self, context: &Context) -> Result<SearchOutput, String> {
+        let matches = match search(&context.db, &self.query) {
+            Ok(matches) => matches,
+            Err(e) => return Err(format!("Error searching entries: {}", e)),
+        };
+
+        let matches = matches
+            .into_iter()
+            .map(|found| SearchMatch {
+                name: found.entry.ent_name,
+                username: found.entry.username,
+                url: found.entry.url,
+                tags: found.tags,
+            })
+            .collect();
+        Ok(SearchOutput { query: self.query.clone(), matches })
+    }
+
+    fn validate(&self, context: &Context) -> Result<(), String> {
+        let val_reg = ValidationRegistry::<SearchCmd>::new();
+
+        let val_checks = vec![
+            ValidationType::MasterKeyCheck,
+            ValidationType::SessionCheck,
+        ];
+
+        for a_check in val_checks {
+            match val_reg.validators.get(&a_check).unwrap().validate(context, &self) {
+                ValidationResult::Failure(msg) => return Err(msg),
+                ValidationResult::Warning(msg) => warn!("{msg}"),
+                ValidationResult::Success => debug!("test passed ✅")
+            }
+        }
+        Ok(())
+    }
+
+    fn display(&self) {
+        debug!("Search command with query = {}", self.query);
+        ()
+    }
+}
@@ -1,7 +1,11 @@
+use crate::cli::output::{write_lines, Report};
 use crate::cli::Command;
 use crate::context::Context;
+use crate::policy::{list_policies, load_policy, parse_spec, save_policy, to_spec, POLICY_KEY_PREFIX};
 use crate::settings::Setting;
 use log::{debug, error, info, warn};
+use serde::Serialize;
+use std::fmt;
 
 pub struct SettingsCmd {
     action: SettingsAction,
@@ -27,8 +31,74 @@ impl SettingsCmd {
     }
 }
 
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum SettingsOutput {
+    List { settings: Vec<SettingValue>, policies: Vec<PolicyValue> },
+    Value(SettingValue),
+    Set(SettingValue),
+}
+
+#[derive(Serialize)]
+pub struct SettingValue {
+    pub key: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<&'static str>,
+    /// Nothing was set, this is the default value.
+    pub default: bool,
+}
+
+/// A policy saved in the vault, `error` says why it cannot be read.
+#[derive(Serialize)]
+pub struct PolicyValue {
+    pub key: String,
+    pub spec: Option<String>,
+    pub error: Option<String>,
+}
+
+impl fmt::Display for SettingValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {}", self.key, self.value)?;
+        match (self.description, self.default) {
+            (Some(description), _) => write!(f, " ({})", description),
+            (None, true) => write!(f, " (default)"),
+            (None, false) => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for PolicyValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.spec, &self.error) {
+            (Some(spec), _) => write!(f, "{} = {}", self.key, spec),
+            (None, error) => write!(f, "{}: invalid ({})", self.key, error.as_deref().unwrap_or_default()),
+        }
+    }
+}
+
+impl fmt::Display for SettingsOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsOutput::List { settings, policies } => {
+                write_lines(f, settings, "")?;
+                if !policies.is_empty() {
+                    writeln!(f)?;
+                }
+                write_lines(f, policies, "")
+            }
+            SettingsOutput::Value(value) => write!(f, "{}", value),
+            SettingsOutput::Set(value) => write!(f, "{} set to {}", value.key, value.value),
+        }
+    }
+}
+
+impl Report for SettingsOutput {}
+
 impl Command for SettingsCmd {
-    fn validate(&self, context: &Context) -> bool {
+    type Output = SettingsOutput;
+
+    fn validate(&self, context: &Context) -> Result<(), String> {
         match &self.action {
             SettingsAction::List => {
                 let sttngs = context.db.list_settings().unwrap();
@@ -52,32 +122,73 @@ impl Command for SettingsCmd {
 
                 //TODO: to if a setting has custom dont show it twice
 
-                return true;
+                return Ok(());
             },
             SettingsAction::Get(name) => {
-                
-                true
+                if name.starts_with(POLICY_KEY_PREFIX) {
+                    return Ok(());
+                }
+                if context.settings.borrow().find(name).is_none() {
+                    return Err(format!("Unknown setting {}", name));
+                }
+                Ok(())
+            },
+            SettingsAction::Set(name, value) if name.starts_with(POLICY_KEY_PREFIX) => {
+                parse_spec(value).map(|_| ())
             },
             SettingsAction::Set(name, value) => {
-                
-                true
+                match context.settings.borrow().find(name) {
+                    Some(setting) => setting.validate(value),
+                    None => Err(format!("Unknown setting {}", name)),
+                }
             }
         }
     }
 
-    fn execute(&self, context: &Context) -> bool {
+    fn execute(&self, context: &Context) -> Result<SettingsOutput, String> {
         match &self.action {
             SettingsAction::List => {
-                // Get all settings
-                true
+                let mut settings = Vec::new();
+                for setting in context.settings.borrow().list_settings() {
+                    let value = context.db.get_setting_value(setting.key()).ok().flatten();
+                    settings.push(SettingValue {
+                        key: setting.key().to_string(),
+                        default: value.is_none(),
+                        value: value.unwrap_or_else(|| setting.default_value().to_string()),
+                        description: Some(setting.description()),
+                    });
+                }
+                let policies = list_policies(Some(&context.db))
+                    .into_iter()
+                    .map(|(name, policy)| PolicyValue {
+                        key: format!("{}{}", POLICY_KEY_PREFIX, name),
+                        spec: policy.as_ref().ok().map(to_spec),
+                        error: policy.err(),
+                    })
+                    .collect();
+                Ok(SettingsOutput::List { settings, policies })
+            },
+            SettingsAction::Get(name) if name.starts_with(POLICY_KEY_PREFIX) => {
+                let policy_name = &name[POLICY_KEY_PREFIX.len()..];
+                let policy = load_policy(Some(&context.db), policy_name)?;
+                Ok(SettingsOutput::Value(SettingValue { key: name.clone(), value: to_spec(&policy), description: None, default: false }))
             },
             SettingsAction::Get(name) => {
-                // Get specific setting
-                true
+                let setting = context.settings.borrow().find(name).unwrap(); // checked in validate
+                let value = match context.settings.borrow_mut().get_string(context, setting) {
+                    Ok(value) => SettingValue { key: name.clone(), value, description: None, default: false },
+                    Err(_) => SettingValue { key: name.clone(), value: setting.default_value().to_string(), description: None, default: true },
+                };
+                Ok(SettingsOutput::Value(value))
+            },
+            SettingsAction::Set(name, value) if name.starts_with(POLICY_KEY_PREFIX) => {
+                save_policy(&context.db, &name[POLICY_KEY_PREFIX.len()..], value)?;
+                Ok(SettingsOutput::Set(SettingValue { key: name.clone(), value: value.clone(), description: None, default: false }))
             },
             SettingsAction::Set(name, value) => {
-                // Set specific setting
-                true
+                let setting = context.settings.borrow().find(name).unwrap(); // checked in validate
+                context.settings.borrow_mut().set(context, setting, value)?;
+                Ok(SettingsOutput::Set(SettingValue { key: name.clone(), value: value.clone(), description: None, default: false }))
             }
         }
     }
@@ -0,0 +1,111 @@
+use crate::cli::output::Report;
+use crate::cli::Command;
+use crate::context::Context;
+use crate::validator::core::{ValidationResult, ValidationType};
+use crate::validator::registry::ValidationRegistry;
+use log::{debug, error, info, warn};
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagAction {
+    Add,
+    Remove,
+}
+
+pub struct TagCmd {
+    pub ent_name: String,
+    pub tags: Vec<String>,
+    action: TagAction,
+}
+
+impl TagCmd {
+    pub fn new_add(ent_name: String, tags: Vec<String>) -> Self {
+        Self { ent_name, tags, action: TagAction::Add }
+    }
+
+    pub fn new_remove(ent_name: String, tags: Vec<String>) -> Self {
+        Self { ent_name, tags, action: TagAction::Remove }
+    }
+}
+
+#[derive(Serialize)]
+pub struct TagOutput {
+    pub name: String,
+    pub action: TagAction,
+    /// Tags added or removed.
+    pub changed: Vec<String>,
+    /// Tags the entry already had, or did not have for a removal.
+    pub unchanged: Vec<String>,
+}
+
+impl fmt::Display for TagOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut lines = Vec::new();
+        for tag in &self.changed {
+            lines.push(match self.action {
+                TagAction::Add => format!("{} tagged {}", self.name, tag),
+                TagAction::Remove => format!("{} removed from {}", tag, self.name),
+            });
+        }
+        for tag in &self.unchanged {
+            lines.push(match self.action {
+                TagAction::Add => format!("{} was already tagged {}", self.name, tag),
+                TagAction::Remove => format!("{} is not tagged {}", self.name, tag),
+            });
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+impl Report for TagOutput {}
+
+impl Command for TagCmd {
+    type Output = TagOutput;
+
+    fn execute(&self, context: &Context) -> Result<TagOutput, String> {
+        let entry = match context.db.get_entry_by_name(&self.ent_name) {
+            Ok(entry) => entry,
+            Err(e) => return Err(format!("Error retrieving entry by name: {}", e)),
+        };
+
+        let mut output = TagOutput { name: entry.ent_name, action: self.action, changed: Vec::new(), unchanged: Vec::new() };
+        for tag in &self.tags {
+            let changed = match self.action {
+                TagAction::Add => context.db.add_tag(entry.id, tag),
+                TagAction::Remove => context.db.remove_tag(entry.id, tag),
+            };
+            match changed {
+                Ok(true) => output.changed.push(tag.clone()),
+                Ok(false) => output.unchanged.push(tag.clone()),
+                Err(e) => return Err(format!("Error updating tag {}: {}", tag, e)),
+            }
+        }
+        Ok(output)
+    }
+
+    fn validate(&self, context: &Context) -> Result<(), String> {
+        let val_reg = ValidationRegistry::<TagCmd>::new();
+
+        let val_checks = vec![
+            ValidationType::MasterKeyCheck,
+            ValidationType::SessionCheck,
+            ValidationType::EntryExistsCheck,
+        ];
+
+        for a_check in val_checks {
+            match val_reg.validators.get(&a_check).unwrap().validate(context, &self) {
+                ValidationResult::Failure(msg) => return Err(msg),
+                ValidationResult::Warning(msg) => warn!("{msg}"),
+                ValidationResult::Success => debug!("test passed ✅")
+            }
+        }
+        Ok(())
+    }
+
+    fn display(&self) {
+        debug!("Tag command with entry name = {}", self.ent_name);
+        ()
+    }
+}
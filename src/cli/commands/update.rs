@@ -5,23 +5,25 @@ use crate::validator::registry::ValidationRegistry;
 use std::fmt;
 use crate::errors::{ErrorExecution, ErrorValidation};
 use crate::context::Context;
+use crate::crypto::{encrypt_secret, secret_mac};
 use crate::db::Db::Entry;
+use crate::cli::output::Report;
+use crate::strength::{estimate_strength, Strength};
 use chrono::prelude::*;
 use log::{debug, info, warn, error};
+use serde::Serialize;
 use sha2::Digest;
-use std::cell::Cell;
-
-use aes::cipher::{
-    KeyIvInit, StreamCipher,
-    generic_array::GenericArray,
-};
-use ctr::Ctr32BE;
-type Aes256Ctr = Ctr32BE<aes::Aes256>;
 pub struct UpdateCmd {
     pub name: String,
     pub password: String,
+    /// The password was generated, so it skips the password requirements.
     pub suggest_flag: bool,
-    pub suggested_pwd : Cell<String>,
+    pub max_age: Option<u32>,
+    /// Password policy checked on typed passwords, the default one when `None`.
+    pub policy: Option<String>,
+    pub username: Option<String>,
+    pub url: Option<String>,
+    pub notes: Option<String>,
 }
 
 
@@ -29,10 +31,52 @@ impl  UpdateCmd {
     pub fn new(name: String, password: String,  suggest_flag: bool) -> UpdateCmd
     {
         UpdateCmd{name, password,suggest_flag,
-            suggested_pwd: Cell::new(String::new()),}
+            max_age: None,
+            policy: None,
+            username: None,
+            url: None,
+            notes: None,}
+    }
+
+    /// Maximum age in days before the password should be rotated, 0 never expires.
+    pub fn with_max_age(mut self, max_age: Option<u32>) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    pub fn with_policy(mut self, policy: Option<String>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// What the password should not be built from.
+    pub fn user_inputs(&self) -> Vec<&str> {
+        [Some(self.name.as_str()), self.username.as_deref(), self.url.as_deref()].into_iter().flatten().collect()
+    }
+
+    pub fn with_metadata(mut self, username: Option<String>, url: Option<String>, notes: Option<String>) -> Self {
+        self.username = username;
+        self.url = url;
+        self.notes = notes;
+        self
     }
 }
 
+#[derive(Serialize)]
+pub struct UpdateOutput {
+    pub name: String,
+    pub strength: Strength,
+}
+
+impl fmt::Display for UpdateOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Password strength: {}", self.strength)?;
+        write!(f, "Entry updated successfully")
+    }
+}
+
+impl Report for UpdateOutput {}
+
 impl PartialEq for UpdateCmd {
     fn eq(&self, other: &Self) -> bool {
         if (self.name == other.name) && (self.password == other.password) {return true}
@@ -51,67 +95,52 @@ impl fmt::Debug for UpdateCmd {
 
 
 impl Command for UpdateCmd {
-    fn execute(&self, context: &Context) -> bool  {
+    type Output = UpdateOutput;
+
+    fn execute(&self, context: &Context) -> Result<UpdateOutput, String> {
         let master_key_hash = {
             let kgc = context.kgc.borrow();
             kgc.get_hashed_pwd()
         };
 
-        let master_key_bytes = match hex::decode(&master_key_hash) {
-            Ok(bytes) => bytes,
-            Err(e) => {
-                error!("Error decoding master key hash: {}", e);
-                return false;
-            }
-        };
-
-        // Create key and nonce
-        let key = GenericArray::from_slice(&master_key_bytes);
-        let nonce = GenericArray::from_slice(&[0u8; 16]); // In production, use secure random nonce
-
-        // Initialize ciph
-        let mut cipher = Aes256Ctr::new(key, nonce);
+        let strength = estimate_strength(&self.password, &self.user_inputs());
 
         // Encrypt the password
-        let mut encrypted_password;
-
-        if (self.suggest_flag) {
-            encrypted_password = self.suggested_pwd.take().into_bytes();
-
-        }else {
-            encrypted_password = self.password.clone().into_bytes();
-        }
-        
-        cipher.apply_keystream(&mut encrypted_password);
-
-        // Convert to hex for storage
-        let encrypted_password_hex = hex::encode(encrypted_password);
+        let encrypted_password_hex = match encrypt_secret(&master_key_hash, &self.password) {
+            Ok(hex) => hex,
+            Err(e) => return Err(e.to_string()),
+        };
+        let secret_mac = match secret_mac(&master_key_hash, &self.password) {
+            Ok(mac) => mac,
+            Err(e) => return Err(e.to_string()),
+        };
 
-        let entry_id = match context.db.get_entry_by_name(&self.name) {
-            Ok(entry) => entry.id,
-            Err(e) => {
-                error!("Error retrieving entry by name: {}", e); // should shoul never happen as this check happened in validate
-                return false;
-            }
+        let current = match context.db.get_entry_by_name(&self.name) {
+            Ok(entry) => entry,
+            // should never happen as this check happened in validate
+            Err(e) => return Err(format!("Error retrieving entry by name: {}", e)),
         };
 
         // Create new entry with updated information
         let updated_entry = Entry {
-            id: entry_id,
             ent_name: self.name.clone(),
             password_hash: encrypted_password_hex,
-            timestamp: Utc::now().to_rfc3339(),
+            updated_at: Utc::now(),
+            max_age_days: self.max_age.or(current.max_age_days),
+            username: self.username.clone().or(current.username.clone()),
+            url: self.url.clone().or(current.url.clone()),
+            notes: self.notes.clone().or(current.notes.clone()),
+            policy: self.policy.clone().or(current.policy.clone()),
+            secret_mac: Some(secret_mac),
+            ..current.clone()
         };
 
-        // Update the entry in the database
-        match context.db.update_entry(entry_id, updated_entry) {
-            Ok(_) => {
-                info!("Entry updated successfully");
-            },
-            Err(e) => {
-                error!("Error updating entry: {}", e);
-                return false;
-            }
+        let history_limit = context.settings.borrow_mut().get_history_limit(context);
+
+        // Update the entry in the database, the previous password goes to the history
+        match context.db.update_entry_with_history(&current, updated_entry, history_limit) {
+            Ok(_) => (),
+            Err(e) => return Err(format!("Error updating entry: {}", e)),
         }
 
         let bc = Backup::new().unwrap();
@@ -120,10 +149,10 @@ impl Command for UpdateCmd {
         &context.kgc.borrow().get_data_storage_path(), 
         &context.kgc.borrow().get_config_path().with_extension("checksum")).unwrap();
 
-        true
+        Ok(UpdateOutput { name: self.name.clone(), strength })
     }
 
-    fn validate(&self, context: &Context) -> bool  {
+    fn validate(&self, context: &Context) -> Result<(), String>  {
         
         let val_reg = ValidationRegistry::<UpdateCmd>::new();
 
@@ -132,22 +161,21 @@ impl Command for UpdateCmd {
             ValidationType::SessionCheck,
             ValidationType::EntryExistsCheck,
             ValidationType::PasswordRequirementCheck,
+            ValidationType::BreachCheck,
+            ValidationType::DuplicateEntryCheck,
         ];
 
 
         for a_check in val_checks {
 
             match val_reg.validators.get(&a_check).unwrap().validate(context, &self) {
-                ValidationResult::Failure(msg) => {
-                    error!("{msg}");
-                    return false;
-                },
+                ValidationResult::Failure(msg) => return Err(msg),
                 ValidationResult::Warning(msg) => warn!("{msg}"),
                 ValidationResult::Success => debug!("test passed ✅")
 
             }
         }
-        true
+        Ok(())
     }
 
     fn display(&self) {
@@ -0,0 +1,155 @@
+use crate::cli::output::{write_lines, Report};
+use crate::cli::StandaloneCommand;
+use crate::vault::{is_valid_vault_name, VaultEntry, VaultPaths, VaultRegistry};
+use log::{debug, error, info};
+use serde::Serialize;
+use std::fmt;
+use std::env;
+use std::path::PathBuf;
+
+pub struct VaultCmd {
+    action: VaultAction,
+}
+
+pub enum VaultAction {
+    List,
+    Create(String, Option<PathBuf>),
+    Use(String),
+}
+
+impl VaultCmd {
+    pub fn new_list() -> Self {
+        Self { action: VaultAction::List }
+    }
+
+    pub fn new_create(name: String, path: Option<PathBuf>) -> Self {
+        Self { action: VaultAction::Create(name, path) }
+    }
+
+    pub fn new_use(name: String) -> Self {
+        Self { action: VaultAction::Use(name) }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum VaultOutput {
+    List {
+        vaults: Vec<VaultValue>,
+        /// Vault used by this run when `--vault` or `KOFL_HOME` overrides the current one.
+        overridden_by: Option<String>,
+    },
+    Created { name: String, path: PathBuf },
+    Current { current: String },
+}
+
+#[derive(Serialize)]
+pub struct VaultValue {
+    pub name: String,
+    pub config_path: PathBuf,
+    /// False until the vault is opened once.
+    pub created: bool,
+    pub current: bool,
+}
+
+impl fmt::Display for VaultValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let marker = if self.current { "*" } else { " " };
+        let state = if self.created { "created" } else { "never opened" };
+        write!(f, "{} {} ({}) - {}", marker, self.name, state, self.config_path.display())
+    }
+}
+
+impl fmt::Display for VaultOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VaultOutput::List { vaults, overridden_by } => {
+                write_lines(f, vaults, "")?;
+                match overridden_by {
+                    Some(name) => write!(f, "\nOverridden for this run by {}", name),
+                    None => Ok(()),
+                }
+            }
+            VaultOutput::Created { name, path } => {
+                writeln!(f, "Vault {} created in {}", name, path.display())?;
+                write!(f, "Run `kofl --vault {} init` to set its master password.", name)
+            }
+            VaultOutput::Current { current } => write!(f, "Now using vault {}", current),
+        }
+    }
+}
+
+impl Report for VaultOutput {}
+
+impl StandaloneCommand for VaultCmd {
+    type Output = VaultOutput;
+
+    fn validate(&self) -> Result<(), String> {
+        let registry = VaultRegistry::load();
+        match &self.action {
+            VaultAction::List => Ok(()),
+            VaultAction::Create(name, _) => {
+                if !is_valid_vault_name(name) {
+                    return Err("Vault names may only contain letters, digits, '-' and '_' ⛔".to_string());
+                }
+                if registry.contains(name) {
+                    return Err(format!("Vault {} already exists ⛔", name));
+                }
+                Ok(())
+            }
+            VaultAction::Use(name) => registry.check_known(name),
+        }
+    }
+
+    fn execute(&self) -> Result<VaultOutput, String> {
+        let mut registry = VaultRegistry::load();
+        match &self.action {
+            VaultAction::List => {
+                let active = VaultPaths::resolve();
+                let vaults = registry
+                    .names()
+                    .into_iter()
+                    .map(|name| {
+                        let paths = registry.paths_of(&name);
+                        VaultValue { current: name == registry.current, created: paths.exists(), config_path: paths.config_path, name }
+                    })
+                    .collect();
+                let overridden_by = Some(active.name).filter(|name| *name != registry.current);
+                Ok(VaultOutput::List { vaults, overridden_by })
+            }
+            VaultAction::Create(name, path) => {
+                let dir = path.as_ref().map(|p| {
+                    if p.is_absolute() {
+                        p.clone()
+                    } else {
+                        env::current_dir().unwrap_or_default().join(p)
+                    }
+                });
+                registry.vaults.insert(name.clone(), VaultEntry { path: dir });
+
+                let paths = registry.paths_of(name);
+                if let Err(e) = paths.create_dirs() {
+                    return Err(format!("Failed to create vault directories: {}", e));
+                }
+                if let Err(e) = registry.save() {
+                    return Err(format!("Failed to save vault list: {}", e));
+                }
+
+                let path = paths.data_path.parent().unwrap_or(&paths.data_path).to_path_buf();
+                Ok(VaultOutput::Created { name: name.clone(), path })
+            }
+            VaultAction::Use(name) => {
+                registry.current = name.clone();
+                if let Err(e) = registry.save() {
+                    return Err(format!("Failed to save vault list: {}", e));
+                }
+                Ok(VaultOutput::Current { current: name.clone() })
+            }
+        }
+    }
+
+    fn display(&self) {
+        debug!("Vault Command");
+        ()
+    }
+}
@@ -1,11 +1,25 @@
 use crate::context::Context;
 use crate::errors::{ErrorExecution, ErrorValidation};
+use output::Report;
 
+/// `validate` and `execute` return the message shown to the user when they fail.
 pub trait Command {
-    fn validate(&self, _context: &Context) -> bool;
-    fn execute(&self, context: &Context) -> bool;
+    type Output: Report;
+    fn validate(&self, _context: &Context) -> Result<(), String>;
+    fn execute(&self, context: &Context) -> Result<Self::Output, String>;
+    fn display(&self);
+}
+
+/// Commands that run without opening a vault.
+pub trait StandaloneCommand {
+    type Output: Report;
+    fn validate(&self) -> Result<(), String>;
+    fn execute(&self) -> Result<Self::Output, String>;
     fn display(&self);
 }
 
 // Re-export commands
 pub mod commands;
+pub mod output;
+pub mod secret;
+pub mod suggest;
@@ -0,0 +1,147 @@
+//! What the commands print: text for people or, with `--output json`, a single
+//! JSON document on stdout for scripts. The field names of the results and of
+//! the `error` object are kept stable, logs go to stderr in JSON mode.
+
+use log::error;
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!("Unknown output `{}`, expected text or json", s)),
+        }
+    }
+}
+
+/// The result of a command that ran, `Display` is its text output.
+pub trait Report: Serialize + fmt::Display {
+    /// False when the command ran but the outcome is a failure, as an audit
+    /// finding more issues than allowed.
+    fn passed(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// The command was refused before it changed anything.
+    Validation,
+    Execution,
+    /// The user turned down what was asked, such as a suggested password.
+    Cancelled,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct CommandError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl CommandError {
+    pub fn validation(message: String) -> Self {
+        CommandError { kind: ErrorKind::Validation, message }
+    }
+
+    pub fn execution(message: String) -> Self {
+        CommandError { kind: ErrorKind::Execution, message }
+    }
+
+    pub fn cancelled(message: String) -> Self {
+        CommandError { kind: ErrorKind::Cancelled, message }
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorDocument<'a> {
+    error: &'a CommandError,
+}
+
+/// Prints the result in the format asked for, returns whether the command succeeded.
+pub fn render<R: Report>(result: &Result<R, CommandError>, format: OutputFormat) -> bool {
+    match (result, format) {
+        (Ok(report), OutputFormat::Text) => {
+            let text = report.to_string();
+            if !text.is_empty() {
+                println!("{}", text);
+            }
+        }
+        (Ok(report), OutputFormat::Json) => println!("{}", to_json(report)),
+        (Err(e), format) => render_error(e, format),
+    }
+    result.as_ref().is_ok_and(|report| report.passed())
+}
+
+/// For failures before a command could run, such as reading its secret.
+pub fn render_error(error: &CommandError, format: OutputFormat) {
+    match format {
+        OutputFormat::Text => error!("{}", error.message),
+        OutputFormat::Json => println!("{}", to_json(&ErrorDocument { error })),
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> String {
+    // plain structs with string keys, serializing them cannot fail
+    serde_json::to_string_pretty(value).expect("command results serialize to JSON")
+}
+
+/// Writes the items one per line, `empty` when there is none.
+pub fn write_lines<T: fmt::Display>(f: &mut fmt::Formatter<'_>, items: &[T], empty: &str) -> fmt::Result {
+    if items.is_empty() {
+        return write!(f, "{}", empty);
+    }
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            writeln!(f)?;
+        }
+        write!(f, "{}", item)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Added {
+        name: String,
+    }
+
+    impl fmt::Display for Added {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "Entry {} added", self.name)
+        }
+    }
+
+    impl Report for Added {}
+
+    #[test]
+    fn test_json_documents() {
+        assert_eq!(to_json(&Added { name: "github".to_string() }), "{\n  \"name\": \"github\"\n}");
+
+        let error = CommandError::validation("Entry github already exists".to_string());
+        let document: serde_json::Value = serde_json::from_str(&to_json(&ErrorDocument { error: &error })).unwrap();
+        assert_eq!(document, serde_json::json!({"error": {"kind": "validation", "message": "Entry github already exists"}}));
+    }
+
+    #[test]
+    fn test_render_status() {
+        assert!(render(&Ok(Added { name: "github".to_string() }), OutputFormat::Json));
+        assert!(!render(&Err::<Added, _>(CommandError::execution("disk full".to_string())), OutputFormat::Json));
+        assert_eq!("json".parse(), Ok(OutputFormat::Json));
+        assert!("yaml".parse::<OutputFormat>().is_err());
+    }
+}
@@ -0,0 +1,225 @@
+//! Where the secret of an entry comes from, resolved before the command is validated,
+//! and where the master password is read from.
+
+use crate::cli::suggest::review_on_terminal;
+use crate::context::Context;
+use crate::generator::generate;
+use crate::generator::passphrase::{generate_passphrase, load_wordlist, PassphraseOptions};
+use crate::generator::profile::load_profile;
+use crate::validator::pwd_req::PasswordRequirementValidator;
+use std::env;
+use std::fs;
+use std::io::{self, BufRead};
+use std::path::{Path, PathBuf};
+
+/// File holding the master password, used when no flag says where to read it from.
+pub const MASTER_PASSWORD_FILE_ENV: &str = "KOFL_MASTER_PASSWORD_FILE";
+
+pub enum SecretSource {
+    Prompt,
+    Generate {
+        profile: Option<String>,
+        passphrase: bool,
+        /// Print the candidate instead of reviewing it.
+        print: bool,
+        /// Policy an edited candidate is checked against.
+        policy: Option<String>,
+    },
+    Stdin,
+    File(PathBuf),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Secret {
+    pub value: String,
+    /// Generated secrets follow the policy but skip the weak password warning.
+    pub generated: bool,
+}
+
+impl Secret {
+    pub fn typed(value: String) -> Self {
+        Secret { value, generated: false }
+    }
+
+    pub fn generated(value: String) -> Self {
+        Secret { value, generated: true }
+    }
+}
+
+impl SecretSource {
+    /// `--password-stdin` or `--password-file` for the secret of an entry,
+    /// clap makes sure they are not both given.
+    pub fn from_flags(stdin: bool, file: &Option<PathBuf>) -> Option<Self> {
+        match (stdin, file) {
+            (true, _) => Some(SecretSource::Stdin),
+            (false, Some(path)) => Some(SecretSource::File(path.clone())),
+            (false, None) => None,
+        }
+    }
+
+    /// Where the master password is read from: the flags, then the file named by
+    /// `KOFL_MASTER_PASSWORD_FILE`, then a prompt.
+    pub fn master_password(stdin: bool, file: &Option<PathBuf>) -> Self {
+        SecretSource::from_flags(stdin, file)
+            .or_else(|| env::var_os(MASTER_PASSWORD_FILE_ENV).filter(|path| !path.is_empty()).map(|path| SecretSource::File(path.into())))
+            .unwrap_or(SecretSource::Prompt)
+    }
+
+    pub fn is_prompt(&self) -> bool {
+        matches!(self, SecretSource::Prompt)
+    }
+
+    /// Reads a typed secret, asking with `prompt` on the terminal for `Prompt`.
+    pub fn read(&self, prompt: &str) -> Result<String, String> {
+        match self {
+            SecretSource::Prompt => rpassword::prompt_password(prompt).map_err(|e| format!("Cannot read the password: {}", e)),
+            SecretSource::Stdin => read_secret(&mut io::stdin().lock()),
+            SecretSource::File(path) => {
+                check_not_world_readable(path)?;
+                let content = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+                read_secret(&mut content.as_bytes())
+            }
+            SecretSource::Generate { .. } => Err("A password cannot be generated here".to_string()),
+        }
+    }
+
+    /// Returns `None` when the user turned the suggested password down.
+    pub fn resolve(&self, context: &Context) -> Result<Option<Secret>, String> {
+        match self {
+            SecretSource::Generate { profile, passphrase, print, policy } => {
+                let candidate = suggest(context, profile, *passphrase, policy.as_deref())?;
+                review_on_terminal(
+                    candidate,
+                    *print,
+                    || suggest(context, profile, *passphrase, policy.as_deref()),
+                    |password| PasswordRequirementValidator::check_requirements(context, policy.as_deref(), password, &[]),
+                )
+            }
+            _ => self.read("Enter the password for the entry ===> ").map(|value| Some(Secret::typed(value))),
+        }
+    }
+}
+
+// a secret every user of the machine can read is not a secret
+fn check_not_world_readable(path: &Path) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let metadata = fs::metadata(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        if metadata.permissions().mode() & 0o004 != 0 {
+            return Err(format!("{} is readable by every user, restrict it with `chmod 600`", path.display()));
+        }
+    }
+    Ok(())
+}
+
+/// The first line, without its line ending.
+fn read_secret<R: BufRead>(reader: &mut R) -> Result<String, String> {
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(|e| format!("Cannot read the password: {}", e))?;
+    let secret = line.trim_end_matches(['\r', '\n']);
+    if secret.is_empty() {
+        return Err("The password is empty".to_string());
+    }
+    Ok(secret.to_string())
+}
+
+/// Drawn before giving up on a policy the generator rarely meets.
+const SUGGEST_ATTEMPTS: usize = 100;
+
+/// Generates a candidate for `--suggest` that follows the password policy, with
+/// the profile given on the command line or the default one from the settings.
+pub fn suggest(context: &Context, profile: &Option<String>, passphrase: bool, policy: Option<&str>) -> Result<String, String> {
+    let policy = PasswordRequirementValidator::policy(context, policy)?;
+    let candidate: Box<dyn Fn() -> Result<String, String>> = if passphrase {
+        let wordlist_path = context.settings.borrow_mut().get_passphrase_wordlist(context);
+        let wordlist = load_wordlist(wordlist_path.as_deref())?;
+        let options = policy.fit_passphrase(PassphraseOptions::default());
+        Box::new(move || generate_passphrase(&options, &wordlist))
+    } else {
+        let name = match profile {
+            Some(name) => name.clone(),
+            None => context.settings.borrow_mut().get_default_generator_profile(context),
+        };
+        let options = policy.fit_generator(load_profile(Some(&context.db), &name)?);
+        Box::new(move || generate(&options))
+    };
+
+    let mut failure = String::new();
+    for _ in 0..SUGGEST_ATTEMPTS {
+        let password = candidate()?;
+        match PasswordRequirementValidator::check_policy(&policy, &password, &[]) {
+            Ok(()) => return Ok(password),
+            Err(message) => failure = message,
+        }
+    }
+    Err(format!("No suggestion met the password policy, try another profile or policy. {}", failure))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{load_policy, save_policy};
+    use crate::test_support::EnvGuard;
+    use serial_test::serial;
+
+    #[test]
+    fn test_read_secret() {
+        assert_eq!(read_secret(&mut "s3cret!Pass\nnext line\n".as_bytes()).unwrap(), "s3cret!Pass");
+        assert_eq!(read_secret(&mut "with spaces \r\n".as_bytes()).unwrap(), "with spaces ");
+        assert!(read_secret(&mut "\n".as_bytes()).is_err());
+        assert!(read_secret(&mut "".as_bytes()).is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_password_file() {
+        use std::os::unix::fs::PermissionsExt;
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("password");
+        fs::write(&path, "from file\n").unwrap();
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(SecretSource::File(path.clone()).read("").unwrap_err().contains("chmod 600"));
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        assert_eq!(SecretSource::File(path).read("").unwrap(), "from file");
+    }
+
+    #[test]
+    #[serial]
+    fn test_suggest_follows_the_policy() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (_env, context) = crate::test_support::isolated_context(&temp_dir);
+        save_policy(&context.db, "short", "min=8 max=10 symbols=_~ max-repeats=1 forbid=a,e").unwrap();
+        save_policy(&context.db, "words", "min=20 require=lower,upper,digits symbols=").unwrap();
+        let short = load_policy(Some(&context.db), "short").unwrap();
+        let words = load_policy(Some(&context.db), "words").unwrap();
+
+        for _ in 0..20 {
+            let password = suggest(&context, &None, false, Some("short")).unwrap();
+            assert!(short.violations(&password).is_empty(), "{}", password);
+            let passphrase = suggest(&context, &None, true, Some("words")).unwrap();
+            assert!(words.violations(&passphrase).is_empty(), "{}", passphrase);
+        }
+
+        // passphrases cannot be made short enough
+        save_policy(&context.db, "pin", "min=4 max=6 require=digits").unwrap();
+        assert!(suggest(&context, &None, true, Some("pin")).unwrap_err().contains("another profile or policy"));
+        assert!(suggest(&context, &None, false, Some("missing")).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_master_password_source() {
+        let guard = EnvGuard::new(MASTER_PASSWORD_FILE_ENV);
+        guard.remove_var();
+        assert!(SecretSource::master_password(false, &None).is_prompt());
+
+        guard.set_var("/run/secrets/kofl");
+        assert!(matches!(SecretSource::master_password(false, &None), SecretSource::File(path) if path == Path::new("/run/secrets/kofl")));
+        // the flags win over the environment
+        assert!(matches!(SecretSource::master_password(true, &None), SecretSource::Stdin));
+        let flag = Some(PathBuf::from("master"));
+        assert!(matches!(SecretSource::master_password(false, &flag), SecretSource::File(path) if path == Path::new("master")));
+    }
+}
@@ -0,0 +1,119 @@
+//! Review of a suggested password before it is stored.
+
+use crate::cli::secret::Secret;
+use arboard::Clipboard;
+use std::io::{self, BufRead, IsTerminal, Write};
+
+const HELP: &str = "[Enter] accept, (r)egenerate, (e)dit, (c)opy, (q)uit";
+
+/// Shows the candidate and loops until the user accepts it, returns `None` when they quit.
+///
+/// `regenerate` draws a new candidate, `check` validates a password typed with `e`
+/// and read with `read_secret`, so it is not echoed on a terminal.
+pub fn review<R, W, G, C, P>(
+    input: &mut R,
+    output: &mut W,
+    candidate: String,
+    regenerate: G,
+    check: C,
+    mut read_secret: P,
+) -> io::Result<Option<Secret>>
+where
+    R: BufRead,
+    W: Write,
+    G: Fn() -> Result<String, String>,
+    C: Fn(&str) -> Result<(), String>,
+    P: FnMut(&mut R) -> io::Result<String>,
+{
+    let mut candidate = Secret::generated(candidate);
+    loop {
+        writeln!(output, "Suggested password: {}", candidate.value)?;
+        write!(output, "{} ", HELP)?;
+        output.flush()?;
+
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            // end of input is not a confirmation
+            return Ok(None);
+        }
+
+        match answer.trim() {
+            "" | "y" | "yes" => return Ok(Some(candidate)),
+            "r" => match regenerate() {
+                Ok(new_candidate) => candidate = Secret::generated(new_candidate),
+                Err(e) => writeln!(output, "Password generation failed: {}", e)?,
+            },
+            "e" => {
+                write!(output, "New password: ")?;
+                output.flush()?;
+                let edited = read_secret(input)?;
+                let edited = edited.trim_end_matches(['\r', '\n']);
+                match check(edited) {
+                    Ok(_) => candidate = Secret::typed(edited.to_string()),
+                    Err(e) => writeln!(output, "{}", e)?,
+                }
+            }
+            "c" => match Clipboard::new().and_then(|mut clipboard| clipboard.set_text(candidate.value.clone())) {
+                Ok(_) => writeln!(output, "Copied to clipboard")?,
+                Err(e) => writeln!(output, "Error copying to clipboard: {}", e)?,
+            },
+            "q" | "n" | "no" => return Ok(None),
+            _ => writeln!(output, "Unknown choice")?,
+        }
+    }
+}
+
+/// Reviews the candidate on the terminal, with `print` it is written to stderr
+/// and accepted as is. Without a terminal nobody can review it, so `print`
+/// is required: a piped stdin must not accept a secret on its own.
+pub fn review_on_terminal<G, C>(candidate: String, print: bool, regenerate: G, check: C) -> Result<Option<Secret>, String>
+where
+    G: Fn() -> Result<String, String>,
+    C: Fn(&str) -> Result<(), String>,
+{
+    // stdout is kept for the command result
+    if print {
+        eprintln!("{}", candidate);
+        return Ok(Some(Secret::generated(candidate)));
+    }
+    if !io::stdin().is_terminal() {
+        return Err("Cannot review the suggested password without a terminal, add --print to accept it as is".to_string());
+    }
+
+    review(&mut io::stdin().lock(), &mut io::stderr(), candidate, regenerate, check, |_| rpassword::read_password())
+        .map_err(|e| format!("Cannot read the answer: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn run(answers: &str) -> Option<(String, bool)> {
+        let mut input = Cursor::new(answers.as_bytes().to_vec());
+        let mut output = Vec::new();
+        review(
+            &mut input,
+            &mut output,
+            "first".to_string(),
+            || Ok("second".to_string()),
+            |password| if password.len() >= 8 { Ok(()) } else { Err("too short".to_string()) },
+            |input| {
+                let mut line = String::new();
+                input.read_line(&mut line).map(|_| line)
+            },
+        )
+        .unwrap()
+        .map(|secret| (secret.value, secret.generated))
+    }
+
+    #[test]
+    fn test_review() {
+        assert_eq!(run("\n"), Some(("first".to_string(), true)));
+        assert_eq!(run("r\ny\n"), Some(("second".to_string(), true)));
+        // an edited password is no longer a generated one
+        assert_eq!(run("e\nshort\ne\nlong enough\n\n"), Some(("long enough".to_string(), false)));
+        assert_eq!(run("x\nq\n"), None);
+        assert_eq!(run(""), None);
+    }
+}
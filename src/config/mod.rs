@@ -1,8 +1,10 @@
 pub mod Config {
 
-    use crate::backup::Backup;
-    use crate::utils::Utils::{check_existing_config, get_config_path, get_home_dir};
-    use log::{debug, error, info};
+    use crate::backup::{quarantine_files, Backup};
+    use crate::errors::ErrorIntegrity;
+    use crate::vault::VaultPaths;
+    use hmac::{Hmac, Mac};
+    use log::{debug, error, info, warn};
     use serde::{Deserialize, Serialize};
     use sha2::{Digest, Sha256};
     use std::env;
@@ -12,6 +14,12 @@ pub mod Config {
     use std::path::PathBuf;
     use toml;
 
+    type HmacSha256 = Hmac<Sha256>;
+
+    // prefix of a keyed checksum, anything else is treated as a legacy plain sha256
+    const MAC_PREFIX: &str = "hmac-sha256:";
+    const MAC_KEY_CONTEXT: &[u8] = b"kofl-config-mac";
+
     #[derive(Serialize, Deserialize)]
     pub struct KoflGlobalConfig {
         config_path: PathBuf,
@@ -21,15 +29,18 @@ pub mod Config {
         salt: String,
         hashed_pwd: String,
         master_key_provided: bool,
+        // derived from the master password once unlocked, never written to disk
+        #[serde(skip)]
+        mac_key: Option<Vec<u8>>,
     }
 
     impl KoflGlobalConfig {
         pub fn new() -> KoflGlobalConfig {
-            let home_dir = get_home_dir().expect("Home directory not found");
+            let vault_paths = VaultPaths::resolve();
             let key = "USER";
             KoflGlobalConfig {
-                config_path: home_dir.join(".kofl"),
-                data_storage_path: home_dir.join("kofl.sqlite"),
+                config_path: vault_paths.config_path,
+                data_storage_path: vault_paths.data_path,
                 user_id: String::from("1234567"),
                 username: match env::var(key) {
                     Ok(val) => val,
@@ -38,6 +49,7 @@ pub mod Config {
                 salt: String::from(""),
                 hashed_pwd: String::from(""),
                 master_key_provided: false,
+                mac_key: None,
             }
         }
 
@@ -96,15 +108,113 @@ pub mod Config {
             hex::encode(hasher.finalize())
         }
 
-        fn verify_integrity(&self) -> bool {
-            // Read stored checksum from a separate file
+        pub fn verify_master_password(&self, master_pwd: &str) -> bool {
+            let mut hasher = Sha256::new();
+            hasher.update(master_pwd.as_bytes());
+            hasher.update(self.salt.as_bytes());
+            hex::encode(hasher.finalize()) == self.hashed_pwd
+        }
+
+        /// Derives the key used to authenticate the config file.
+        /// It is bound to the salt so it differs from the stored password hash.
+        pub fn derive_mac_key(&self, master_pwd: &str) -> Vec<u8> {
+            let mut mac = HmacSha256::new_from_slice(master_pwd.as_bytes())
+                .expect("HMAC can take a key of any size");
+            mac.update(MAC_KEY_CONTEXT);
+            mac.update(self.salt.as_bytes());
+            mac.finalize().into_bytes().to_vec()
+        }
+
+        pub fn set_mac_key(&mut self, key: Vec<u8>) {
+            self.mac_key = Some(key);
+        }
+
+        pub fn get_config_mac(&self, key: &[u8]) -> String {
+            let content = fs::read(self.get_config_path()).unwrap_or_default();
+
+            let mut mac = HmacSha256::new_from_slice(key).expect("HMAC can take a key of any size");
+            mac.update(&content);
+            format!("{}{}", MAC_PREFIX, hex::encode(mac.finalize().into_bytes()))
+        }
+
+        fn read_stored_checksum(&self) -> Result<String, ErrorIntegrity> {
             let checksum_path = self.get_config_path().with_extension("checksum");
+            match fs::read_to_string(&checksum_path) {
+                Ok(content) => Ok(content.trim().to_string()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(ErrorIntegrity::MissingChecksum),
+                Err(e) => Err(ErrorIntegrity::Corrupted(e.to_string())),
+            }
+        }
+
+        fn is_hex_digest(value: &str) -> bool {
+            value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit())
+        }
+
+        /// Checks that can run before the master password is known: the checksum
+        /// is present and well formed and the config still parses.
+        /// The keyed part is done by `verify_mac` once the vault is unlocked, which
+        /// only happens at login: the key is not kept in the session, so commands
+        /// run on an open session do not authenticate the config and an edit made
+        /// in between is caught at the next login.
+        pub fn verify_integrity(&self) -> Result<(), ErrorIntegrity> {
+            let stored = self.read_stored_checksum()?;
+
+            let config = match self.read_config_from_toml_file() {
+                Ok(config) => config,
+                Err(e) => return Err(ErrorIntegrity::Corrupted(format!("config does not parse ({})", e))),
+            };
+
+            match stored.strip_prefix(MAC_PREFIX) {
+                Some(digest) if Self::is_hex_digest(digest) => Ok(()),
+                Some(_) => Err(ErrorIntegrity::Corrupted("malformed authentication code".to_string())),
+                None if Self::is_hex_digest(&stored) => {
+                    // legacy unkeyed checksum, can only tell if the content changed
+                    if stored != self.get_config_checksum() {
+                        Err(ErrorIntegrity::Tampered)
+                    } else if config.is_master_key_provided() {
+                        // anyone can compute a plain sha256, it cannot vouch for a config holding a master key
+                        Err(ErrorIntegrity::Unkeyed)
+                    } else {
+                        Ok(())
+                    }
+                }
+                None => Err(ErrorIntegrity::Corrupted("unrecognised checksum format".to_string())),
+            }
+        }
+
+        /// Authenticates the config with a key derived from the master password.
+        /// A legacy checksum matching the config gives `Unkeyed`, the caller decides
+        /// whether to upgrade it.
+        pub fn verify_mac(&self, key: &[u8]) -> Result<(), ErrorIntegrity> {
+            self.verify_integrity()?;
+
+            let stored = self.read_stored_checksum()?;
+            if !stored.starts_with(MAC_PREFIX) {
+                return Err(ErrorIntegrity::Unkeyed);
+            }
+
+            let stored_bytes = hex::decode(&stored[MAC_PREFIX.len()..])
+                .map_err(|_| ErrorIntegrity::Corrupted("malformed authentication code".to_string()))?;
 
-            if let Ok(stored_checksum) = fs::read_to_string(&checksum_path) {
-                let current_checksum = self.get_config_checksum();
-                return stored_checksum == current_checksum;
+            // compare through the Mac api to keep the comparison constant time
+            let mut mac = HmacSha256::new_from_slice(key).expect("HMAC can take a key of any size");
+            mac.update(&fs::read(self.get_config_path()).unwrap_or_default());
+            mac.verify_slice(&stored_bytes).map_err(|_| ErrorIntegrity::Tampered)
+        }
+
+        /// Writes the keyed checksum next to the config, requires an unlocked key.
+        pub fn seal(&self) -> bool {
+            match &self.mac_key {
+                Some(key) => {
+                    let checksum_path = self.get_config_path().with_extension("checksum");
+                    fs::write(checksum_path, self.get_config_mac(key)).expect("Failed to write checksum file");
+                    true
+                }
+                None => {
+                    warn!("Config changed while the vault is locked, login again to reseal it.");
+                    false
+                }
             }
-            false
         }
 
         pub fn remove_files(
@@ -145,76 +255,50 @@ pub mod Config {
             true
         }
 
-        pub fn load(&mut self) {
-            if !check_existing_config() {
+        /// Reads the config once it passed the integrity check. A config failing
+        /// it is quarantined and the failure returned, for the caller to report.
+        pub fn load(&mut self) -> Result<(), ErrorIntegrity> {
+            if !self.get_config_path().exists() {
                 debug!("no existing config");
                 self.write_config_to_toml_file();
-                return;
+                return Ok(());
             }
 
             #[cfg(not(debug_assertions))]
-            if !self.verify_integrity() {
-                debug!("calling verify integrity");
-                error!(
-                    "\n
-                Config file integrity check failed! Possible tampering detected.
-                The default backup will be implemented,
-                The program will retrieve the last backup added
-                and replace currents configs, with onces from a backup,
-                in ideal case no data should be lost.
-                "
-                );
-
-                // I should later decide what's gonna happen if no backup found.
-                let bc = Backup::new().unwrap();
-
-                match bc.get_last_backup().unwrap() {
-                    Some(backup_dir_path) => {
-                        debug!("A backup dir was found, starting a new backup");
-                        debug!("{:?}", backup_dir_path.display());
-
-                        std::process::exit(1);
-                    }
-                    None => {
-                        info!(
-                            "\n
-                    No backup plan, means there is no password entries were added,
-                    the system will delete all cofig files run `kofl init` to reinitailise again.
-                    "
-                        );
-
-                        match fs::remove_file(self.get_data_storage_path()) {
-                            Ok(_) => (),
-                            Err(err) => {
-                                error!("Failed to remove data storage file: {}", err);
-                            }
-                        }
-                        match fs::remove_file(self.get_config_path()) {
-                            Ok(_) => (),
-                            Err(err) => {
-                                error!("Failed to remove kofl configuration file: {}", err);
-                            }
-                        }
-
-                        match fs::remove_file(self.get_config_path().with_extension("checksum")) {
-                            Ok(_) => (),
-                            Err(err) => {
-                                error!("Failed to remove checksum file: {}", err);
-                            }
-                        }
-                    }
-                }
+            if let Err(integrity_err) = self.verify_on_load() {
+                debug!("config file integrity check failed: {}", integrity_err);
+                self.recover_from_integrity_failure();
+                return Err(integrity_err);
+            }
+
+            self.load_unchecked();
+            Ok(())
+        }
 
-                std::process::exit(1);
-                // return;
+        /// The integrity check `load` runs before the vault is unlocked.
+        pub fn verify_on_load(&self) -> Result<(), ErrorIntegrity> {
+            match self.verify_integrity() {
+                // before `kofl init` there is no secret in the config and nothing was sealed yet
+                Err(ErrorIntegrity::MissingChecksum) if self.read_config_from_toml_file().map_or(false, |config| !config.is_master_key_provided()) => Ok(()),
+                // vaults from before the keyed checksum, it matched the config and login upgrades it
+                Err(ErrorIntegrity::Unkeyed) => {
+                    warn!("The config still has a legacy checksum, login to seal it with the master password.");
+                    Ok(())
+                }
+                result => result,
             }
+        }
 
+        /// Reads the config without the integrity check, used by the recovery commands.
+        pub fn load_unchecked(&mut self) {
             match self.read_config_from_toml_file() {
                 Ok(config) => {
+                    // where the vault lives is decided by the vault selection, not by the file
+                    let config_path = self.config_path.clone();
+                    let data_storage_path = self.data_storage_path.clone();
                     *self = config;
-                    // create a backup for now this is only for testing;
-                    // let bc = Backup::new().unwrap();
-                    // bc.create_new_backup(&self.get_config_path(), &self.get_data_storage_path(), &self.get_config_path().with_extension("checksum"));
+                    self.config_path = config_path;
+                    self.data_storage_path = data_storage_path;
                 }
                 Err(e) => {
                     error!("Failed to load config: {}", e);
@@ -223,12 +307,40 @@ pub mod Config {
             }
         }
 
+        /// Sets the suspicious files aside and explains how to get out of it.
+        /// Nothing is deleted here, least of all the database.
+        pub fn recover_from_integrity_failure(&self) {
+            let checksum_path = self.get_config_path().with_extension("checksum");
+            let files = [self.get_config_path(), &checksum_path, self.get_data_storage_path()];
+
+            match quarantine_files(&files) {
+                Ok(quarantine_dir) => info!(
+                    "A copy of the config, checksum and database was saved in {}",
+                    quarantine_dir.display()
+                ),
+                Err(e) => error!("Failed to quarantine files: {}", e),
+            }
+
+            info!("Your entries were NOT deleted, kofl refuses to open the vault until this is resolved.");
+            info!("  kofl doctor              # inspect the vault and see what is wrong");
+
+            let last_backup = Backup::new().ok().and_then(|bc| bc.get_last_backup().ok().flatten());
+            match last_backup {
+                Some(backup_dir_path) => {
+                    info!(
+                        "  kofl restore             # bring back the config from the last backup ({})",
+                        backup_dir_path.display()
+                    );
+                }
+                None => info!("  No backup was found to restore from."),
+            }
+            info!("  kofl restore --reseal    # you edited the config yourself, trust it again after entering the master password");
+        }
+
         pub fn update(&self) {
             self.write_config_to_toml_file();
             // Save checksum
-            let checksum = self.get_config_checksum();
-            let checksum_path = self.get_config_path().with_extension("checksum");
-            fs::write(checksum_path, checksum).expect("Failed to write checksum file");
+            self.seal();
         }
 
         pub fn serialize_to_toml(&self) -> String {
@@ -296,40 +408,13 @@ pub mod Config {
 #[cfg(test)]
 mod tests {
     use super::Config::KoflGlobalConfig;
+    use crate::errors::ErrorIntegrity;
     use serial_test::serial;
     use std::path::PathBuf;
     use std::{env, fs};
+    use crate::test_support::EnvGuard;
     use tempfile::TempDir;
 
-    struct EnvGuard {
-        key: &'static str,
-        original: Option<String>,
-    }
-
-    impl EnvGuard {
-        fn new(key: &'static str) -> Self {
-            let original = env::var(key).ok();
-            Self { key, original }
-        }
-
-        fn set_var(&self, value: &str) {
-            env::set_var(self.key, value);
-        }
-
-        fn remove_var(&self) {
-            env::remove_var(self.key);
-        }
-    }
-
-    impl Drop for EnvGuard {
-        fn drop(&mut self) {
-            match &self.original {
-                Some(original) => env::set_var(self.key, original),
-                None => env::remove_var(self.key),
-            }
-        }
-    }
-
     // Add this setup function
     fn setup_test_env() -> EnvGuard {
         let guard = EnvGuard::new("USER");
@@ -388,7 +473,7 @@ mod tests {
         let _guard = EnvGuard::new("USER");
         let temp_dir = TempDir::new().expect("Failed to create temp dir");
 
-        let config = KoflGlobalConfig::new();
+        let config = create_test_config(&temp_dir);
 
         std::fs::write(config.get_config_path(), String::from("test"));
 
@@ -413,6 +498,98 @@ mod tests {
         );
     }
 
+    // Helper that seals a config in the temp directory with a password derived key
+    fn create_sealed_config(temp_dir: &TempDir, master_pwd: &str) -> (KoflGlobalConfig, Vec<u8>) {
+        let mut config = create_test_config(temp_dir);
+        config.set_salt("VkfXMtmXShVXBBkv".to_string());
+        config.set_master_key_provided(true);
+        let mac_key = config.derive_mac_key(master_pwd);
+        config.set_mac_key(mac_key.clone());
+        config.update();
+        (config, mac_key)
+    }
+
+    #[test]
+    fn test_verify_integrity_with_mac() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let (config, mac_key) = create_sealed_config(&temp_dir, "master-password");
+
+        let stored = fs::read_to_string(config.get_config_path().with_extension("checksum")).unwrap();
+        assert!(stored.starts_with("hmac-sha256:"), "Checksum should be a keyed MAC");
+        assert_ne!(stored, config.get_config_checksum(), "MAC must differ from the plain sha256");
+
+        assert_eq!(config.verify_integrity(), Ok(()));
+        assert_eq!(config.verify_mac(&mac_key), Ok(()));
+    }
+
+    #[test]
+    fn test_verify_integrity_detects_tampering() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let (config, mac_key) = create_sealed_config(&temp_dir, "master-password");
+
+        // attacker edits the config but cannot recompute the keyed checksum
+        let edited = fs::read_to_string(config.get_config_path())
+            .unwrap()
+            .replace("kofl.sqlite", "evil.sqlite");
+        fs::write(config.get_config_path(), edited).unwrap();
+
+        assert_eq!(config.verify_integrity(), Ok(()), "Structure is still valid");
+        assert_eq!(config.verify_mac(&mac_key), Err(ErrorIntegrity::Tampered));
+    }
+
+    #[test]
+    fn test_verify_integrity_wrong_key() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let (config, _) = create_sealed_config(&temp_dir, "master-password");
+
+        let other_key = config.derive_mac_key("not-the-master-password");
+        assert_eq!(config.verify_mac(&other_key), Err(ErrorIntegrity::Tampered));
+    }
+
+    #[test]
+    fn test_verify_integrity_corrupted() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let (config, _) = create_sealed_config(&temp_dir, "master-password");
+        let checksum_path = config.get_config_path().with_extension("checksum");
+
+        // half written checksum
+        fs::write(&checksum_path, "hmac-sha256:9f86d0818").unwrap();
+        assert!(matches!(config.verify_integrity(), Err(ErrorIntegrity::Corrupted(_))));
+
+        // garbage config
+        config.seal();
+        create_invalid_config_file(config.get_config_path());
+        assert!(matches!(config.verify_integrity(), Err(ErrorIntegrity::Corrupted(_))));
+    }
+
+    #[test]
+    fn test_legacy_checksum_is_refused() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let (config, mac_key) = create_sealed_config(&temp_dir, "master-password");
+
+        // an attacker edits the config and writes a plain sha256 next to it
+        let edited = fs::read_to_string(config.get_config_path()).unwrap().replace("kofl.sqlite", "evil.sqlite");
+        fs::write(config.get_config_path(), edited).unwrap();
+        fs::write(config.get_config_path().with_extension("checksum"), config.get_config_checksum()).unwrap();
+
+        assert_eq!(config.verify_integrity(), Err(ErrorIntegrity::Unkeyed));
+        assert_eq!(config.verify_mac(&mac_key), Err(ErrorIntegrity::Unkeyed));
+
+        // a plain sha256 that does not match is no upgrade either
+        fs::write(config.get_config_path().with_extension("checksum"), "0".repeat(64)).unwrap();
+        assert_eq!(config.verify_integrity(), Err(ErrorIntegrity::Tampered));
+        assert_eq!(config.verify_on_load(), Err(ErrorIntegrity::Tampered));
+    }
+
+    #[test]
+    fn test_verify_integrity_missing_checksum() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let (config, _) = create_sealed_config(&temp_dir, "master-password");
+
+        fs::remove_file(config.get_config_path().with_extension("checksum")).unwrap();
+        assert_eq!(config.verify_integrity(), Err(ErrorIntegrity::MissingChecksum));
+    }
+
     #[test]
     #[serial]
     fn test_new_config_default_values() {
@@ -694,7 +871,7 @@ mod tests {
 
         // Create new config instance with same temp directory
         let mut new_config = create_test_config(&temp_dir);
-        new_config.load();
+        new_config.load().unwrap();
 
         println!("New config after load: {:?}", new_config);
         println!("New config file path: {:?}", new_config.get_config_path());
@@ -771,7 +948,7 @@ mod tests {
         println!("Initial config: {:?}", config);
 
         // Act
-        config.load();
+        config.load().unwrap();
 
         println!("Config after load: {:?}", config);
 
@@ -810,7 +987,7 @@ mod tests {
 
         // Act
         config.update();
-        config.load();
+        config.load().unwrap();
 
         // Assert
         assert!(
@@ -838,7 +1015,7 @@ mod tests {
 
         // Act
         config.update();
-        config.load();
+        config.load().unwrap();
 
         // Assert
         // Should fall back to default values
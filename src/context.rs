@@ -7,6 +7,7 @@ use rusqlite::Error;
 use std::cell::RefCell;
 use crate::session::Session;
 use crate::session::SessionError;
+use crate::vault::{migrate_legacy_layout, VaultPaths};
 use log::{debug, info, warn, error};
 use colored::*;
 use std::io::Write;
@@ -22,10 +23,41 @@ pub struct Context {
 
 impl Context {
     pub fn new() -> Result<Self, ErrorSetup> {
+        Self::prepare_vault()?;
+
         // Initialize the configuration
         let mut config = KoflGlobalConfig::new();
-        config.load();
+        config.load().map_err(ErrorSetup::Integrity)?;
+
+        Self::setup(config)
+    }
+
+    /// Same as `new` but skips the config integrity check, so that the
+    /// recovery commands can still run on a vault that fails it.
+    pub fn new_unchecked() -> Result<Self, ErrorSetup> {
+        Self::prepare_vault()?;
 
+        let mut config = KoflGlobalConfig::new();
+        config.load_unchecked();
+
+        Self::setup(config)
+    }
+
+    fn prepare_vault() -> Result<(), ErrorSetup> {
+        if let Err(err) = migrate_legacy_layout() {
+            error!("Error moving the kofl files out of the home directory: {}", err);
+            return Err(ErrorSetup::Vault);
+        }
+
+        let vault_paths = VaultPaths::resolve();
+        debug!("using vault {} ({})", vault_paths.name, vault_paths.config_path.display());
+        vault_paths.create_dirs().map_err(|err| {
+            error!("Error creating vault directories: {}", err);
+            ErrorSetup::Vault
+        })
+    }
+
+    fn setup(config: KoflGlobalConfig) -> Result<Self, ErrorSetup> {
         // Wrap the configuration in a RefCell
         let c = RefCell::new(config);
 
@@ -39,7 +71,10 @@ impl Context {
         };
 
         // Initialize the database schema
-        let _ = dbase.initialize();
+        if let Err(err) = dbase.initialize() {
+            error!("Error setting up the database schema: {}", err);
+            return Err(ErrorSetup::DataBase);
+        }
 
         // Initialize or load the session
         let user_login = match std::env::var("USER") {
@@ -0,0 +1,103 @@
+use crate::errors::ErrorExecution;
+use age::armor::{ArmoredReader, ArmoredWriter, Format};
+use age::secrecy::SecretString;
+use aes::cipher::{generic_array::GenericArray, KeyIvInit, StreamCipher};
+use ctr::Ctr32BE;
+use hmac::{Hmac, Mac};
+use log::error;
+use sha2::Sha256;
+use std::io::{Read, Write};
+
+type Aes256Ctr = Ctr32BE<aes::Aes256>;
+type HmacSha256 = Hmac<Sha256>;
+
+const SECRET_MAC_CONTEXT: &[u8] = b"kofl-entry-secret-mac";
+
+fn new_cipher(master_key_hash: &str) -> Result<Aes256Ctr, ErrorExecution> {
+    let master_key_bytes = hex::decode(master_key_hash).map_err(|e| {
+        error!("Error decoding master key hash: {}", e);
+        ErrorExecution::EncryptionError
+    })?;
+
+    if master_key_bytes.len() != 32 {
+        error!("Master key hash has an unexpected length");
+        return Err(ErrorExecution::EncryptionError);
+    }
+
+    // Create key and nonce
+    let key = GenericArray::from_slice(&master_key_bytes);
+    let nonce = GenericArray::from_slice(&[0u8; 16]); // In production, use secure random nonce
+
+    Ok(Aes256Ctr::new(key, nonce))
+}
+
+/// Encrypts an entry secret with the master key, returns it hex encoded for storage.
+pub fn encrypt_secret(master_key_hash: &str, secret: &str) -> Result<String, ErrorExecution> {
+    let mut cipher = new_cipher(master_key_hash)?;
+
+    let mut encrypted_password = secret.as_bytes().to_vec();
+    cipher.apply_keystream(&mut encrypted_password);
+
+    Ok(hex::encode(encrypted_password))
+}
+
+/// Decrypts a hex encoded entry secret stored by `encrypt_secret`.
+pub fn decrypt_secret(master_key_hash: &str, encrypted_hex: &str) -> Result<String, ErrorExecution> {
+    let mut cipher = new_cipher(master_key_hash).map_err(|_| ErrorExecution::DecryptionError)?;
+
+    let mut password = hex::decode(encrypted_hex).map_err(|_| ErrorExecution::DecryptionError)?;
+    cipher.apply_keystream(&mut password);
+
+    String::from_utf8(password).map_err(|_| ErrorExecution::DecryptionError)
+}
+
+/// Keyed hash of an entry secret, equal secrets give equal hashes so reuse can
+/// be found without decrypting every entry. The key is derived from the master
+/// key hash, which is stored in the config just like for `encrypt_secret`: the
+/// hashes only resist a dictionary attack while the config stays private.
+pub fn secret_mac(master_key_hash: &str, secret: &str) -> Result<String, ErrorExecution> {
+    let master_key_bytes = hex::decode(master_key_hash).map_err(|_| ErrorExecution::EncryptionError)?;
+    if master_key_bytes.len() != 32 {
+        return Err(ErrorExecution::EncryptionError);
+    }
+
+    let mut key = HmacSha256::new_from_slice(&master_key_bytes).expect("HMAC can take a key of any size");
+    key.update(SECRET_MAC_CONTEXT);
+    let key = key.finalize().into_bytes();
+
+    let mut mac = HmacSha256::new_from_slice(&key).expect("HMAC can take a key of any size");
+    mac.update(secret.as_bytes());
+    Ok(hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Encrypts data with a passphrase in the armored age format, so it can also be
+/// decrypted with `age -d` without kofl.
+pub fn encrypt_with_passphrase(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>, ErrorExecution> {
+    let encryptor = age::Encryptor::with_user_passphrase(SecretString::from(passphrase.to_string()));
+    let armor = ArmoredWriter::wrap_output(Vec::new(), Format::AsciiArmor)?;
+    let mut writer = encryptor.wrap_output(armor)?;
+    writer.write_all(plaintext)?;
+    Ok(writer.finish()?.finish()?)
+}
+
+/// Decrypts data written by `encrypt_with_passphrase` or `age -p`, armored or not.
+pub fn decrypt_with_passphrase(encrypted: &[u8], passphrase: &str) -> Result<Vec<u8>, ErrorExecution> {
+    let decryptor = age::Decryptor::new(ArmoredReader::new(encrypted)).map_err(|_| ErrorExecution::DecryptionError)?;
+    if !decryptor.is_scrypt() {
+        error!("The file is not encrypted with a passphrase");
+        return Err(ErrorExecution::DecryptionError);
+    }
+
+    let identity = age::scrypt::Identity::new(SecretString::from(passphrase.to_string()));
+    let mut reader = decryptor
+        .decrypt(std::iter::once(&identity as &dyn age::Identity))
+        .map_err(|_| ErrorExecution::AuthenticationFailed)?;
+    let mut plaintext = Vec::new();
+    reader.read_to_end(&mut plaintext).map_err(|_| ErrorExecution::DecryptionError)?;
+    Ok(plaintext)
+}
+
+/// Whether the data looks like an age file, armored or binary.
+pub fn is_passphrase_encrypted(data: &[u8]) -> bool {
+    data.starts_with(b"age-encryption.org/") || data.trim_ascii_start().starts_with(b"-----BEGIN AGE ENCRYPTED FILE-----")
+}
@@ -1,7 +1,80 @@
 pub mod Db {
+    use chrono::{DateTime, Utc};
     use rusqlite::{params, Connection, Result};
     use std::path::PathBuf;
-    
+
+    /// Version of the schema created by `initialize`, stored in `PRAGMA user_version`.
+    pub const SCHEMA_VERSION: i32 = 8;
+
+    const ENTRY_COLUMNS: &str = "id, ent_name, password_hash, created_at, updated_at, last_accessed_at, max_age_days, username, url, notes, policy, secret_mac";
+
+    // schema changes in order, the first one brings a version 1 database to version 2
+    const MIGRATIONS: &[&str] = &[
+        // 2: previous secrets of every entry
+        "
+        CREATE TABLE IF NOT EXISTS entry_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            entry_id INTEGER NOT NULL REFERENCES entry(id) ON DELETE CASCADE,
+            version INTEGER NOT NULL,
+            password_hash TEXT NOT NULL,
+            timestamp TEXT NOT NULL,
+            replaced_at TEXT NOT NULL,
+            UNIQUE (entry_id, version)
+        );
+        ",
+        // 3: typed timestamps instead of the single free-form `timestamp` column,
+        // rows whose timestamp does not parse are dated from the migration
+        "
+        CREATE TABLE entry_new (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            ent_name TEXT NOT NULL,
+            password_hash TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            last_accessed_at TEXT
+        );
+        INSERT INTO entry_new (id, ent_name, password_hash, created_at, updated_at)
+            SELECT id, ent_name, password_hash,
+                COALESCE(strftime('%Y-%m-%d %H:%M:%f+00:00', timestamp), strftime('%Y-%m-%d %H:%M:%f+00:00', 'now')),
+                COALESCE(strftime('%Y-%m-%d %H:%M:%f+00:00', timestamp), strftime('%Y-%m-%d %H:%M:%f+00:00', 'now'))
+            FROM entry;
+        DROP TABLE entry;
+        ALTER TABLE entry_new RENAME TO entry;
+        ALTER TABLE entry_history RENAME COLUMN timestamp TO set_at;
+        ",
+        // 4: per-entry override of the default maximum password age
+        "
+        ALTER TABLE entry ADD COLUMN max_age_days INTEGER;
+        ",
+        // 5: free-form tags, many-to-many with entries
+        "
+        CREATE TABLE IF NOT EXISTS tag (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE
+        );
+        CREATE TABLE IF NOT EXISTS entry_tag (
+            entry_id INTEGER NOT NULL REFERENCES entry(id) ON DELETE CASCADE,
+            tag_id INTEGER NOT NULL REFERENCES tag(id) ON DELETE CASCADE,
+            PRIMARY KEY (entry_id, tag_id)
+        );
+        ",
+        // 6: searchable metadata next to the secret
+        "
+        ALTER TABLE entry ADD COLUMN username TEXT;
+        ALTER TABLE entry ADD COLUMN url TEXT;
+        ALTER TABLE entry ADD COLUMN notes TEXT;
+        ",
+        // 7: password policy the entry is checked against, the default one when NULL
+        "
+        ALTER TABLE entry ADD COLUMN policy TEXT;
+        ",
+        // 8: keyed hash of the secret to find reused passwords without decrypting,
+        // NULL until the entry is written again or audited
+        "
+        ALTER TABLE entry ADD COLUMN secret_mac TEXT;
+        CREATE INDEX IF NOT EXISTS entry_secret_mac ON entry (secret_mac);
+        ",
+    ];
 
 
     #[warn(unused_variables)]
@@ -14,9 +87,9 @@ pub mod Db {
     impl Database {
         pub fn new(path :&PathBuf) -> Result<Self, rusqlite::Error>
         {
-            Ok(Database {
-                connection: Connection::open(path)?
-            })
+            let connection = Connection::open(path)?;
+            connection.pragma_update(None, "foreign_keys", true)?;
+            Ok(Database { connection })
         }
         // set up the db schema
         pub fn initialize(&self) -> Result<(), rusqlite::Error> {
@@ -41,50 +114,127 @@ pub mod Db {
                 "
             )?;
 
+            self.migrate()
+        }
+
+        fn migrate(&self) -> Result<(), rusqlite::Error> {
+            // the tables above are version 1, a fresh database reports 0
+            let version = self.schema_version()?.max(1);
+
+            // rebuilding a table must not cascade to the rows referencing it
+            self.connection.pragma_update(None, "foreign_keys", false)?;
+            let migrated = self.apply_migrations(version);
+            // back on even when a migration failed, the connection is still used
+            self.connection.pragma_update(None, "foreign_keys", true)?;
+            migrated?;
+
+            if self.schema_version()? == 0 {
+                self.set_schema_version(SCHEMA_VERSION)?;
+            }
+            Ok(())
+        }
+
+        fn apply_migrations(&self, version: i32) -> Result<(), rusqlite::Error> {
+            for (index, migration) in MIGRATIONS.iter().enumerate() {
+                let target = index as i32 + 2;
+                if version < target {
+                    let tx = self.connection.unchecked_transaction()?;
+                    tx.execute_batch(migration)?;
+                    tx.pragma_update(None, "user_version", target)?;
+                    tx.commit()?;
+                }
+            }
             Ok(())
         }
-        pub fn add_entry(&self, entry: Entry) -> Result<(), rusqlite::Error> {
+
+        pub fn schema_version(&self) -> Result<i32, rusqlite::Error> {
+            self.connection.query_row("PRAGMA user_version", [], |row| row.get(0))
+        }
+
+        pub fn set_schema_version(&self, version: i32) -> Result<(), rusqlite::Error> {
+            self.connection.pragma_update(None, "user_version", version)
+        }
+
+        /// Runs sqlite's own consistency check, a healthy database returns `["ok"]`.
+        pub fn integrity_check(&self) -> Result<Vec<String>, rusqlite::Error> {
+            let mut stmt = self.connection.prepare("PRAGMA integrity_check")?;
+            let rows = stmt.query_map([], |row| row.get(0))?;
+            rows.collect()
+        }
+        /// Inserts the entry and returns its id.
+        pub fn add_entry(&self, entry: Entry) -> Result<u32, rusqlite::Error> {
             self.connection.execute(
-                "INSERT INTO entry (ent_name, password_hash, timestamp) VALUES (?1, ?2, ?3)",
-                params![entry.ent_name, entry.password_hash, entry.timestamp],
+                "INSERT INTO entry (ent_name, password_hash, created_at, updated_at, last_accessed_at, max_age_days, username, url, notes, policy, secret_mac)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                params![
+                    entry.ent_name, entry.password_hash, entry.created_at, entry.updated_at, entry.last_accessed_at,
+                    entry.max_age_days, entry.username, entry.url, entry.notes, entry.policy, entry.secret_mac
+                ],
             )?;
-            Ok(())
+            Ok(self.connection.last_insert_rowid() as u32)
+        }
+
+        pub fn add_entry_with_tags(&self, entry: Entry, tags: &[String]) -> Result<u32, rusqlite::Error> {
+            self.atomically(|| {
+                let id = self.add_entry(entry)?;
+                for tag in tags {
+                    self.add_tag(id, tag)?;
+                }
+                Ok(id)
+            })
         }
 
         pub fn get_entry_by_name(&self, ent_name: &str) -> Result<Entry, rusqlite::Error> {
-            let mut stmt = self.connection.prepare("SELECT id, ent_name, password_hash, timestamp FROM entry WHERE ent_name = ?1")?;
-            let entry = stmt.query_row(params![ent_name], |row| {
-                Ok(Entry {
-                    id: row.get(0)?,
-                    ent_name: row.get(1)?,
-                    password_hash: row.get(2)?,
-                    timestamp: row.get(3)?,
-                })
-            })?;
+            let mut stmt = self.connection.prepare(&format!("SELECT {} FROM entry WHERE ent_name = ?1", ENTRY_COLUMNS))?;
+            let entry = stmt.query_row(params![ent_name], Entry::from_row)?;
             Ok(entry)
         }
 
         pub fn get_entry(&self, id: u32) -> Result<Entry, rusqlite::Error> {
-            let mut stmt = self.connection.prepare("SELECT id, ent_name, password_hash, timestamp FROM entry WHERE id = ?1")?;
-            let entry = stmt.query_row(params![id], |row| {
-                Ok(Entry {
-                    id: row.get(0)?,
-                    ent_name: row.get(1)?,
-                    password_hash: row.get(2)?,
-                    timestamp: row.get(3)?,
-                })
-            })?;
+            let mut stmt = self.connection.prepare(&format!("SELECT {} FROM entry WHERE id = ?1", ENTRY_COLUMNS))?;
+            let entry = stmt.query_row(params![id], Entry::from_row)?;
             Ok(entry)
         }
 
         pub fn update_entry(&self, id: u32, entry: Entry) -> Result<(), rusqlite::Error> {
             self.connection.execute(
-                "UPDATE entry SET ent_name = ?1, password_hash = ?2, timestamp = ?3 WHERE id = ?4",
-                params![entry.ent_name, entry.password_hash, entry.timestamp, id],
+                "UPDATE entry SET ent_name = ?1, password_hash = ?2, updated_at = ?3, max_age_days = ?4, username = ?5, url = ?6, notes = ?7,
+                 policy = ?8, secret_mac = ?9 WHERE id = ?10",
+                params![
+                    entry.ent_name, entry.password_hash, entry.updated_at, entry.max_age_days,
+                    entry.username, entry.url, entry.notes, entry.policy, entry.secret_mac, id
+                ],
             )?;
             Ok(())
         }
 
+        /// Records that the secret of an entry was read.
+        pub fn touch_entry(&self, id: u32) -> Result<(), rusqlite::Error> {
+            self.connection.execute(
+                "UPDATE entry SET last_accessed_at = ?1 WHERE id = ?2",
+                params![Utc::now(), id],
+            )?;
+            Ok(())
+        }
+
+        /// Stores the keyed hash of an entry written before reuse detection.
+        pub fn set_secret_mac(&self, id: u32, secret_mac: &str) -> Result<(), rusqlite::Error> {
+            self.connection.execute("UPDATE entry SET secret_mac = ?1 WHERE id = ?2", params![secret_mac, id])?;
+            Ok(())
+        }
+
+        /// Names of the entries whose secret has this keyed hash.
+        pub fn find_entries_by_secret_mac(&self, secret_mac: &str) -> Result<Vec<String>, rusqlite::Error> {
+            let mut stmt = self.connection.prepare("SELECT ent_name FROM entry WHERE secret_mac = ?1 ORDER BY ent_name")?;
+            let rows = stmt.query_map(params![secret_mac], |row| row.get(0))?;
+            rows.collect()
+        }
+
+        /// Number of entries without a keyed hash, invisible to reuse detection.
+        pub fn count_entries_without_secret_mac(&self) -> Result<u32, rusqlite::Error> {
+            self.connection.query_row("SELECT COUNT(*) FROM entry WHERE secret_mac IS NULL", [], |row| row.get(0))
+        }
+
         pub fn delete_entry(&self, id: u32) -> Result<(), rusqlite::Error> {
             self.connection.execute("DELETE FROM entry WHERE id = ?1", params![id])?;
             Ok(())
@@ -98,15 +248,8 @@ pub mod Db {
         }
 
         pub fn list_entries(&self) -> Result<Vec<Entry>, rusqlite::Error> {
-            let mut stmt = self.connection.prepare("SELECT id, ent_name, password_hash, timestamp FROM entry")?;
-            let entry_iter = stmt.query_map([], |row| {
-                Ok(Entry {
-                    id: row.get(0)?,
-                    ent_name: row.get(1)?,
-                    password_hash: row.get(2)?,
-                    timestamp: row.get(3)?,
-                })
-            })?;
+            let mut stmt = self.connection.prepare(&format!("SELECT {} FROM entry ORDER BY ent_name", ENTRY_COLUMNS))?;
+            let entry_iter = stmt.query_map([], Entry::from_row)?;
     
             let mut entries = Vec::new();
             for entry in entry_iter {
@@ -116,13 +259,148 @@ pub mod Db {
         }
 
 
+        /// Archives the current secret of an entry then replaces it, keeping at
+        /// most `keep` previous versions.
+        pub fn update_entry_with_history(&self, current: &Entry, entry: Entry, keep: u32) -> Result<(), rusqlite::Error> {
+            self.atomically(|| self.archive_and_update(current, entry, keep))
+        }
+
+        /// Appends a previous secret to the history of an entry, keeping at most
+        /// `keep` versions.
+        pub fn add_history(
+            &self,
+            entry_id: u32,
+            password_hash: &str,
+            set_at: DateTime<Utc>,
+            replaced_at: DateTime<Utc>,
+            keep: u32,
+        ) -> Result<(), rusqlite::Error> {
+            if keep > 0 {
+                self.connection.execute(
+                    "INSERT INTO entry_history (entry_id, version, password_hash, set_at, replaced_at)
+                     VALUES (?1, (SELECT COALESCE(MAX(version), 0) + 1 FROM entry_history WHERE entry_id = ?1), ?2, ?3, ?4)",
+                    params![entry_id, password_hash, set_at, replaced_at],
+                )?;
+            }
+            self.connection.execute(
+                "DELETE FROM entry_history WHERE entry_id = ?1 AND id NOT IN
+                    (SELECT id FROM entry_history WHERE entry_id = ?1 ORDER BY version DESC LIMIT ?2)",
+                params![entry_id, keep],
+            )?;
+            Ok(())
+        }
+
+        fn archive_and_update(&self, current: &Entry, entry: Entry, keep: u32) -> Result<(), rusqlite::Error> {
+            self.add_history(current.id, &current.password_hash, current.updated_at, entry.updated_at, keep)?;
+            self.connection.execute(
+                "UPDATE entry SET ent_name = ?1, password_hash = ?2, updated_at = ?3, max_age_days = ?4, username = ?5, url = ?6, notes = ?7,
+                 policy = ?8, secret_mac = ?9 WHERE id = ?10",
+                params![
+                    entry.ent_name, entry.password_hash, entry.updated_at, entry.max_age_days,
+                    entry.username, entry.url, entry.notes, entry.policy, entry.secret_mac, current.id
+                ],
+            )?;
+            Ok(())
+        }
+
+        /// Starts a transaction covering the following calls, rolled back when dropped
+        /// without being committed.
+        pub fn transaction(&self) -> Result<rusqlite::Transaction<'_>, rusqlite::Error> {
+            self.connection.unchecked_transaction()
+        }
+
+        // runs `f` in its own transaction, or in the caller's one if there is already one
+        fn atomically<T>(&self, f: impl FnOnce() -> Result<T, rusqlite::Error>) -> Result<T, rusqlite::Error> {
+            if !self.connection.is_autocommit() {
+                return f();
+            }
+            let tx = self.connection.unchecked_transaction()?;
+            let value = f()?;
+            tx.commit()?;
+            Ok(value)
+        }
+
+        pub fn list_history(&self, entry_id: u32) -> Result<Vec<HistoryEntry>, rusqlite::Error> {
+            let mut stmt = self.connection.prepare(
+                "SELECT entry_id, version, password_hash, set_at, replaced_at FROM entry_history
+                 WHERE entry_id = ?1 ORDER BY version",
+            )?;
+            let history_iter = stmt.query_map(params![entry_id], HistoryEntry::from_row)?;
+            history_iter.collect()
+        }
+
+        pub fn get_history_version(&self, entry_id: u32, version: u32) -> Result<HistoryEntry, rusqlite::Error> {
+            self.connection.query_row(
+                "SELECT entry_id, version, password_hash, set_at, replaced_at FROM entry_history
+                 WHERE entry_id = ?1 AND version = ?2",
+                params![entry_id, version],
+                HistoryEntry::from_row,
+            )
+        }
+
+        /// Tags an entry, returns false when it already had the tag.
+        pub fn add_tag(&self, entry_id: u32, tag: &str) -> Result<bool, rusqlite::Error> {
+            self.connection.execute("INSERT OR IGNORE INTO tag (name) VALUES (?1)", params![tag])?;
+            let added = self.connection.execute(
+                "INSERT OR IGNORE INTO entry_tag (entry_id, tag_id) SELECT ?1, id FROM tag WHERE name = ?2",
+                params![entry_id, tag],
+            )?;
+            Ok(added > 0)
+        }
+
+        /// Removes a tag from an entry, returns false when the entry did not have it.
+        pub fn remove_tag(&self, entry_id: u32, tag: &str) -> Result<bool, rusqlite::Error> {
+            let removed = self.connection.execute(
+                "DELETE FROM entry_tag WHERE entry_id = ?1 AND tag_id = (SELECT id FROM tag WHERE name = ?2)",
+                params![entry_id, tag],
+            )?;
+            // tags only live as long as an entry uses them
+            self.connection.execute(
+                "DELETE FROM tag WHERE id NOT IN (SELECT tag_id FROM entry_tag)",
+                [],
+            )?;
+            Ok(removed > 0)
+        }
+
+        pub fn list_tags(&self, entry_id: u32) -> Result<Vec<String>, rusqlite::Error> {
+            let mut stmt = self.connection.prepare(
+                "SELECT tag.name FROM tag JOIN entry_tag ON entry_tag.tag_id = tag.id
+                 WHERE entry_tag.entry_id = ?1 ORDER BY tag.name",
+            )?;
+            let tag_iter = stmt.query_map(params![entry_id], |row| row.get(0))?;
+            tag_iter.collect()
+        }
+
+        pub fn list_entries_with_tag(&self, tag: &str) -> Result<Vec<Entry>, rusqlite::Error> {
+            let mut stmt = self.connection.prepare(&format!(
+                "SELECT {} FROM entry WHERE id IN
+                    (SELECT entry_tag.entry_id FROM entry_tag JOIN tag ON tag.id = entry_tag.tag_id WHERE tag.name = ?1)
+                 ORDER BY ent_name",
+                ENTRY_COLUMNS
+            ))?;
+            let entry_iter = stmt.query_map(params![tag], Entry::from_row)?;
+            entry_iter.collect()
+        }
+
+        /// Entries of a folder and of its sub folders.
+        pub fn list_entries_in_folder(&self, folder: &str) -> Result<Vec<Entry>, rusqlite::Error> {
+            let prefix = format!("{}/", folder.trim_end_matches('/'));
+            let mut stmt = self.connection.prepare(&format!(
+                "SELECT {} FROM entry WHERE substr(ent_name, 1, length(?1)) = ?1 ORDER BY ent_name",
+                ENTRY_COLUMNS
+            ))?;
+            let entry_iter = stmt.query_map(params![prefix], Entry::from_row)?;
+            entry_iter.collect()
+        }
+
         pub fn list_settings(&self) -> Result<Vec<Setting>, rusqlite::Error> {
             let mut stmt = self.connection.prepare("SELECT key, value, description from settings")?;
             let setting_iter = stmt.query_map([], |row| {
                 Ok(Setting {
                     key: row.get(0)?,
                     value: row.get(1)?,
-                    desc: row.get(2)?,
+                    // rows written by set_setting_value have no description
+                    desc: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
                 })
             })?;
     
@@ -160,23 +438,95 @@ pub mod Db {
     
     }
 
+    #[derive(Debug, Clone)]
     pub struct Entry {
         pub id: u32,
         pub ent_name: String, 
         pub password_hash: String,
-        pub timestamp: String,
+        pub created_at: DateTime<Utc>,
+        pub updated_at: DateTime<Utc>,
+        pub last_accessed_at: Option<DateTime<Utc>>,
+        /// Overrides the `default_max_age` setting, 0 means the entry never expires.
+        pub max_age_days: Option<u32>,
+        pub username: Option<String>,
+        pub url: Option<String>,
+        pub notes: Option<String>,
+        /// Name of the password policy, the default one when `None`.
+        pub policy: Option<String>,
+        /// Keyed hash of the secret, see `crypto::secret_mac`.
+        pub secret_mac: Option<String>,
     }
 
     impl Entry {
         pub fn new(id: u32, ent_name: String, password_hash: String) -> Self
         {
+            let now = Utc::now();
             Entry {
                 id,
                 ent_name,
                 password_hash,
-                timestamp: String::from("12-12-12")
+                created_at: now,
+                updated_at: now,
+                last_accessed_at: None,
+                max_age_days: None,
+                username: None,
+                url: None,
+                notes: None,
+                policy: None,
+                secret_mac: None,
+            }
+        }
+
+        /// Date after which the password should be rotated, `None` when it never expires.
+        /// A maximum age past the dates chrono can hold never expires either, such
+        /// values can only come from a database written before `--max-age` was bounded.
+        pub fn due_at(&self, default_max_age: u32) -> Option<DateTime<Utc>> {
+            match self.max_age_days.unwrap_or(default_max_age) {
+                0 => None,
+                days => self.updated_at.checked_add_signed(chrono::Duration::days(days as i64)),
             }
         }
+
+        pub fn is_stale_at(&self, default_max_age: u32, now: DateTime<Utc>) -> bool {
+            self.due_at(default_max_age).map_or(false, |due| due <= now)
+        }
+
+        fn from_row(row: &rusqlite::Row) -> Result<Self, rusqlite::Error> {
+            Ok(Entry {
+                id: row.get(0)?,
+                ent_name: row.get(1)?,
+                password_hash: row.get(2)?,
+                created_at: row.get(3)?,
+                updated_at: row.get(4)?,
+                last_accessed_at: row.get(5)?,
+                max_age_days: row.get(6)?,
+                username: row.get(7)?,
+                url: row.get(8)?,
+                notes: row.get(9)?,
+                policy: row.get(10)?,
+                secret_mac: row.get(11)?,
+            })
+        }
+    }
+
+    pub struct HistoryEntry {
+        pub entry_id: u32,
+        pub version: u32,
+        pub password_hash: String,
+        pub set_at: DateTime<Utc>,
+        pub replaced_at: DateTime<Utc>,
+    }
+
+    impl HistoryEntry {
+        fn from_row(row: &rusqlite::Row) -> Result<Self, rusqlite::Error> {
+            Ok(HistoryEntry {
+                entry_id: row.get(0)?,
+                version: row.get(1)?,
+                password_hash: row.get(2)?,
+                set_at: row.get(3)?,
+                replaced_at: row.get(4)?,
+            })
+        }
     }
 
     pub struct Setting {
@@ -196,4 +546,260 @@ pub mod Db {
         }
     }
 
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::Db::*;
+    use tempfile::TempDir;
+
+    fn open_test_db(temp_dir: &TempDir) -> Database {
+        let db = Database::new(&temp_dir.path().join("kofl.sqlite")).unwrap();
+        db.initialize().unwrap();
+        db
+    }
+
+    fn add_test_entry(db: &Database, name: &str, secret: &str) -> Entry {
+        db.add_entry(Entry::new(0, name.to_string(), secret.to_string())).unwrap();
+        db.get_entry_by_name(name).unwrap()
+    }
+
+    fn update_test_entry(db: &Database, name: &str, secret: &str, keep: u32) {
+        let current = db.get_entry_by_name(name).unwrap();
+        let entry = Entry {
+            password_hash: secret.to_string(),
+            updated_at: chrono::Utc::now(),
+            ..current.clone()
+        };
+        db.update_entry_with_history(&current, entry, keep).unwrap();
+    }
+
+    #[test]
+    fn test_initialize_migrates_version_1() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("kofl.sqlite");
+        {
+            let db = Database::new(&path).unwrap();
+            db.connection
+                .execute_batch(
+                    "CREATE TABLE entry (id INTEGER PRIMARY KEY AUTOINCREMENT, ent_name TEXT NOT NULL,
+                     password_hash TEXT NOT NULL, timestamp TEXT NOT NULL);
+                     INSERT INTO entry (ent_name, password_hash, timestamp) VALUES
+                        ('github', 'v1', '2024-03-01T10:20:30.123456+00:00'),
+                        ('legacy', 'v1', '12-12-12');",
+                )
+                .unwrap();
+            db.set_schema_version(1).unwrap();
+        }
+
+        let db = open_test_db(&temp_dir);
+        assert_eq!(db.schema_version().unwrap(), SCHEMA_VERSION);
+
+        let github = db.get_entry_by_name("github").unwrap();
+        assert_eq!(github.created_at.to_rfc3339(), "2024-03-01T10:20:30.123+00:00");
+        assert_eq!(github.updated_at, github.created_at);
+        assert!(github.last_accessed_at.is_none());
+        assert!(db.list_history(github.id).unwrap().is_empty());
+
+        // the placeholder timestamp is replaced by the migration date
+        let legacy = db.get_entry_by_name("legacy").unwrap();
+        assert!(chrono::Utc::now() - legacy.created_at < chrono::Duration::minutes(1));
+    }
+
+    #[test]
+    fn test_failed_migration_keeps_foreign_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("kofl.sqlite");
+        let db = Database::new(&path).unwrap();
+        db.connection
+            .execute_batch(
+                "CREATE TABLE entry (id INTEGER PRIMARY KEY AUTOINCREMENT, ent_name TEXT NOT NULL,
+                 password_hash TEXT NOT NULL, timestamp TEXT NOT NULL);
+                 CREATE TABLE entry_new (id INTEGER PRIMARY KEY);",
+            )
+            .unwrap();
+        db.set_schema_version(1).unwrap();
+
+        // migration 3 cannot create entry_new, the ones before it stay applied
+        assert!(db.initialize().is_err());
+        assert_eq!(db.schema_version().unwrap(), 2);
+        let foreign_keys: bool = db.connection.query_row("PRAGMA foreign_keys", [], |row| row.get(0)).unwrap();
+        assert!(foreign_keys);
+    }
+
+    #[test]
+    fn test_timestamps_follow_updates_and_reads() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = open_test_db(&temp_dir);
+        let entry = add_test_entry(&db, "github", "v1");
+        assert_eq!(entry.created_at, entry.updated_at);
+
+        update_test_entry(&db, "github", "v2", 10);
+        let updated = db.get_entry(entry.id).unwrap();
+        assert_eq!(updated.created_at, entry.created_at);
+        assert!(updated.updated_at > entry.updated_at);
+        assert_eq!(db.list_history(entry.id).unwrap()[0].set_at, entry.updated_at);
+
+        db.touch_entry(entry.id).unwrap();
+        assert!(db.get_entry(entry.id).unwrap().last_accessed_at.is_some());
+    }
+
+    #[test]
+    fn test_update_keeps_previous_versions() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = open_test_db(&temp_dir);
+        let entry = add_test_entry(&db, "github", "v1");
+
+        update_test_entry(&db, "github", "v2", 10);
+        update_test_entry(&db, "github", "v3", 10);
+
+        let history = db.list_history(entry.id).unwrap();
+        let versions: Vec<(u32, &str)> = history.iter().map(|h| (h.version, h.password_hash.as_str())).collect();
+        assert_eq!(versions, vec![(1, "v1"), (2, "v2")]);
+        assert_eq!(db.get_history_version(entry.id, 1).unwrap().password_hash, "v1");
+        assert_eq!(db.get_entry_by_name("github").unwrap().password_hash, "v3");
+    }
+
+    #[test]
+    fn test_history_is_capped() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = open_test_db(&temp_dir);
+        let entry = add_test_entry(&db, "github", "v1");
+
+        for secret in ["v2", "v3", "v4", "v5"] {
+            update_test_entry(&db, "github", secret, 2);
+        }
+
+        let history = db.list_history(entry.id).unwrap();
+        let versions: Vec<u32> = history.iter().map(|h| h.version).collect();
+        assert_eq!(versions, vec![3, 4]);
+        assert!(matches!(
+            db.get_history_version(entry.id, 1),
+            Err(rusqlite::Error::QueryReturnedNoRows)
+        ));
+
+        update_test_entry(&db, "github", "v6", 0);
+        assert!(db.list_history(entry.id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_entry_expiry() {
+        let mut entry = Entry::new(0, "github".to_string(), "v1".to_string());
+        let now = entry.updated_at;
+
+        assert!(!entry.is_stale_at(90, now + chrono::Duration::days(89)));
+        assert!(entry.is_stale_at(90, now + chrono::Duration::days(90)));
+        assert!(!entry.is_stale_at(0, now + chrono::Duration::days(1000)));
+
+        // the per-entry value wins over the default, 0 never expires
+        entry.max_age_days = Some(30);
+        assert!(entry.is_stale_at(90, now + chrono::Duration::days(31)));
+        entry.max_age_days = Some(0);
+        assert_eq!(entry.due_at(90), None);
+        entry.max_age_days = Some(u32::MAX);
+        assert_eq!(entry.due_at(90), None);
+        assert!(!entry.is_stale_at(90, now));
+    }
+
+    #[test]
+    fn test_max_age_is_stored() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = open_test_db(&temp_dir);
+        let mut entry = Entry::new(0, "bank".to_string(), "v1".to_string());
+        entry.max_age_days = Some(30);
+        db.add_entry(entry).unwrap();
+
+        assert_eq!(db.get_entry_by_name("bank").unwrap().max_age_days, Some(30));
+        assert_eq!(add_test_entry(&db, "github", "v1").max_age_days, None);
+    }
+
+    #[test]
+    fn test_policy_is_stored() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = open_test_db(&temp_dir);
+        let mut entry = Entry::new(0, "bank".to_string(), "v1".to_string());
+        entry.policy = Some("bank".to_string());
+        db.add_entry(entry).unwrap();
+        assert_eq!(db.get_entry_by_name("bank").unwrap().policy.as_deref(), Some("bank"));
+
+        update_test_entry(&db, "bank", "v2", 10);
+        assert_eq!(db.get_entry_by_name("bank").unwrap().policy.as_deref(), Some("bank"));
+        assert_eq!(add_test_entry(&db, "github", "v1").policy, None);
+    }
+
+    #[test]
+    fn test_find_entries_by_secret_mac() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = open_test_db(&temp_dir);
+        for name in ["mail", "forum"] {
+            let mut entry = Entry::new(0, name.to_string(), "v1".to_string());
+            entry.secret_mac = Some("mac1".to_string());
+            db.add_entry(entry).unwrap();
+        }
+        let bank = add_test_entry(&db, "bank", "v1");
+        assert_eq!(db.count_entries_without_secret_mac().unwrap(), 1);
+
+        db.set_secret_mac(bank.id, "mac2").unwrap();
+        assert_eq!(db.count_entries_without_secret_mac().unwrap(), 0);
+        assert_eq!(db.find_entries_by_secret_mac("mac1").unwrap(), vec!["forum", "mail"]);
+
+        // an update carries the keyed hash of the new secret
+        let current = db.get_entry_by_name("mail").unwrap();
+        let entry = Entry { secret_mac: Some("mac2".to_string()), ..current.clone() };
+        db.update_entry_with_history(&current, entry, 10).unwrap();
+        assert_eq!(db.find_entries_by_secret_mac("mac1").unwrap(), vec!["forum"]);
+        assert_eq!(db.find_entries_by_secret_mac("mac2").unwrap(), vec!["bank", "mail"]);
+    }
+
+    #[test]
+    fn test_tags() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = open_test_db(&temp_dir);
+        let id = db
+            .add_entry_with_tags(Entry::new(0, "work/aws/prod".to_string(), "v1".to_string()), &["cloud".to_string()])
+            .unwrap();
+        let other = add_test_entry(&db, "personal/mail", "v1");
+
+        assert!(db.add_tag(id, "prod").unwrap());
+        assert!(!db.add_tag(id, "prod").unwrap());
+        assert!(db.add_tag(other.id, "cloud").unwrap());
+        assert_eq!(db.list_tags(id).unwrap(), vec!["cloud", "prod"]);
+
+        let names = |entries: Vec<Entry>| entries.into_iter().map(|e| e.ent_name).collect::<Vec<_>>();
+        assert_eq!(names(db.list_entries_with_tag("cloud").unwrap()), vec!["personal/mail", "work/aws/prod"]);
+
+        assert!(db.remove_tag(id, "prod").unwrap());
+        assert!(!db.remove_tag(id, "prod").unwrap());
+        assert!(db.list_entries_with_tag("prod").unwrap().is_empty());
+
+        db.delete_entry(id).unwrap();
+        assert_eq!(names(db.list_entries_with_tag("cloud").unwrap()), vec!["personal/mail"]);
+    }
+
+    #[test]
+    fn test_list_entries_in_folder() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = open_test_db(&temp_dir);
+        for name in ["work/aws/prod", "work/aws/staging", "work/github", "workshop", "personal/mail"] {
+            add_test_entry(&db, name, "v1");
+        }
+
+        let names = |entries: Vec<Entry>| entries.into_iter().map(|e| e.ent_name).collect::<Vec<_>>();
+        assert_eq!(
+            names(db.list_entries_in_folder("work").unwrap()),
+            vec!["work/aws/prod", "work/aws/staging", "work/github"]
+        );
+        assert_eq!(names(db.list_entries_in_folder("work/aws/").unwrap()), vec!["work/aws/prod", "work/aws/staging"]);
+        assert!(db.list_entries_in_folder("work/aw").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_history_removed_with_entry() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = open_test_db(&temp_dir);
+        let entry = add_test_entry(&db, "github", "v1");
+        update_test_entry(&db, "github", "v2", 10);
+
+        db.delete_entry(entry.id).unwrap();
+        assert!(db.list_history(entry.id).unwrap().is_empty());
+    }
+}
@@ -5,6 +5,9 @@ use std::io;
 pub enum ErrorSetup {
     Session,
     DataBase,
+    Vault,
+    /// The config failed its integrity check and was quarantined.
+    Integrity(ErrorIntegrity),
 }
 
 
@@ -15,13 +18,36 @@ impl fmt::Display for ErrorSetup {
 
         match self {
             ErrorSetup::Session => write!(f, "Session set up failed"),
-            ErrorSetup::DataBase => write!(f, "DataBase set up failed")
+            ErrorSetup::DataBase => write!(f, "DataBase set up failed"),
+            ErrorSetup::Vault => write!(f, "Vault directories could not be created"),
+            ErrorSetup::Integrity(err) => write!(f, "{}", err),
         }
         
     }
 }
 
 
+#[derive(Debug, PartialEq)]
+pub enum ErrorIntegrity {
+    MissingChecksum,
+    Corrupted(String),
+    Tampered,
+    /// A plain sha256 from before the keyed checksum that matches the config, replaced at login.
+    Unkeyed,
+}
+
+impl fmt::Display for ErrorIntegrity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorIntegrity::MissingChecksum => write!(f, "Config checksum file is missing."),
+            ErrorIntegrity::Corrupted(reason) => write!(f, "Config or checksum file is corrupted: {}.", reason),
+            ErrorIntegrity::Tampered => write!(f, "Config does not match its authentication code, possible tampering detected."),
+            ErrorIntegrity::Unkeyed => write!(f, "Config is protected by a legacy unkeyed checksum, login to seal it with the master password."),
+        }
+    }
+}
+
+
 pub enum ErrorValidation {
     EmptyName,
     LongName,
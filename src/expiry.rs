@@ -0,0 +1,32 @@
+use crate::context::Context;
+use crate::db::Db::Entry;
+use chrono::Utc;
+use log::warn;
+
+/// Entries past their maximum age, the most overdue first.
+pub fn stale_entries(context: &Context) -> Result<Vec<Entry>, rusqlite::Error> {
+    let default_max_age = context.settings.borrow_mut().get_default_max_age(context);
+    let now = Utc::now();
+
+    let mut stale: Vec<Entry> = context
+        .db
+        .list_entries()?
+        .into_iter()
+        .filter(|entry| entry.is_stale_at(default_max_age, now))
+        .collect();
+    stale.sort_by_key(|entry| entry.due_at(default_max_age));
+    Ok(stale)
+}
+
+/// One line reminder printed after a command, silent when nothing is due or the vault is not set up.
+pub fn warn_if_stale(context: &Context) {
+    if !context.kgc.borrow().is_master_key_provided() {
+        return;
+    }
+
+    match stale_entries(context) {
+        Ok(stale) if stale.len() == 1 => warn!("1 entry is due for rotation, see `kofl list --stale`"),
+        Ok(stale) if stale.len() > 1 => warn!("{} entries are due for rotation, see `kofl list --stale`", stale.len()),
+        _ => (),
+    }
+}
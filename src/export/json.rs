@@ -0,0 +1,126 @@
+//! The kofl export format, a JSON document:
+//!
+//! ```text
+//! {
+//!   "format": "kofl-export",
+//!   "version": 1,
+//!   "exported_at": "2025-01-31T10:00:00Z",
+//!   "entries": [
+//!     {
+//!       "name": "work/github",
+//!       "password": "…",
+//!       "username": "me",                // optional, as url, notes, policy and max_age_days
+//!       "url": "https://github.com",
+//!       "notes": "…",
+//!       "tags": ["dev"],
+//!       "policy": "strict",
+//!       "max_age_days": 90,
+//!       "created_at": "2024-01-01T10:00:00Z",
+//!       "updated_at": "2024-06-01T10:00:00Z",
+//!       "history": [
+//!         { "password": "…", "set_at": "2024-01-01T10:00:00Z", "replaced_at": "2024-06-01T10:00:00Z" }
+//!       ]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Times are RFC 3339 in UTC and history is sorted from the oldest secret. The
+//! version changes only when a reader of the previous version would misread the
+//! file, new optional fields do not change it. By default the document is
+//! encrypted with a passphrase in the armored age format (`age -d` decrypts it).
+
+use crate::crypto::{decrypt_with_passphrase, encrypt_with_passphrase, is_passphrase_encrypted};
+use crate::errors::ErrorExecution;
+use crate::export::ExportedEntry;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+pub const FORMAT: &str = "kofl-export";
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ExportFile {
+    format: String,
+    version: u32,
+    exported_at: DateTime<Utc>,
+    entries: Vec<ExportedEntry>,
+}
+
+/// The export document, encrypted when a passphrase is given.
+pub fn write_json(entries: Vec<ExportedEntry>, passphrase: Option<&str>) -> Result<Vec<u8>, String> {
+    let file = ExportFile { format: FORMAT.to_string(), version: FORMAT_VERSION, exported_at: Utc::now(), entries };
+    let mut json = serde_json::to_vec_pretty(&file).map_err(|e| format!("Cannot serialize the vault: {}", e))?;
+    json.push(b'\n');
+
+    match passphrase {
+        Some(passphrase) => encrypt_with_passphrase(&json, passphrase).map_err(|e| format!("Cannot encrypt the export: {}", e)),
+        None => Ok(json),
+    }
+}
+
+/// Reads an export, `passphrase` is only asked for when the file is encrypted.
+pub fn read_json(data: &[u8], passphrase: impl FnOnce() -> Result<String, String>) -> Result<Vec<ExportedEntry>, String> {
+    let decrypted;
+    let json = if is_passphrase_encrypted(data) {
+        decrypted = decrypt_with_passphrase(data, &passphrase()?).map_err(|e| match e {
+            ErrorExecution::AuthenticationFailed => "Wrong passphrase".to_string(),
+            e => format!("Cannot decrypt the export: {}", e),
+        })?;
+        decrypted.as_slice()
+    } else {
+        data
+    };
+
+    let file: ExportFile = serde_json::from_slice(json).map_err(|e| format!("Not a kofl export: {}", e))?;
+    if file.format != FORMAT {
+        return Err(format!("Not a kofl export, the format is `{}`", file.format));
+    }
+    if file.version > FORMAT_VERSION {
+        return Err(format!("The export is version {}, this kofl reads up to version {}", file.version, FORMAT_VERSION));
+    }
+    Ok(file.entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry() -> ExportedEntry {
+        let time = |text: &str| DateTime::parse_from_rfc3339(text).unwrap().with_timezone(&Utc);
+        ExportedEntry {
+            name: "work/github".to_string(),
+            password: "s3cret \"quoted\"".to_string(),
+            username: Some("me".to_string()),
+            url: None,
+            notes: Some("line one\nline two".to_string()),
+            tags: vec!["dev".to_string()],
+            policy: None,
+            max_age_days: Some(90),
+            created_at: time("2024-01-01T10:00:00Z"),
+            updated_at: time("2024-06-01T10:00:00Z"),
+            history: vec![],
+        }
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let plain = write_json(vec![entry()], None).unwrap();
+        assert!(String::from_utf8_lossy(&plain).contains("\"version\": 1"));
+        let read = read_json(&plain, || panic!("not encrypted")).unwrap();
+        assert_eq!(read, vec![entry()]);
+
+        let encrypted = write_json(vec![entry()], Some("correct horse")).unwrap();
+        assert!(!String::from_utf8_lossy(&encrypted).contains("github"));
+        assert_eq!(read_json(&encrypted, || Ok("correct horse".to_string())).unwrap(), vec![entry()]);
+        assert_eq!(read_json(&encrypted, || Ok("wrong".to_string())), Err("Wrong passphrase".to_string()));
+    }
+
+    #[test]
+    fn test_json_version() {
+        let newer = r#"{"format": "kofl-export", "version": 2, "exported_at": "2025-01-01T00:00:00Z", "entries": []}"#;
+        assert!(read_json(newer.as_bytes(), || unreachable!()).unwrap_err().contains("version 2"));
+        let other = r#"{"format": "other", "version": 1, "exported_at": "2025-01-01T00:00:00Z", "entries": []}"#;
+        assert!(read_json(other.as_bytes(), || unreachable!()).is_err());
+    }
+}
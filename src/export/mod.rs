@@ -0,0 +1,207 @@
+//! The vault written out with its secrets in clear, see `kofl export`.
+
+pub mod json;
+pub mod pass;
+
+use crate::context::Context;
+use crate::crypto::decrypt_secret;
+use crate::import::{ImportedEntry, PreviousSecret};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// Versioned JSON document, see `json`.
+    Json,
+    /// One encrypted file per entry, see `pass`.
+    Pass,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "json" => Ok(ExportFormat::Json),
+            "pass" => Ok(ExportFormat::Pass),
+            other => Err(format!("Unknown export format `{}`, use json or pass", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedEntry {
+    pub name: String,
+    pub password: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Name of the password policy, the default one when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<String>,
+    /// Overrides the `default_max_age` setting, 0 means the entry never expires.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u32>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Previous secrets, the oldest first.
+    #[serde(default)]
+    pub history: Vec<ExportedSecret>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedSecret {
+    pub password: String,
+    pub set_at: DateTime<Utc>,
+    pub replaced_at: DateTime<Utc>,
+}
+
+/// Every entry of the vault with its secrets decrypted, sorted by name.
+pub fn export_entries(context: &Context) -> Result<Vec<ExportedEntry>, String> {
+    let master_key_hash = context.kgc.borrow().get_hashed_pwd();
+    let db_error = |e: rusqlite::Error| format!("Database error: {}", e);
+    let decrypt = |name: &str, encrypted: &str| {
+        decrypt_secret(&master_key_hash, encrypted).map_err(|e| format!("Cannot decrypt {}: {}", name, e))
+    };
+
+    let mut entries = context.db.list_entries().map_err(db_error)?;
+    entries.sort_by(|a, b| a.ent_name.cmp(&b.ent_name));
+
+    let mut exported = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let mut history = Vec::new();
+        for previous in context.db.list_history(entry.id).map_err(db_error)? {
+            history.push(ExportedSecret {
+                password: decrypt(&entry.ent_name, &previous.password_hash)?,
+                set_at: previous.set_at,
+                replaced_at: previous.replaced_at,
+            });
+        }
+
+        exported.push(ExportedEntry {
+            password: decrypt(&entry.ent_name, &entry.password_hash)?,
+            tags: context.db.list_tags(entry.id).map_err(db_error)?,
+            username: entry.username,
+            url: entry.url,
+            notes: entry.notes,
+            policy: entry.policy,
+            max_age_days: entry.max_age_days,
+            created_at: entry.created_at,
+            updated_at: entry.updated_at,
+            history,
+            name: entry.ent_name,
+        });
+    }
+    Ok(exported)
+}
+
+impl From<ExportedEntry> for ImportedEntry {
+    fn from(entry: ExportedEntry) -> Self {
+        ImportedEntry {
+            name: entry.name,
+            password: entry.password,
+            username: entry.username,
+            url: entry.url,
+            notes: entry.notes,
+            tags: entry.tags,
+            created_at: Some(entry.created_at),
+            updated_at: Some(entry.updated_at),
+            policy: entry.policy,
+            max_age_days: entry.max_age_days,
+            history: entry
+                .history
+                .into_iter()
+                .map(|previous| PreviousSecret {
+                    password: previous.password,
+                    set_at: Some(previous.set_at),
+                    replaced_at: Some(previous.replaced_at),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Writes a file only the user can read, replacing it if it exists.
+pub(crate) fn write_private_file(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::encrypt_secret;
+    use crate::db::Db::Entry;
+    use crate::export::json::{read_json, write_json};
+    use crate::import::{import_entries, Collision};
+    use chrono::Duration;
+    use serial_test::serial;
+    use crate::test_support::IsolatedEnv;
+    use tempfile::TempDir;
+
+    fn vault(env: &IsolatedEnv, root: &Path, name: &str, master_key_hash: &str) -> Context {
+        env.set_var("KOFL_HOME", root.join(name).to_str().unwrap());
+        let context = Context::new().unwrap();
+        context.kgc.borrow_mut().set_master_key_hash(master_key_hash.to_string());
+        context
+    }
+
+    #[test]
+    #[serial]
+    fn test_export_import_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let env = IsolatedEnv::new(root);
+
+        let key = "ab".repeat(32);
+        let source = vault(&env, root, "source", &key);
+        let created = Utc::now() - Duration::days(400);
+
+        let mut github = Entry::new(0, "work/github".to_string(), encrypt_secret(&key, "v1").ok().unwrap());
+        github.created_at = created;
+        github.updated_at = created;
+        github.username = Some("me".to_string());
+        github.notes = Some("line one\nline two".to_string());
+        github.policy = Some("strict".to_string());
+        github.max_age_days = Some(90);
+        let id = source.db.add_entry_with_tags(github, &["dev".to_string(), "work".to_string()]).unwrap();
+        for (version, days_ago) in [("v2", 200), ("v3", 10)] {
+            let current = source.db.get_entry(id).unwrap();
+            let next = Entry {
+                password_hash: encrypt_secret(&key, version).ok().unwrap(),
+                updated_at: Utc::now() - Duration::days(days_ago),
+                ..current.clone()
+            };
+            source.db.update_entry_with_history(&current, next, 5).unwrap();
+        }
+        source.db.add_entry(Entry::new(0, "mail".to_string(), encrypt_secret(&key, "p").ok().unwrap())).unwrap();
+
+        let exported = export_entries(&source).unwrap();
+        assert_eq!(exported[1].history.iter().map(|previous| previous.password.as_str()).collect::<Vec<_>>(), vec!["v1", "v2"]);
+        let file = write_json(exported.clone(), None).unwrap();
+
+        // another vault with another master key
+        let target = vault(&env, root, "target", &"cd".repeat(32));
+        let entries = read_json(&file, || unreachable!()).unwrap().into_iter().map(ImportedEntry::from).collect();
+        let summary = import_entries(&target, entries, Collision::Skip).unwrap();
+        assert_eq!(summary.added, 2);
+
+        assert_eq!(export_entries(&target).unwrap(), exported);
+    }
+}
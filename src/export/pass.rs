@@ -0,0 +1,240 @@
+//! The password-store layout of `pass`: one file per entry under its folder
+//! path, encrypted with a passphrase in the age format instead of GPG, so the
+//! files are named `<name>.age`. A file holds the password on its first line
+//! and `key: value` lines after it:
+//!
+//! ```text
+//! s3cret
+//! username: me
+//! url: https://github.com
+//! tags: dev, work
+//! notes: first line of the notes
+//! notes: second line
+//! ```
+//!
+//! History, timestamps, policies and maximum ages are not kept in this layout.
+
+use crate::crypto::{decrypt_with_passphrase, encrypt_with_passphrase};
+use crate::errors::ErrorExecution;
+use crate::export::{write_private_file, ExportedEntry};
+use crate::import::{entry_name, split_tags, ImportedEntry};
+use crate::organize::{validate_entry_name, FOLDER_SEPARATOR};
+use crate::vault::create_private_dir;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const EXTENSION: &str = "age";
+
+/// Writes one encrypted file per entry, the directory must be empty or missing
+/// so no file of a previous export is left behind.
+pub fn write_store(dir: &Path, entries: &[ExportedEntry], passphrase: &str) -> Result<(), String> {
+    let is_empty = |dir: &Path| fs::read_dir(dir).map(|mut files| files.next().is_none()).unwrap_or(false);
+    if dir.exists() && !is_empty(dir) {
+        return Err(format!("{} is not an empty directory", dir.display()));
+    }
+    // names saved before they were validated could point outside the directory
+    for entry in entries {
+        validate_entry_name(&entry.name).map_err(|e| format!("{}, rename it before exporting", e))?;
+    }
+
+    for entry in entries {
+        let path = entry_path(dir, &entry.name);
+        if let Some(parent) = path.parent() {
+            create_private_dir(parent).map_err(|e| format!("Cannot create {}: {}", parent.display(), e))?;
+        }
+        let encrypted = encrypt_with_passphrase(to_pass_file(entry).as_bytes(), passphrase)
+            .map_err(|e| format!("Cannot encrypt {}: {}", entry.name, e))?;
+        write_private_file(&path, &encrypted).map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+fn entry_path(dir: &Path, name: &str) -> PathBuf {
+    let mut path = dir.to_path_buf();
+    path.extend(name.split(FOLDER_SEPARATOR));
+    let file_name = format!("{}.{}", path.file_name().unwrap_or_default().to_string_lossy(), EXTENSION);
+    path.set_file_name(file_name);
+    path
+}
+
+fn to_pass_file(entry: &ExportedEntry) -> String {
+    let mut lines = vec![entry.password.clone()];
+    if let Some(username) = &entry.username {
+        lines.push(format!("username: {}", username));
+    }
+    if let Some(url) = &entry.url {
+        lines.push(format!("url: {}", url));
+    }
+    if !entry.tags.is_empty() {
+        lines.push(format!("tags: {}", entry.tags.join(", ")));
+    }
+    for line in entry.notes.iter().flat_map(|notes| notes.lines()) {
+        lines.push(format!("notes: {}", line));
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Reads every `.age` file under the directory, hidden files and folders such
+/// as `.git` are ignored. A file that cannot be read is returned as an error
+/// naming it, a wrong passphrase stops the whole import.
+pub fn read_store(dir: &Path, passphrase: &str) -> Result<Vec<Result<ImportedEntry, String>>, String> {
+    if !dir.is_dir() {
+        return Err(format!("{} is not a directory", dir.display()));
+    }
+    let mut files = Vec::new();
+    collect_files(dir, &mut files).map_err(|e| format!("Cannot read {}: {}", dir.display(), e))?;
+    files.sort();
+
+    let mut rows = Vec::new();
+    for path in files {
+        let relative = path.strip_prefix(dir).unwrap_or(&path);
+        if path.extension().and_then(|extension| extension.to_str()) != Some(EXTENSION) {
+            log::warn!("{} is skipped, only .{} files can be read", relative.display(), EXTENSION);
+            continue;
+        }
+        let title = path.file_stem().unwrap_or_default().to_string_lossy();
+        let folder = relative.parent().map(|parent| parent.to_string_lossy());
+
+        let row = fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|encrypted| match decrypt_with_passphrase(&encrypted, passphrase) {
+                Ok(decrypted) => String::from_utf8(decrypted).map_err(|_| "not a text file".to_string()),
+                Err(ErrorExecution::AuthenticationFailed) => Err("wrong passphrase".to_string()),
+                Err(e) => Err(e.to_string()),
+            })
+            .and_then(|content| from_pass_file(entry_name(folder.as_deref(), &title)?, &content));
+        if row.as_ref().is_err_and(|e| e == "wrong passphrase") {
+            return Err(format!("Wrong passphrase for {}", relative.display()));
+        }
+        rows.push(row.map_err(|reason| format!("{}: {}", relative.display(), reason)));
+    }
+    Ok(rows)
+}
+
+// symbolic links are not followed, a link to a parent folder would never end
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for file in fs::read_dir(dir)? {
+        let path = file?.path();
+        if path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.')) {
+            continue;
+        }
+        let file_type = fs::symlink_metadata(&path)?.file_type();
+        if file_type.is_symlink() {
+            log::warn!("{} is skipped, symbolic links are not followed", path.display());
+        } else if file_type.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+// unknown `key: value` lines and free text are kept in the notes
+fn from_pass_file(name: String, content: &str) -> Result<ImportedEntry, String> {
+    let mut lines = content.lines();
+    let password = lines.next().unwrap_or_default();
+    if password.is_empty() {
+        return Err("no password".to_string());
+    }
+
+    let mut entry = ImportedEntry { name, password: password.to_string(), ..Default::default() };
+    let mut notes = Vec::new();
+    for line in lines {
+        let (key, value) = line.split_once(':').map_or(("", line), |(key, value)| (key, value.strip_prefix(' ').unwrap_or(value)));
+        match key.trim().to_lowercase().as_str() {
+            "username" | "login" | "user" if entry.username.is_none() => entry.username = Some(value.trim().to_string()),
+            "url" | "website" if entry.url.is_none() => entry.url = Some(value.trim().to_string()),
+            "tags" => entry.tags.extend(split_tags(value)),
+            "notes" => notes.push(value.to_string()),
+            _ => notes.push(line.to_string()),
+        }
+    }
+    while notes.last().is_some_and(|line| line.trim().is_empty()) {
+        notes.pop();
+    }
+    entry.notes = if notes.is_empty() { None } else { Some(notes.join("\n")) };
+    Ok(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    fn entry(name: &str, password: &str) -> ExportedEntry {
+        ExportedEntry {
+            name: name.to_string(),
+            password: password.to_string(),
+            username: None,
+            url: None,
+            notes: None,
+            tags: vec![],
+            policy: None,
+            max_age_days: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            history: vec![],
+        }
+    }
+
+    #[test]
+    fn test_pass_file() {
+        let mut github = entry("work/github", "pw: with colon");
+        github.username = Some("me".to_string());
+        github.tags = vec!["dev".to_string(), "work".to_string()];
+        github.notes = Some("first\n\nrecovery: 1234".to_string());
+        let content = to_pass_file(&github);
+        assert_eq!(content, "pw: with colon\nusername: me\ntags: dev, work\nnotes: first\nnotes: \nnotes: recovery: 1234\n");
+
+        let read = from_pass_file(github.name.clone(), &content).unwrap();
+        assert_eq!((read.password, read.username, read.tags), (github.password, github.username, github.tags));
+        assert_eq!(read.notes, github.notes);
+
+        // as written by pass users and browserpass
+        let read = from_pass_file("shop".to_string(), "secret\nlogin: me\nURL: https://shop.example.com\nPIN: 0000\nfree text\n").unwrap();
+        assert_eq!((read.username.as_deref(), read.url.as_deref()), (Some("me"), Some("https://shop.example.com")));
+        assert_eq!(read.notes.as_deref(), Some("PIN: 0000\nfree text"));
+        assert!(from_pass_file("empty".to_string(), "\nlogin: me").is_err());
+    }
+
+    #[test]
+    fn test_store_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = temp_dir.path().join("store");
+        write_store(&store, &[entry("work/aws/prod", "p1"), entry("mail", "p2")], "passphrase").unwrap();
+        assert!(store.join("work").join("aws").join("prod.age").is_file());
+        fs::write(store.join("work").join("old.gpg"), "gpg").unwrap();
+        fs::create_dir(store.join(".git")).unwrap();
+        fs::write(store.join(".git").join("HEAD.age"), "not an entry").unwrap();
+
+        let rows = read_store(&store, "passphrase").unwrap();
+        let names: Vec<_> = rows.iter().map(|row| row.as_ref().unwrap().name.as_str()).collect();
+        assert_eq!(names, vec!["mail", "work/aws/prod"]);
+        assert!(read_store(&store, "wrong").is_err());
+        assert!(write_store(&store, &[], "passphrase").is_err(), "the store is not empty");
+    }
+
+    #[test]
+    fn test_store_stays_in_its_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = temp_dir.path().join("store");
+        let error = write_store(&store, &[entry("mail", "p1"), entry("../outside", "p2")], "passphrase").unwrap_err();
+        assert!(error.contains("cannot contain `..`"));
+        assert!(!temp_dir.path().join("outside.age").exists());
+        assert!(!store.exists(), "nothing is written when a name is refused");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_symlinks_are_not_followed() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = temp_dir.path().join("store");
+        write_store(&store, &[entry("work/mail", "p1")], "passphrase").unwrap();
+        // a link back to the store itself would never end
+        std::os::unix::fs::symlink(&store, store.join("work").join("loop")).unwrap();
+        let rows = read_store(&store, "passphrase").unwrap();
+        assert_eq!(rows.len(), 1);
+    }
+}
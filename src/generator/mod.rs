@@ -0,0 +1,212 @@
+//! Random password and passphrase generation from the OS CSPRNG.
+
+pub mod passphrase;
+pub mod profile;
+
+use rand::rngs::OsRng;
+use rand::seq::SliceRandom;
+use std::fmt;
+use std::str::FromStr;
+
+pub const DEFAULT_SYMBOLS: &str = "!@#$%^&*";
+/// Characters easily confused with one another when read or typed.
+pub const AMBIGUOUS: &str = "Il1|O0o";
+
+pub const MIN_LENGTH: usize = 4;
+pub const MAX_LENGTH: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharClass {
+    Lower,
+    Upper,
+    Digits,
+    Symbols,
+}
+
+impl CharClass {
+    pub fn all() -> Vec<CharClass> {
+        vec![CharClass::Lower, CharClass::Upper, CharClass::Digits, CharClass::Symbols]
+    }
+
+    fn chars(&self, symbols: &str) -> Vec<char> {
+        match self {
+            CharClass::Lower => ('a'..='z').collect(),
+            CharClass::Upper => ('A'..='Z').collect(),
+            CharClass::Digits => ('0'..='9').collect(),
+            CharClass::Symbols => symbols.chars().collect(),
+        }
+    }
+}
+
+impl fmt::Display for CharClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CharClass::Lower => "lower",
+            CharClass::Upper => "upper",
+            CharClass::Digits => "digits",
+            CharClass::Symbols => "symbols",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for CharClass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "lower" | "lowercase" => Ok(CharClass::Lower),
+            "upper" | "uppercase" => Ok(CharClass::Upper),
+            "digits" | "digit" | "numbers" => Ok(CharClass::Digits),
+            "symbols" | "symbol" | "special" => Ok(CharClass::Symbols),
+            other => Err(format!("Unknown character class `{}`, use lower, upper, digits or symbols", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratorOptions {
+    pub length: usize,
+    pub charset: Vec<CharClass>,
+    /// Symbols allowed when `CharClass::Symbols` is in the charset.
+    pub symbols: String,
+    pub exclude_ambiguous: bool,
+    /// Classes that must appear at least once, every class of the charset when `None`.
+    pub required: Option<Vec<CharClass>>,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        GeneratorOptions {
+            length: 24,
+            charset: CharClass::all(),
+            symbols: DEFAULT_SYMBOLS.to_string(),
+            exclude_ambiguous: false,
+            required: None,
+        }
+    }
+}
+
+impl GeneratorOptions {
+    fn pool(&self, class: CharClass) -> Vec<char> {
+        class
+            .chars(&self.symbols)
+            .into_iter()
+            .filter(|c| !(self.exclude_ambiguous && AMBIGUOUS.contains(*c)))
+            .collect()
+    }
+
+    fn required_classes(&self) -> Vec<CharClass> {
+        self.required.clone().unwrap_or_else(|| self.charset.clone())
+    }
+
+    /// Every character the generator can pick from.
+    pub fn alphabet(&self) -> Vec<char> {
+        let mut alphabet: Vec<char> = self.charset.iter().flat_map(|class| self.pool(*class)).collect();
+        alphabet.sort_unstable();
+        alphabet.dedup();
+        alphabet
+    }
+
+    pub fn check(&self) -> Result<(), String> {
+        if self.length < MIN_LENGTH || self.length > MAX_LENGTH {
+            return Err(format!("Length must be between {} and {}", MIN_LENGTH, MAX_LENGTH));
+        }
+        if self.charset.is_empty() {
+            return Err("The charset needs at least one character class".to_string());
+        }
+        let required = self.required_classes();
+        for class in &required {
+            if !self.charset.contains(class) {
+                return Err(format!("Required class {} is not in the charset", class));
+            }
+            if self.pool(*class).is_empty() {
+                return Err(format!("No character left in class {}", class));
+            }
+        }
+        if required.len() > self.length {
+            return Err(format!("Length {} is too short for {} required classes", self.length, required.len()));
+        }
+        Ok(())
+    }
+
+    /// Entropy in bits of a password drawn uniformly from the alphabet.
+    pub fn entropy_bits(&self) -> f64 {
+        self.length as f64 * (self.alphabet().len() as f64).log2()
+    }
+}
+
+/// Generates a password with at least one character of every required class.
+pub fn generate(options: &GeneratorOptions) -> Result<String, String> {
+    options.check()?;
+    let mut rng = OsRng;
+
+    let mut password: Vec<char> = options
+        .required_classes()
+        .iter()
+        .map(|class| *options.pool(*class).choose(&mut rng).unwrap()) // pools checked above
+        .collect();
+
+    let alphabet = options.alphabet();
+    while password.len() < options.length {
+        password.push(*alphabet.choose(&mut rng).unwrap());
+    }
+
+    // the required characters must not always sit at the start
+    password.shuffle(&mut rng);
+    Ok(password.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_default() {
+        let options = GeneratorOptions::default();
+        let password = generate(&options).unwrap();
+        assert_eq!(password.chars().count(), 24);
+        assert!(password.chars().any(|c| c.is_ascii_lowercase()));
+        assert!(password.chars().any(|c| c.is_ascii_uppercase()));
+        assert!(password.chars().any(|c| c.is_ascii_digit()));
+        assert!(password.chars().any(|c| DEFAULT_SYMBOLS.contains(c)));
+    }
+
+    #[test]
+    fn test_generate_respects_charset() {
+        let options = GeneratorOptions {
+            length: 40,
+            charset: vec![CharClass::Lower, CharClass::Digits, CharClass::Symbols],
+            symbols: "-_".to_string(),
+            exclude_ambiguous: true,
+            required: None,
+        };
+        for _ in 0..20 {
+            let password = generate(&options).unwrap();
+            assert!(password.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_'));
+            assert!(!password.chars().any(|c| AMBIGUOUS.contains(c)));
+        }
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        let mut options = GeneratorOptions { length: 3, ..GeneratorOptions::default() };
+        assert!(generate(&options).is_err());
+
+        options.length = 12;
+        options.charset = vec![CharClass::Digits];
+        options.required = Some(vec![CharClass::Upper]);
+        assert!(generate(&options).is_err());
+
+        options.required = None;
+        options.charset = vec![CharClass::Symbols];
+        options.symbols = String::new();
+        assert!(generate(&options).is_err());
+    }
+
+    #[test]
+    fn test_entropy() {
+        let options = GeneratorOptions { length: 10, charset: vec![CharClass::Digits], ..GeneratorOptions::default() };
+        assert!((options.entropy_bits() - 10.0 * 10f64.log2()).abs() < 1e-9);
+    }
+}
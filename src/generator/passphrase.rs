@@ -0,0 +1,253 @@
+//! Diceware-style passphrases made of random words.
+//!
+//! The embedded list holds 2445 common English words of 3 to 9 letters, about
+//! 11.3 bits per word. It is not the EFF large list (7776 words, 12.9 bits).
+//! Unless a word count is given, passphrases take as many words as needed to
+//! match six EFF words: seven from the embedded list, six from the EFF list.
+//! Any list with one word per line can replace it with the `passphrase_wordlist`
+//! setting, including the EFF dice lists where each word is preceded by its
+//! dice roll (`11111	abacus`).
+
+use super::DEFAULT_SYMBOLS;
+use rand::rngs::OsRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+const EMBEDDED_WORDLIST: &str = include_str!("wordlist.txt");
+
+/// A shorter list makes each word worth too little to be useful.
+pub const MIN_WORDLIST_LEN: usize = 1000;
+pub const MIN_WORDS: usize = 3;
+pub const MAX_WORDS: usize = 64;
+
+/// The default passphrase is as strong as this many words of the EFF large list.
+const EFF_DEFAULT_WORDS: f64 = 6.0;
+const EFF_LARGE_LEN: usize = 7776;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capitalization {
+    None,
+    First,
+    Random,
+}
+
+impl FromStr for Capitalization {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "none" => Ok(Capitalization::None),
+            "first" => Ok(Capitalization::First),
+            "random" => Ok(Capitalization::Random),
+            other => Err(format!("Unknown capitalization `{}`, use none, first or random", other)),
+        }
+    }
+}
+
+impl fmt::Display for Capitalization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Capitalization::None => "none",
+            Capitalization::First => "first",
+            Capitalization::Random => "random",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassphraseOptions {
+    /// Enough to match six words of the EFF large list when `None`.
+    pub words: Option<usize>,
+    pub separator: String,
+    pub capitalize: Capitalization,
+    /// Appends a random digit to one of the words.
+    pub digit: bool,
+    /// Appends a random symbol to one of the words.
+    pub symbol: bool,
+}
+
+impl Default for PassphraseOptions {
+    fn default() -> Self {
+        PassphraseOptions {
+            words: None,
+            separator: "-".to_string(),
+            capitalize: Capitalization::None,
+            digit: false,
+            symbol: false,
+        }
+    }
+}
+
+impl PassphraseOptions {
+    pub fn check(&self) -> Result<(), String> {
+        match self.words {
+            Some(words) if !(MIN_WORDS..=MAX_WORDS).contains(&words) => {
+                Err(format!("Word count must be between {} and {}", MIN_WORDS, MAX_WORDS))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The number of words drawn from a list of `wordlist_len` words.
+    pub fn word_count(&self, wordlist_len: usize) -> usize {
+        self.words.unwrap_or_else(|| {
+            let target = EFF_DEFAULT_WORDS * (EFF_LARGE_LEN as f64).log2();
+            // the small margin keeps exactly six EFF words at six
+            let words = (target / (wordlist_len.max(2) as f64).log2() - 1e-9).ceil() as usize;
+            words.clamp(MIN_WORDS, MAX_WORDS)
+        })
+    }
+
+    /// Entropy in bits, assuming the attacker knows the options and the word list.
+    pub fn entropy_bits(&self, wordlist_len: usize) -> f64 {
+        let words = self.word_count(wordlist_len) as f64;
+        let mut bits = words * (wordlist_len as f64).log2();
+        if self.capitalize == Capitalization::Random {
+            bits += words;
+        }
+        if self.digit {
+            bits += 10f64.log2() + words.log2();
+        }
+        if self.symbol {
+            bits += (DEFAULT_SYMBOLS.len() as f64).log2() + words.log2();
+        }
+        bits
+    }
+}
+
+fn parse_wordlist(content: &str) -> Vec<String> {
+    let mut words: Vec<String> = content
+        .lines()
+        .filter_map(|line| line.split_whitespace().last())
+        .map(str::to_lowercase)
+        .collect();
+    words.sort_unstable();
+    words.dedup();
+    words
+}
+
+/// The embedded list, or the one read from `path`.
+pub fn load_wordlist(path: Option<&Path>) -> Result<Vec<String>, String> {
+    let words = match path {
+        None => parse_wordlist(EMBEDDED_WORDLIST),
+        Some(path) => {
+            let content = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+            parse_wordlist(&content)
+        }
+    };
+
+    if words.len() < MIN_WORDLIST_LEN {
+        return Err(format!("The word list has {} distinct words, at least {} are needed", words.len(), MIN_WORDLIST_LEN));
+    }
+    Ok(words)
+}
+
+fn capitalize_first(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+pub fn generate_passphrase(options: &PassphraseOptions, wordlist: &[String]) -> Result<String, String> {
+    options.check()?;
+    if wordlist.is_empty() {
+        return Err("The word list is empty".to_string());
+    }
+    let mut rng = OsRng;
+
+    let mut words: Vec<String> = (0..options.word_count(wordlist.len()))
+        .map(|_| {
+            let word = wordlist.choose(&mut rng).unwrap().clone(); // not empty
+            match options.capitalize {
+                Capitalization::None => word,
+                Capitalization::First => capitalize_first(&word),
+                Capitalization::Random if rng.gen_bool(0.5) => capitalize_first(&word),
+                Capitalization::Random => word,
+            }
+        })
+        .collect();
+
+    if options.digit {
+        let index = rng.gen_range(0..words.len());
+        words[index].push(char::from(b'0' + rng.gen_range(0..10u8)));
+    }
+    if options.symbol {
+        let symbols: Vec<char> = DEFAULT_SYMBOLS.chars().collect();
+        let index = rng.gen_range(0..words.len());
+        words[index].push(*symbols.choose(&mut rng).unwrap());
+    }
+
+    Ok(words.join(&options.separator))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_embedded_wordlist() {
+        let words = load_wordlist(None).unwrap();
+        assert!(words.len() >= 2048);
+        assert!(words.iter().all(|word| word.chars().all(|c| c.is_ascii_lowercase())));
+    }
+
+    #[test]
+    fn test_generate_passphrase() {
+        let wordlist = load_wordlist(None).unwrap();
+        let options = PassphraseOptions { words: Some(5), separator: ".".to_string(), ..PassphraseOptions::default() };
+        let passphrase = generate_passphrase(&options, &wordlist).unwrap();
+        let words: Vec<&str> = passphrase.split('.').collect();
+        assert_eq!(words.len(), 5);
+        assert!(words.iter().all(|word| wordlist.iter().any(|w| w == word)));
+
+        let options = PassphraseOptions {
+            capitalize: Capitalization::First,
+            digit: true,
+            symbol: true,
+            ..PassphraseOptions::default()
+        };
+        let passphrase = generate_passphrase(&options, &wordlist).unwrap();
+        assert!(passphrase.split('-').all(|word| word.chars().next().unwrap().is_ascii_uppercase()));
+        assert!(passphrase.chars().any(|c| c.is_ascii_digit()));
+        assert!(passphrase.chars().any(|c| DEFAULT_SYMBOLS.contains(c)));
+    }
+
+    #[test]
+    fn test_dice_wordlist_file() {
+        let mut file = NamedTempFile::new().unwrap();
+        for i in 0..1200 {
+            writeln!(file, "{:05}\tword{}", 11111 + i, i).unwrap();
+        }
+        let words = load_wordlist(Some(file.path())).unwrap();
+        assert_eq!(words.len(), 1200);
+        assert!(words.contains(&"word42".to_string()));
+
+        let mut short = NamedTempFile::new().unwrap();
+        writeln!(short, "one\ntwo\nthree").unwrap();
+        assert!(load_wordlist(Some(short.path())).is_err());
+    }
+
+    #[test]
+    fn test_entropy() {
+        let options = PassphraseOptions::default();
+        assert_eq!(options.word_count(7776), 6);
+        assert!((options.entropy_bits(7776) - 6.0 * 7776f64.log2()).abs() < 1e-9);
+        // as strong by default as six words of the EFF large list
+        let embedded = load_wordlist(None).unwrap().len();
+        assert_eq!(options.word_count(embedded), 7);
+        assert!(options.entropy_bits(embedded) >= 6.0 * 7776f64.log2());
+        let fixed = PassphraseOptions { words: Some(4), ..PassphraseOptions::default() };
+        assert_eq!(fixed.word_count(embedded), 4);
+        let random = PassphraseOptions { capitalize: Capitalization::Random, ..PassphraseOptions::default() };
+        assert!(random.entropy_bits(7776) > options.entropy_bits(7776));
+    }
+}
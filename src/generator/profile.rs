@@ -0,0 +1,171 @@
+//! Named generator settings, the built-in ones can be overridden per vault.
+//!
+//! A profile is stored in the settings table under `generator_profile.<name>`
+//! as a spec such as `length=12 charset=lower,upper,digits exclude-ambiguous`.
+
+use super::{CharClass, GeneratorOptions, DEFAULT_SYMBOLS};
+use crate::db::Db::Database;
+
+pub const PROFILE_KEY_PREFIX: &str = "generator_profile.";
+
+pub fn builtin_profiles() -> Vec<(&'static str, GeneratorOptions)> {
+    vec![
+        ("strong", GeneratorOptions::default()),
+        (
+            "legacy-bank",
+            GeneratorOptions {
+                length: 12,
+                charset: vec![CharClass::Lower, CharClass::Upper, CharClass::Digits],
+                symbols: DEFAULT_SYMBOLS.to_string(),
+                exclude_ambiguous: true,
+                required: None,
+            },
+        ),
+        (
+            "pin",
+            GeneratorOptions {
+                length: 6,
+                charset: vec![CharClass::Digits],
+                symbols: DEFAULT_SYMBOLS.to_string(),
+                exclude_ambiguous: false,
+                required: None,
+            },
+        ),
+    ]
+}
+
+pub fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn join_classes(classes: &[CharClass]) -> String {
+    classes.iter().map(|class| class.to_string()).collect::<Vec<_>>().join(",")
+}
+
+fn parse_classes(value: &str) -> Result<Vec<CharClass>, String> {
+    value.split(',').map(str::parse).collect()
+}
+
+pub fn to_spec(options: &GeneratorOptions) -> String {
+    let mut spec = format!("length={} charset={}", options.length, join_classes(&options.charset));
+    if options.charset.contains(&CharClass::Symbols) {
+        spec.push_str(&format!(" symbols={}", options.symbols));
+    }
+    if let Some(required) = &options.required {
+        spec.push_str(&format!(" require={}", join_classes(required)));
+    }
+    if options.exclude_ambiguous {
+        spec.push_str(" exclude-ambiguous");
+    }
+    spec
+}
+
+pub fn parse_spec(spec: &str) -> Result<GeneratorOptions, String> {
+    let mut options = GeneratorOptions::default();
+
+    for part in spec.split_whitespace() {
+        match part.split_once('=') {
+            Some(("length", value)) => {
+                options.length = value.parse().map_err(|_| format!("Invalid length `{}`", value))?
+            }
+            Some(("charset", value)) => options.charset = parse_classes(value)?,
+            Some(("symbols", value)) => options.symbols = value.to_string(),
+            Some(("require", value)) => options.required = Some(parse_classes(value)?),
+            None if part == "exclude-ambiguous" => options.exclude_ambiguous = true,
+            _ => return Err(format!("Unknown profile option `{}`", part)),
+        }
+    }
+
+    options.check()?;
+    Ok(options)
+}
+
+/// Looks the profile up in the vault first, then in the built-in ones.
+pub fn load_profile(db: Option<&Database>, name: &str) -> Result<GeneratorOptions, String> {
+    if let Some(db) = db {
+        match db.get_setting_value(&format!("{}{}", PROFILE_KEY_PREFIX, name)) {
+            Ok(Some(spec)) => return parse_spec(&spec).map_err(|e| format!("Profile {} is invalid: {}", name, e)),
+            Ok(None) | Err(rusqlite::Error::QueryReturnedNoRows) => (),
+            Err(e) => return Err(format!("Database error: {}", e)),
+        }
+    }
+
+    builtin_profiles()
+        .into_iter()
+        .find(|(builtin, _)| *builtin == name)
+        .map(|(_, options)| options)
+        .ok_or_else(|| format!("No generator profile named {}", name))
+}
+
+pub fn save_profile(db: &Database, name: &str, options: &GeneratorOptions) -> Result<(), String> {
+    if !is_valid_profile_name(name) {
+        return Err("Profile names may only contain letters, digits, '-' and '_'".to_string());
+    }
+    if options.symbols.chars().any(char::is_whitespace) {
+        return Err("Symbols of a saved profile cannot contain spaces".to_string());
+    }
+    db.set_setting_value(&format!("{}{}", PROFILE_KEY_PREFIX, name), &to_spec(options))
+        .map_err(|e| format!("Database error: {}", e))
+}
+
+/// Built-in profiles followed by the vault ones, a vault profile replaces the built-in of the same name.
+pub fn list_profiles(db: Option<&Database>) -> Vec<(String, Result<GeneratorOptions, String>)> {
+    let mut profiles: Vec<(String, Result<GeneratorOptions, String>)> = builtin_profiles()
+        .into_iter()
+        .map(|(name, options)| (name.to_string(), Ok(options)))
+        .collect();
+
+    let stored = db.and_then(|db| db.list_settings().ok()).unwrap_or_default();
+    for setting in stored {
+        if let Some(name) = setting.key.strip_prefix(PROFILE_KEY_PREFIX) {
+            let options = parse_spec(&setting.value);
+            match profiles.iter_mut().find(|(existing, _)| existing == name) {
+                Some(profile) => profile.1 = options,
+                None => profiles.push((name.to_string(), options)),
+            }
+        }
+    }
+    profiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_spec_round_trip() {
+        for (_, options) in builtin_profiles() {
+            assert_eq!(parse_spec(&to_spec(&options)).unwrap(), options);
+        }
+
+        let options = parse_spec("length=16 charset=lower,digits,symbols symbols=-_ require=digits").unwrap();
+        assert_eq!(options.length, 16);
+        assert_eq!(options.symbols, "-_");
+        assert_eq!(options.required, Some(vec![CharClass::Digits]));
+        assert!(parse_spec("length=16 colour=blue").is_err());
+    }
+
+    #[test]
+    fn test_vault_profiles() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::new(&temp_dir.path().join("kofl.sqlite")).unwrap();
+        db.initialize().unwrap();
+
+        assert_eq!(load_profile(Some(&db), "pin").unwrap().length, 6);
+        assert!(load_profile(Some(&db), "wifi").is_err());
+
+        let wifi = GeneratorOptions { length: 63, ..GeneratorOptions::default() };
+        save_profile(&db, "wifi", &wifi).unwrap();
+        let pin = GeneratorOptions { length: 8, ..load_profile(None, "pin").unwrap() };
+        save_profile(&db, "pin", &pin).unwrap();
+
+        assert_eq!(load_profile(Some(&db), "wifi").unwrap(), wifi);
+        assert_eq!(load_profile(Some(&db), "pin").unwrap().length, 8);
+        assert_eq!(load_profile(None, "pin").unwrap().length, 6);
+
+        let names: Vec<String> = list_profiles(Some(&db)).into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["strong", "legacy-bank", "pin", "wifi"]);
+        assert!(save_profile(&db, "my profile", &wifi).is_err());
+    }
+}
@@ -0,0 +1,225 @@
+//! CSV exports of other password managers, matched on their header row.
+
+use crate::import::{entry_name, host_of, split_tags, ImportedEntry};
+use csv::ReaderBuilder;
+use std::io::Read;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvFormat {
+    Bitwarden,
+    LastPass,
+    OnePassword,
+    Chrome,
+    KeePass,
+    /// `name,password,username,url,notes,folder,tags`, only the first two are required.
+    Generic,
+}
+
+impl FromStr for CsvFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "bitwarden" => Ok(CsvFormat::Bitwarden),
+            "lastpass" => Ok(CsvFormat::LastPass),
+            "1password" => Ok(CsvFormat::OnePassword),
+            "chrome" => Ok(CsvFormat::Chrome),
+            "keepass" => Ok(CsvFormat::KeePass),
+            "generic" => Ok(CsvFormat::Generic),
+            other => Err(format!("Unknown CSV format `{}`", other)),
+        }
+    }
+}
+
+// accepted header names of each field, compared case insensitively
+struct Columns {
+    name: &'static [&'static str],
+    password: &'static [&'static str],
+    username: &'static [&'static str],
+    url: &'static [&'static str],
+    notes: &'static [&'static str],
+    folder: &'static [&'static str],
+    tags: &'static [&'static str],
+}
+
+impl CsvFormat {
+    fn columns(&self) -> Columns {
+        match self {
+            CsvFormat::Bitwarden => Columns {
+                name: &["name"],
+                password: &["login_password"],
+                username: &["login_username"],
+                url: &["login_uri"],
+                notes: &["notes"],
+                folder: &["folder"],
+                tags: &[],
+            },
+            CsvFormat::LastPass => Columns {
+                name: &["name"],
+                password: &["password"],
+                username: &["username"],
+                url: &["url"],
+                notes: &["extra"],
+                folder: &["grouping"],
+                tags: &[],
+            },
+            CsvFormat::OnePassword => Columns {
+                name: &["title"],
+                password: &["password"],
+                username: &["username"],
+                url: &["url", "website"],
+                notes: &["notes", "notesplain"],
+                folder: &[],
+                tags: &["tags"],
+            },
+            CsvFormat::Chrome => Columns {
+                name: &["name"],
+                password: &["password"],
+                username: &["username"],
+                url: &["url"],
+                notes: &["note"],
+                folder: &[],
+                tags: &[],
+            },
+            CsvFormat::KeePass => Columns {
+                name: &["title"],
+                password: &["password"],
+                username: &["username", "user name"],
+                url: &["url"],
+                notes: &["notes"],
+                folder: &["group"],
+                tags: &["tags"],
+            },
+            CsvFormat::Generic => Columns {
+                name: &["name", "title"],
+                password: &["password"],
+                username: &["username", "login"],
+                url: &["url", "website"],
+                notes: &["notes", "note"],
+                folder: &["folder", "group"],
+                tags: &["tags"],
+            },
+        }
+    }
+}
+
+/// Reads every row of the export. A row that cannot be imported is returned
+/// as an error naming its line, the file itself failing to parse is an error.
+pub fn parse_csv<R: Read>(format: CsvFormat, reader: R) -> Result<Vec<Result<ImportedEntry, String>>, String> {
+    let mut reader = ReaderBuilder::new().flexible(true).from_reader(reader);
+    let header: Vec<String> = reader
+        .headers()
+        .map_err(|e| format!("Cannot read the CSV header: {}", e))?
+        .iter()
+        .map(|name| name.trim_start_matches('\u{feff}').trim().to_lowercase())
+        .collect();
+
+    let columns = format.columns();
+    let column = |aliases: &[&str]| aliases.iter().find_map(|alias| header.iter().position(|name| name == alias));
+    let (Some(name), Some(password)) = (column(columns.name), column(columns.password)) else {
+        return Err(format!("The file has no {} or {} column, is it the right format?", columns.name[0], columns.password[0]));
+    };
+    let kind = if format == CsvFormat::Bitwarden { column(&["type"]) } else { None };
+    let (username, url, notes, folder, tags) =
+        (column(columns.username), column(columns.url), column(columns.notes), column(columns.folder), column(columns.tags));
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| format!("Cannot read the CSV file: {}", e))?;
+        let line = record.position().map(|position| position.line()).unwrap_or_default();
+        let field = |index: Option<usize>| {
+            index.and_then(|index| record.get(index)).map(str::trim).filter(|value| !value.is_empty()).map(String::from)
+        };
+
+        let row = (|| {
+            if kind.is_some_and(|kind| record.get(kind).is_some_and(|value| value != "login")) {
+                return Err("not a login".to_string());
+            }
+            // LastPass exports secure notes with this placeholder URL
+            if format == CsvFormat::LastPass && field(url).as_deref() == Some("http://sn") {
+                return Err("secure note".to_string());
+            }
+            let secret = record.get(password).unwrap_or_default();
+            if secret.is_empty() {
+                return Err("no password".to_string());
+            }
+
+            let title = field(Some(name)).or_else(|| field(url).map(|url| host_of(&url))).unwrap_or_default();
+            let mut group = field(folder);
+            // KeePass puts every group under the root group
+            if format == CsvFormat::KeePass {
+                group = group.filter(|group| group != "Root").map(|group| group.strip_prefix("Root/").map(String::from).unwrap_or(group));
+            }
+
+            Ok(ImportedEntry {
+                name: entry_name(group.as_deref(), &title)?,
+                password: secret.to_string(),
+                username: field(username),
+                url: field(url),
+                notes: field(notes),
+                tags: split_tags(&field(tags).unwrap_or_default()),
+                ..Default::default()
+            })
+        })();
+        rows.push(row.map_err(|reason| format!("line {}: {}", line, reason)));
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(format: CsvFormat, content: &str) -> Vec<Result<ImportedEntry, String>> {
+        parse_csv(format, content.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_formats() {
+        let bitwarden = "folder,favorite,type,name,notes,fields,reprompt,login_uri,login_username,login_password,login_totp\n\
+            Work,,login,GitHub,,,,https://github.com,me,gh-secret,\n\
+            ,,note,Recovery codes,1234,,,,,,\n";
+        let rows = parse(CsvFormat::Bitwarden, bitwarden);
+        let github = rows[0].as_ref().unwrap();
+        assert_eq!((github.name.as_str(), github.password.as_str()), ("Work/GitHub", "gh-secret"));
+        assert_eq!(github.username.as_deref(), Some("me"));
+        assert_eq!(rows[1], Err("line 3: not a login".to_string()));
+
+        let lastpass = "url,username,password,totp,extra,name,grouping,fav\n\
+            https://mail.example.com,me,\"p,ss\",,old account,Mail,Personal,0\n\
+            http://sn,,,,secret note,Note,,0\n";
+        let rows = parse(CsvFormat::LastPass, lastpass);
+        let mail = rows[0].as_ref().unwrap();
+        assert_eq!((mail.name.as_str(), mail.password.as_str()), ("Personal/Mail", "p,ss"));
+        assert_eq!(mail.notes.as_deref(), Some("old account"));
+        assert!(rows[1].is_err());
+
+        let onepassword = "\u{feff}Title,Url,Username,Password,OTPAuth,Favorite,Archived,Tags,Notes\n\
+            Bank,https://bank.example.com,me,\" spaced \",,false,false,\"finance,two words\",\n";
+        let bank = parse(CsvFormat::OnePassword, onepassword).remove(0).unwrap();
+        assert_eq!(bank.password, " spaced ");
+        assert_eq!(bank.tags, vec!["finance", "two-words"]);
+
+        let chrome = "name,url,username,password,note\n,https://accounts.example.com/login?x=1,me,secret,\n";
+        let account = parse(CsvFormat::Chrome, chrome).remove(0).unwrap();
+        assert_eq!(account.name, "accounts.example.com");
+
+        let keepass = "\"Group\",\"Title\",\"Username\",\"Password\",\"URL\",\"Notes\"\n\
+            \"Root/Internet\",\"Forum\",\"me\",\"pw\",\"\",\"\"\n\
+            \"Root\",\"Empty\",\"me\",\"\",\"\",\"\"\n";
+        let rows = parse(CsvFormat::KeePass, keepass);
+        assert_eq!(rows[0].as_ref().unwrap().name, "Internet/Forum");
+        assert_eq!(rows[1], Err("line 3: no password".to_string()));
+
+        let generic = "password,name\nsecret,shop\n";
+        let shop = parse(CsvFormat::Generic, generic).remove(0).unwrap();
+        assert_eq!((shop.name.as_str(), shop.username.clone()), ("shop", None));
+    }
+
+    #[test]
+    fn test_wrong_format() {
+        let chrome = "name,url,username,password,note\n";
+        assert!(parse_csv(CsvFormat::Bitwarden, chrome.as_bytes()).is_err());
+    }
+}
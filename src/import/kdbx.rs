@@ -0,0 +1,867 @@
+//! KeePass databases in the KDBX 3.1 and 4 formats, decrypted in memory.
+//!
+//! Only what the import needs is read: the groups become folders and every
+//! entry outside the recycle bin becomes an `ImportedEntry` with its history.
+//! Attachments, icons and custom data are ignored.
+
+use crate::import::{entry_name, host_of, split_tags, ImportedEntry, PreviousSecret};
+use aes::cipher::{BlockDecryptMut, BlockEncrypt, KeyInit, KeyIvInit, StreamCipher};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use hmac::{Hmac, Mac};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::HashMap;
+use std::io::Read;
+
+const SIGNATURE_1: u32 = 0x9AA2_D903;
+const SIGNATURE_2: u32 = 0xB54B_FB67;
+
+const CIPHER_AES256: [u8; 16] = uuid(0x31c1f2e6_bf71_4350_be58_05216afc5aff);
+const CIPHER_CHACHA20: [u8; 16] = uuid(0xd6038a2b_8b6f_4cb5_a524_339a31dbb59a);
+const KDF_AES_KDBX3: [u8; 16] = uuid(0xc9d9f39a_628a_4460_bf74_0d08c18a4fea);
+const KDF_AES_KDBX4: [u8; 16] = uuid(0x7c02bb82_79a7_4ac0_927d_114a00648238);
+const KDF_ARGON2D: [u8; 16] = uuid(0xef636ddf_8c29_444b_91f7_a9a403e30a0c);
+const KDF_ARGON2ID: [u8; 16] = uuid(0x9e298b19_56db_4773_b23d_fc3ec6f0a1e6);
+
+const INNER_STREAM_SALSA20: u32 = 2;
+const INNER_STREAM_CHACHA20: u32 = 3;
+const SALSA20_NONCE: [u8; 8] = [0xE8, 0x30, 0x09, 0x4B, 0x97, 0x20, 0x5D, 0x2A];
+
+// seconds from 0001-01-01, where KDBX 4 times start, to the Unix epoch
+const KDBX4_EPOCH_OFFSET: i64 = 62_135_596_800;
+
+const WRONG_KEY: &str = "Wrong password or key file, or the database is corrupted";
+
+const fn uuid(value: u128) -> [u8; 16] {
+    value.to_be_bytes()
+}
+
+type Aes256CbcDec = cbc::Decryptor<aes::Aes256>;
+type HmacSha256 = Hmac<Sha256>;
+
+/// Reads every entry of the database. An entry that cannot be imported, or a
+/// protected custom field left out of one, is returned as an error naming it,
+/// a database that cannot be opened is an error.
+pub fn read_kdbx(data: &[u8], password: &str, key_file: Option<&[u8]>) -> Result<Vec<Result<ImportedEntry, String>>, String> {
+    let composite_key = composite_key(password, key_file);
+    let mut input = Input::new(data);
+    if input.u32()? != SIGNATURE_1 || input.u32()? != SIGNATURE_2 {
+        return Err("Not a KeePass 2 database".to_string());
+    }
+    let major = input.u32()? >> 16;
+
+    let (xml, inner_stream) = match major {
+        3 => decrypt_kdbx3(input, &composite_key)?,
+        4 => decrypt_kdbx4(data, input, &composite_key)?,
+        _ => return Err(format!("KDBX {} databases are not supported, only 3.1 and 4", major)),
+    };
+    parse_xml(&xml, inner_stream)
+}
+
+/// The database key, from the password and the key file. An empty password
+/// is left out when there is a key file, as KeePass does for key file only databases.
+fn composite_key(password: &str, key_file: Option<&[u8]>) -> [u8; 32] {
+    let mut composite = Sha256::new();
+    if !password.is_empty() || key_file.is_none() {
+        composite.update(Sha256::digest(password.as_bytes()));
+    }
+    if let Some(key_file) = key_file {
+        composite.update(key_file_key(key_file));
+    }
+    composite.finalize().into()
+}
+
+// the formats KeePass accepts: XML, 32 raw bytes, 64 hex digits or any file hashed
+fn key_file_key(content: &[u8]) -> [u8; 32] {
+    if let Ok(text) = std::str::from_utf8(content) {
+        if let Some(key) = xml_key_file(text) {
+            return key;
+        }
+        let hex_key = text.trim();
+        if hex_key.len() == 64 {
+            if let Ok(key) = hex::decode(hex_key) {
+                return key.try_into().expect("64 hex digits are 32 bytes");
+            }
+        }
+    }
+    if content.len() == 32 {
+        return content.try_into().expect("checked length");
+    }
+    Sha256::digest(content).into()
+}
+
+fn xml_key_file(text: &str) -> Option<[u8; 32]> {
+    if !text.contains("<KeyFile>") {
+        return None;
+    }
+    let start = text.find("<Data")?;
+    let open_end = start + text[start..].find('>')?;
+    let close = open_end + text[open_end..].find("</Data>")?;
+    let data = &text[open_end + 1..close];
+
+    // version 2.0 stores hex with a Hash attribute, version 1.0 base64
+    let key = if text[start..open_end].contains("Hash=") {
+        hex::decode(data.split_whitespace().collect::<String>()).ok()?
+    } else {
+        BASE64.decode(data.trim()).ok()?
+    };
+    key.try_into().ok()
+}
+
+struct Input<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Input<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Input { data, position: 0 }
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], String> {
+        let end = self.position.checked_add(length).filter(|end| *end <= self.data.len());
+        let end = end.ok_or("The database is truncated")?;
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let bytes = &self.data[self.position..];
+        self.position = self.data.len();
+        bytes
+    }
+}
+
+#[derive(Default)]
+struct Header {
+    cipher: Vec<u8>,
+    compressed: bool,
+    master_seed: Vec<u8>,
+    iv: Vec<u8>,
+    // KDBX 3.1 key derivation and inner stream
+    transform_seed: Vec<u8>,
+    transform_rounds: u64,
+    protected_stream_key: Vec<u8>,
+    stream_start_bytes: Vec<u8>,
+    inner_stream: u32,
+    // KDBX 4 key derivation
+    kdf_parameters: HashMap<String, Vec<u8>>,
+}
+
+fn read_header(input: &mut Input, major: u32) -> Result<Header, String> {
+    let mut header = Header::default();
+    loop {
+        let id = input.u8()?;
+        let length = if major == 3 { input.u16()? as usize } else { input.u32()? as usize };
+        let value = input.take(length)?;
+        match id {
+            0 => return Ok(header),
+            2 => header.cipher = value.to_vec(),
+            3 => header.compressed = le_u64(value) == 1,
+            4 => header.master_seed = value.to_vec(),
+            5 => header.transform_seed = value.to_vec(),
+            6 => header.transform_rounds = le_u64(value),
+            7 => header.iv = value.to_vec(),
+            8 => header.protected_stream_key = value.to_vec(),
+            9 => header.stream_start_bytes = value.to_vec(),
+            10 => header.inner_stream = le_u64(value) as u32,
+            11 => header.kdf_parameters = read_variant_dictionary(value)?,
+            _ => (),
+        }
+    }
+}
+
+// little endian integer of up to 8 bytes
+fn le_u64(bytes: &[u8]) -> u64 {
+    bytes.iter().take(8).rev().fold(0, |value, byte| value << 8 | *byte as u64)
+}
+
+// the KDBX 4 key/value map, values are kept as their raw bytes
+fn read_variant_dictionary(data: &[u8]) -> Result<HashMap<String, Vec<u8>>, String> {
+    let mut input = Input::new(data);
+    if input.u16()? >> 8 != 1 {
+        return Err("Unsupported key derivation parameters".to_string());
+    }
+    let mut values = HashMap::new();
+    loop {
+        if input.u8()? == 0 {
+            return Ok(values);
+        }
+        let name_length = input.u32()? as usize;
+        let name = String::from_utf8_lossy(input.take(name_length)?).into_owned();
+        let value_length = input.u32()? as usize;
+        values.insert(name, input.take(value_length)?.to_vec());
+    }
+}
+
+fn decrypt_kdbx3(mut input: Input, composite_key: &[u8; 32]) -> Result<(Vec<u8>, InnerStream), String> {
+    let header = read_header(&mut input, 3)?;
+    let transformed = aes_kdf(composite_key, &header.transform_seed, header.transform_rounds)?;
+    let key = master_key(&header.master_seed, &transformed);
+
+    let payload = decrypt_payload(&header.cipher, &key, &header.iv, input.rest().to_vec())?;
+    if header.stream_start_bytes.is_empty() || !payload.starts_with(&header.stream_start_bytes) {
+        return Err(WRONG_KEY.to_string());
+    }
+    let blocks = read_hashed_blocks(&payload[header.stream_start_bytes.len()..])?;
+    let xml = decompress(blocks, header.compressed)?;
+
+    Ok((xml, InnerStream::new(header.inner_stream, &header.protected_stream_key)?))
+}
+
+fn decrypt_kdbx4(data: &[u8], mut input: Input, composite_key: &[u8; 32]) -> Result<(Vec<u8>, InnerStream), String> {
+    let header = read_header(&mut input, 4)?;
+    let header_bytes = &data[..input.position];
+    let header_hash = input.take(32)?;
+    let header_hmac = input.take(32)?;
+    if Sha256::digest(header_bytes).as_slice() != header_hash {
+        return Err("The database header is corrupted".to_string());
+    }
+
+    let transformed = kdbx4_kdf(&header.kdf_parameters, composite_key)?;
+    let key = master_key(&header.master_seed, &transformed);
+    let hmac_key: [u8; 64] = Sha512::new()
+        .chain_update(&header.master_seed)
+        .chain_update(transformed)
+        .chain_update([1u8])
+        .finalize()
+        .into();
+
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(&block_hmac_key(&hmac_key, u64::MAX)).expect("HMAC takes any key");
+    mac.update(header_bytes);
+    if mac.verify_slice(header_hmac).is_err() {
+        return Err(WRONG_KEY.to_string());
+    }
+
+    let encrypted = read_hmac_blocks(&mut input, &hmac_key)?;
+    let payload = decrypt_payload(&header.cipher, &key, &header.iv, encrypted)?;
+    let content = decompress(payload, header.compressed)?;
+
+    // the inner header holds the key of the protected values
+    let mut inner = Input::new(&content);
+    let (mut stream_id, mut stream_key) = (0, Vec::new());
+    loop {
+        let id = inner.u8()?;
+        let length = inner.u32()? as usize;
+        let value = inner.take(length)?;
+        match id {
+            0 => break,
+            1 => stream_id = le_u64(value) as u32,
+            2 => stream_key = value.to_vec(),
+            _ => (),
+        }
+    }
+    Ok((inner.rest().to_vec(), InnerStream::new(stream_id, &stream_key)?))
+}
+
+fn master_key(master_seed: &[u8], transformed: &[u8; 32]) -> [u8; 32] {
+    Sha256::new().chain_update(master_seed).chain_update(transformed).finalize().into()
+}
+
+fn block_hmac_key(hmac_key: &[u8; 64], index: u64) -> [u8; 64] {
+    Sha512::new().chain_update(index.to_le_bytes()).chain_update(hmac_key).finalize().into()
+}
+
+fn aes_kdf(composite_key: &[u8; 32], seed: &[u8], rounds: u64) -> Result<[u8; 32], String> {
+    let cipher = aes::Aes256::new_from_slice(seed).map_err(|_| "Invalid key derivation seed".to_string())?;
+    let mut key = *composite_key;
+    let (first, second) = key.split_at_mut(16);
+    for _ in 0..rounds {
+        cipher.encrypt_block(first.into());
+        cipher.encrypt_block(second.into());
+    }
+    Ok(Sha256::digest(key).into())
+}
+
+fn kdbx4_kdf(parameters: &HashMap<String, Vec<u8>>, composite_key: &[u8; 32]) -> Result<[u8; 32], String> {
+    let parameter = |name: &str| parameters.get(name).ok_or(format!("Key derivation parameter {} is missing", name));
+    let kdf = parameter("$UUID")?.as_slice();
+
+    if kdf == KDF_AES_KDBX3 || kdf == KDF_AES_KDBX4 {
+        return aes_kdf(composite_key, parameter("S")?, le_u64(parameter("R")?));
+    }
+
+    let algorithm = match kdf {
+        _ if kdf == KDF_ARGON2D => argon2::Algorithm::Argon2d,
+        _ if kdf == KDF_ARGON2ID => argon2::Algorithm::Argon2id,
+        _ => return Err("Unsupported key derivation function".to_string()),
+    };
+    let version = match le_u64(parameter("V")?) {
+        0x10 => argon2::Version::V0x10,
+        _ => argon2::Version::V0x13,
+    };
+    let memory_kib = (le_u64(parameter("M")?) / 1024) as u32;
+    let params = argon2::Params::new(memory_kib, le_u64(parameter("I")?) as u32, le_u64(parameter("P")?) as u32, Some(32))
+        .map_err(|e| format!("Invalid Argon2 parameters: {}", e))?;
+
+    let mut transformed = [0u8; 32];
+    argon2::Argon2::new(algorithm, version, params)
+        .hash_password_into(composite_key, parameter("S")?, &mut transformed)
+        .map_err(|e| format!("Key derivation failed: {}", e))?;
+    Ok(transformed)
+}
+
+fn decrypt_payload(cipher: &[u8], key: &[u8; 32], iv: &[u8], mut payload: Vec<u8>) -> Result<Vec<u8>, String> {
+    if cipher == CIPHER_AES256 {
+        let decryptor = Aes256CbcDec::new_from_slices(key, iv).map_err(|_| "Invalid encryption IV".to_string())?;
+        let length = decryptor
+            .decrypt_padded_mut::<cbc::cipher::block_padding::Pkcs7>(&mut payload)
+            .map_err(|_| WRONG_KEY.to_string())?
+            .len();
+        payload.truncate(length);
+        Ok(payload)
+    } else if cipher == CIPHER_CHACHA20 {
+        let mut chacha = chacha20::ChaCha20::new_from_slices(key, iv).map_err(|_| "Invalid encryption IV".to_string())?;
+        chacha.apply_keystream(&mut payload);
+        Ok(payload)
+    } else {
+        Err("Unsupported cipher, only AES-256 and ChaCha20 databases can be imported".to_string())
+    }
+}
+
+// KDBX 3.1 payload: blocks of [index, SHA-256, length, data], an empty one at the end
+fn read_hashed_blocks(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut input = Input::new(data);
+    let mut content = Vec::new();
+    loop {
+        input.u32()?;
+        let hash = input.take(32)?;
+        let length = input.u32()? as usize;
+        if length == 0 {
+            return Ok(content);
+        }
+        let block = input.take(length)?;
+        if Sha256::digest(block).as_slice() != hash {
+            return Err("A block of the database is corrupted".to_string());
+        }
+        content.extend_from_slice(block);
+    }
+}
+
+// KDBX 4 payload: blocks of [HMAC, length, data], an empty one at the end
+fn read_hmac_blocks(input: &mut Input, hmac_key: &[u8; 64]) -> Result<Vec<u8>, String> {
+    let mut content = Vec::new();
+    for index in 0u64.. {
+        let hmac = input.take(32)?;
+        let length = input.u32()?;
+        let block = input.take(length as usize)?;
+
+        let mut mac = <HmacSha256 as Mac>::new_from_slice(&block_hmac_key(hmac_key, index)).expect("HMAC takes any key");
+        mac.update(&index.to_le_bytes());
+        mac.update(&length.to_le_bytes());
+        mac.update(block);
+        if mac.verify_slice(hmac).is_err() {
+            return Err("A block of the database is corrupted".to_string());
+        }
+        if length == 0 {
+            break;
+        }
+        content.extend_from_slice(block);
+    }
+    Ok(content)
+}
+
+fn decompress(data: Vec<u8>, compressed: bool) -> Result<Vec<u8>, String> {
+    if !compressed {
+        return Ok(data);
+    }
+    let mut content = Vec::new();
+    GzDecoder::new(data.as_slice())
+        .read_to_end(&mut content)
+        .map_err(|e| format!("Cannot decompress the database: {}", e))?;
+    Ok(content)
+}
+
+/// Cipher of the protected values, applied in the order they appear in the XML.
+enum InnerStream {
+    Salsa20(salsa20::Salsa20),
+    ChaCha20(chacha20::ChaCha20),
+}
+
+impl InnerStream {
+    fn new(id: u32, key: &[u8]) -> Result<Self, String> {
+        match id {
+            INNER_STREAM_SALSA20 => {
+                let key = Sha256::digest(key);
+                Ok(InnerStream::Salsa20(salsa20::Salsa20::new(&key, &SALSA20_NONCE.into())))
+            }
+            INNER_STREAM_CHACHA20 => {
+                let hash = Sha512::digest(key);
+                let chacha = chacha20::ChaCha20::new_from_slices(&hash[..32], &hash[32..44]).expect("sizes are fixed");
+                Ok(InnerStream::ChaCha20(chacha))
+            }
+            _ => Err("Unsupported protection of the passwords in the database".to_string()),
+        }
+    }
+
+    fn reveal(&mut self, protected: &str) -> Result<Vec<u8>, String> {
+        let mut value = BASE64.decode(protected.trim()).map_err(|_| "A protected value is not valid base64".to_string())?;
+        match self {
+            InnerStream::Salsa20(cipher) => cipher.apply_keystream(&mut value),
+            InnerStream::ChaCha20(cipher) => cipher.apply_keystream(&mut value),
+        }
+        Ok(value)
+    }
+}
+
+#[derive(Default)]
+struct GroupState {
+    name: String,
+    in_recycle_bin: bool,
+}
+
+#[derive(Default)]
+struct EntryState {
+    // (key, value, protected) in the order of the file
+    strings: Vec<(String, String, bool)>,
+    tags: String,
+    modified: Option<DateTime<Utc>>,
+    history: Vec<EntryState>,
+}
+
+impl EntryState {
+    fn string(&self, key: &str) -> Option<&str> {
+        self.strings.iter().find(|(name, _, _)| name == key).map(|(_, value, _)| value.as_str()).filter(|value| !value.is_empty())
+    }
+}
+
+const STANDARD_FIELDS: [&str; 5] = ["Title", "UserName", "Password", "URL", "Notes"];
+
+fn parse_xml(xml: &[u8], mut inner_stream: InnerStream) -> Result<Vec<Result<ImportedEntry, String>>, String> {
+    let mut reader = Reader::from_reader(xml);
+    let mut buffer = Vec::new();
+
+    let mut path: Vec<String> = Vec::new();
+    let mut groups: Vec<GroupState> = Vec::new();
+    let mut entries: Vec<EntryState> = Vec::new();
+    let mut recycle_bin = None;
+    let (mut text, mut protected) = (String::new(), false);
+    let (mut key, mut value, mut value_protected) = (String::new(), String::new(), false);
+    let mut rows = Vec::new();
+
+    loop {
+        buffer.clear();
+        let event = reader.read_event_into(&mut buffer).map_err(|e| format!("The database content is invalid: {}", e))?;
+        let (start, end) = match &event {
+            Event::Start(element) => (Some(element.clone()), None),
+            Event::Empty(element) => (Some(element.clone()), Some(element.name().as_ref().to_vec())),
+            Event::End(element) => (None, Some(element.name().as_ref().to_vec())),
+            Event::Text(content) => {
+                text.push_str(&content.unescape().map_err(|e| format!("The database content is invalid: {}", e))?);
+                (None, None)
+            }
+            Event::CData(content) => {
+                text.push_str(&String::from_utf8_lossy(content));
+                (None, None)
+            }
+            Event::Eof => break,
+            _ => (None, None),
+        };
+
+        if let Some(element) = start {
+            let name = String::from_utf8_lossy(element.name().as_ref()).into_owned();
+            text.clear();
+            match name.as_str() {
+                "Group" => groups.push(GroupState {
+                    in_recycle_bin: groups.last().is_some_and(|parent| parent.in_recycle_bin),
+                    ..Default::default()
+                }),
+                "Entry" => entries.push(EntryState::default()),
+                "String" => {
+                    key.clear();
+                    value.clear();
+                }
+                _ => (),
+            }
+            protected = is_protected(&element);
+            path.push(name);
+        }
+
+        let Some(_) = end else { continue };
+        let name = path.pop().unwrap_or_default();
+        let parent = path.last().map(String::as_str).unwrap_or_default();
+        let grandparent = path.len().checked_sub(2).and_then(|index| path.get(index)).map(String::as_str).unwrap_or_default();
+        // the stream runs over every protected element, binaries and custom data included
+        let revealed = if std::mem::take(&mut protected) { Some(inner_stream.reveal(&text)?) } else { None };
+        match (name.as_str(), parent) {
+            ("RecycleBinUUID", "Meta") => recycle_bin = Some(text.trim().to_string()),
+            ("UUID", "Group") => {
+                if recycle_bin.as_deref() == Some(text.trim()) {
+                    if let Some(group) = groups.last_mut() {
+                        group.in_recycle_bin = true;
+                    }
+                }
+            }
+            ("Name", "Group") => {
+                if let Some(group) = groups.last_mut() {
+                    group.name = text.clone();
+                }
+            }
+            ("Group", _) => {
+                groups.pop();
+            }
+            ("Key", "String") => key = text.clone(),
+            ("Value", "String") => {
+                value_protected = revealed.is_some();
+                value = match revealed {
+                    Some(bytes) => String::from_utf8(bytes).map_err(|_| WRONG_KEY.to_string())?,
+                    None => text.clone(),
+                };
+            }
+            ("String", "Entry") => {
+                if let Some(entry) = entries.last_mut() {
+                    entry.strings.push((key.clone(), value.clone(), value_protected));
+                }
+                value_protected = false;
+            }
+            ("Tags", "Entry") => {
+                if let Some(entry) = entries.last_mut() {
+                    entry.tags = text.clone();
+                }
+            }
+            ("LastModificationTime", "Times") if grandparent == "Entry" => {
+                if let Some(entry) = entries.last_mut() {
+                    entry.modified = parse_time(text.trim());
+                }
+            }
+            ("Entry", "History") => {
+                let previous = entries.pop().unwrap_or_default();
+                if let Some(entry) = entries.last_mut() {
+                    entry.history.push(previous);
+                }
+            }
+            ("Entry", _) => {
+                let entry = entries.pop().unwrap_or_default();
+                if groups.last().is_some_and(|group| group.in_recycle_bin) {
+                    continue;
+                }
+                // the root group is the database itself, not a folder
+                let folder = groups.iter().skip(1).map(|group| group.name.as_str()).collect::<Vec<_>>().join("/");
+                match to_imported(&folder, entry) {
+                    Ok((imported, dropped)) => {
+                        rows.push(Ok(imported));
+                        rows.extend(dropped.into_iter().map(Err));
+                    }
+                    Err(e) => rows.push(Err(e)),
+                }
+            }
+            _ => (),
+        }
+        text.clear();
+    }
+    Ok(rows)
+}
+
+fn is_protected(element: &BytesStart) -> bool {
+    element
+        .attributes()
+        .flatten()
+        .any(|attribute| attribute.key.as_ref() == b"Protected" && attribute.value.as_ref().eq_ignore_ascii_case(b"true"))
+}
+
+// KDBX 3.1 writes ISO 8601, KDBX 4 base64 of the seconds since year 1
+fn parse_time(text: &str) -> Option<DateTime<Utc>> {
+    if text.contains('-') {
+        return DateTime::parse_from_rfc3339(text).ok().map(|time| time.with_timezone(&Utc));
+    }
+    let bytes: [u8; 8] = BASE64.decode(text).ok()?.try_into().ok()?;
+    DateTime::from_timestamp(i64::from_le_bytes(bytes) - KDBX4_EPOCH_OFFSET, 0)
+}
+
+/// The entry and the protected custom fields left out of it.
+fn to_imported(folder: &str, entry: EntryState) -> Result<(ImportedEntry, Vec<String>), String> {
+    let url = entry.string("URL").map(String::from);
+    let title = entry.string("Title").map(String::from).or_else(|| url.as_deref().map(host_of)).unwrap_or_default();
+    let label = if title.is_empty() { "an entry without title".to_string() } else { title.clone() };
+
+    let password = entry.string("Password").ok_or(format!("{}: no password", label))?;
+    let name = entry_name(Some(folder), &title).map_err(|e| format!("{}: {}", label, e))?;
+
+    // notes are not encrypted, so protected custom fields are left out
+    let mut notes: Vec<String> = entry.string("Notes").map(String::from).into_iter().collect();
+    let mut dropped = Vec::new();
+    for (key, value, protected) in &entry.strings {
+        if STANDARD_FIELDS.contains(&key.as_str()) || value.is_empty() {
+            continue;
+        }
+        if *protected {
+            dropped.push(format!("{}: the protected field {}, notes are not encrypted", name, key));
+        } else {
+            notes.push(format!("{}: {}", key, value));
+        }
+    }
+
+    let mut history: Vec<PreviousSecret> = entry
+        .history
+        .iter()
+        .filter_map(|previous| {
+            let password = previous.string("Password")?;
+            Some(PreviousSecret { password: password.to_string(), set_at: previous.modified, replaced_at: None })
+        })
+        .collect();
+    history.sort_by_key(|previous| previous.set_at);
+    // versions that only changed other fields repeat the same password
+    history.dedup_by(|next, previous| next.password == previous.password);
+    if history.last().is_some_and(|last| last.password == password) {
+        history.pop();
+    }
+
+    let imported = ImportedEntry {
+        name,
+        password: password.to_string(),
+        username: entry.string("UserName").map(String::from),
+        url,
+        notes: if notes.is_empty() { None } else { Some(notes.join("\n")) },
+        tags: split_tags(&entry.tags),
+        updated_at: entry.modified,
+        history,
+        ..Default::default()
+    };
+    Ok((imported, dropped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes::cipher::BlockEncryptMut;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    const BIN_UUID: &str = "AAAAAAAAAAAAAAAAAAAAAQ==";
+
+    // the database content, protected values encrypted in document order
+    fn content(stream: &mut InnerStream, times: [&str; 3]) -> Vec<u8> {
+        let mut protect = |value: &[u8]| {
+            let mut bytes = value.to_vec();
+            match stream {
+                InnerStream::Salsa20(cipher) => cipher.apply_keystream(&mut bytes),
+                InnerStream::ChaCha20(cipher) => cipher.apply_keystream(&mut bytes),
+            }
+            BASE64.encode(bytes)
+        };
+        let string = |key: &str, value: &str| format!("<String><Key>{}</Key><Value>{}</Value></String>", key, value);
+        let secret = |value: String| format!("<String><Key>Password</Key><Value Protected=\"True\">{}</Value></String>", value);
+        let modified = |time: &str| format!("<Times><LastModificationTime>{}</LastModificationTime></Times>", time);
+
+        // attachments come first and use the stream too, even though they are not imported
+        let attachment = protect(b"\x00\xff attachment");
+        let forum = format!(
+            "<Entry>{}{}{}{}{}{}<Tags>forum; old stuff</Tags>{}</Entry>",
+            string("Title", "Forum"),
+            string("UserName", "me &amp; co"),
+            secret(protect(b"pw & more")),
+            string("Notes", "line one\nline two"),
+            string("Recovery", "1234"),
+            format!("<String><Key>PIN</Key><Value Protected=\"True\">{}</Value></String>", protect(b"0000")),
+            modified(times[2]),
+        );
+        let history = |password: String, time: &str| format!("<Entry>{}{}{}</Entry>", string("Title", "Mail"), secret(password), modified(time));
+        let mail = format!(
+            "<Entry>{}{}{}{}<History>{}{}</History></Entry>",
+            string("Title", "Mail"),
+            string("URL", "https://mail.example.com"),
+            secret(protect(b"third")),
+            modified(times[2]),
+            history(protect(b"first"), times[0]),
+            history(protect(b"second"), times[1]),
+        );
+        let deleted = format!("<Entry>{}{}</Entry>", string("Title", "Old"), secret(protect(b"gone")));
+        let untitled = format!("<Entry>{}<String><Key>Password</Key><Value Protected=\"True\"/></String></Entry>", string("URL", "https://shop.example.com/cart"));
+
+        format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\" standalone=\"yes\"?>\n<KeePassFile><Meta>\
+            <Binaries><Binary ID=\"0\" Protected=\"True\">{attachment}</Binary></Binaries><RecycleBinUUID>{bin}</RecycleBinUUID></Meta>\
+            <Root><Group><UUID>AAAAAAAAAAAAAAAAAAAAAA==</UUID><Name>Passwords</Name>{forum}\
+            <Group><UUID>AAAAAAAAAAAAAAAAAAAAAg==</UUID><Name>Internet</Name>{mail}{untitled}</Group>\
+            <Group><UUID>{bin}</UUID><Name>Recycle Bin</Name>{deleted}</Group>\
+            </Group></Root></KeePassFile>",
+            bin = BIN_UUID
+        )
+        .into_bytes()
+    }
+
+    fn field(id: u8, value: &[u8], major: u32, header: &mut Vec<u8>) {
+        header.push(id);
+        if major == 3 {
+            header.extend_from_slice(&(value.len() as u16).to_le_bytes());
+        } else {
+            header.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        }
+        header.extend_from_slice(value);
+    }
+
+    fn signature(version: u32) -> Vec<u8> {
+        [SIGNATURE_1.to_le_bytes(), SIGNATURE_2.to_le_bytes(), version.to_le_bytes()].concat()
+    }
+
+    // AES-256, AES-KDF, gzip and Salsa20 protected values
+    fn write_kdbx3(password: &str) -> Vec<u8> {
+        let (seed, transform_seed, iv, stream_key, start) = ([1u8; 32], [2u8; 32], [3u8; 16], [4u8; 32], [5u8; 32]);
+        let mut data = signature(0x0003_0001);
+        field(2, &CIPHER_AES256, 3, &mut data);
+        field(3, &1u32.to_le_bytes(), 3, &mut data);
+        field(4, &seed, 3, &mut data);
+        field(5, &transform_seed, 3, &mut data);
+        field(6, &10u64.to_le_bytes(), 3, &mut data);
+        field(7, &iv, 3, &mut data);
+        field(8, &stream_key, 3, &mut data);
+        field(9, &start, 3, &mut data);
+        field(10, &INNER_STREAM_SALSA20.to_le_bytes(), 3, &mut data);
+        field(0, b"\r\n\r\n", 3, &mut data);
+
+        let mut stream = InnerStream::new(INNER_STREAM_SALSA20, &stream_key).unwrap();
+        let xml = content(&mut stream, ["2020-01-01T00:00:00Z", "2021-01-01T00:00:00Z", "2022-01-01T00:00:00Z"]);
+        let mut gzip = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(&xml).unwrap();
+        let compressed = gzip.finish().unwrap();
+
+        let mut payload = start.to_vec();
+        for (index, block) in [compressed.as_slice(), &[]].iter().enumerate() {
+            payload.extend_from_slice(&(index as u32).to_le_bytes());
+            payload.extend_from_slice(&if block.is_empty() { [0u8; 32] } else { Sha256::digest(block).into() });
+            payload.extend_from_slice(&(block.len() as u32).to_le_bytes());
+            payload.extend_from_slice(block);
+        }
+
+        let transformed = aes_kdf(&composite_key(password, None), &transform_seed, 10).unwrap();
+        let key = master_key(&seed, &transformed);
+        let length = payload.len();
+        payload.resize(length + 16, 0);
+        let encrypted = cbc::Encryptor::<aes::Aes256>::new_from_slices(&key, &iv)
+            .unwrap()
+            .encrypt_padded_mut::<cbc::cipher::block_padding::Pkcs7>(&mut payload, length)
+            .unwrap()
+            .to_vec();
+        data.extend_from_slice(&encrypted);
+        data
+    }
+
+    // ChaCha20, Argon2d, no compression and ChaCha20 protected values
+    fn write_kdbx4(password: &str, key_file: Option<&[u8]>) -> Vec<u8> {
+        let (seed, salt, iv, stream_key) = ([1u8; 32], [2u8; 32], [3u8; 12], [4u8; 64]);
+        let mut kdf = 0x0100u16.to_le_bytes().to_vec();
+        let parameters: [(u8, &str, Vec<u8>); 6] = [
+            (0x42, "$UUID", KDF_ARGON2D.to_vec()),
+            (0x42, "S", salt.to_vec()),
+            (0x04, "P", 1u32.to_le_bytes().to_vec()),
+            (0x05, "M", (64u64 * 1024).to_le_bytes().to_vec()),
+            (0x05, "I", 2u64.to_le_bytes().to_vec()),
+            (0x04, "V", 0x13u32.to_le_bytes().to_vec()),
+        ];
+        for (kind, name, value) in parameters {
+            kdf.push(kind);
+            kdf.extend_from_slice(&(name.len() as u32).to_le_bytes());
+            kdf.extend_from_slice(name.as_bytes());
+            kdf.extend_from_slice(&(value.len() as u32).to_le_bytes());
+            kdf.extend_from_slice(&value);
+        }
+        kdf.push(0);
+
+        let mut data = signature(0x0004_0000);
+        field(2, &CIPHER_CHACHA20, 4, &mut data);
+        field(3, &0u32.to_le_bytes(), 4, &mut data);
+        field(4, &seed, 4, &mut data);
+        field(7, &iv, 4, &mut data);
+        field(11, &kdf, 4, &mut data);
+        field(0, b"\r\n\r\n", 4, &mut data);
+
+        let transformed = kdbx4_kdf(&read_variant_dictionary(&kdf).unwrap(), &composite_key(password, key_file)).unwrap();
+        let key = master_key(&seed, &transformed);
+        let hmac_key: [u8; 64] = Sha512::new().chain_update(seed).chain_update(transformed).chain_update([1u8]).finalize().into();
+        let hmac = |index: u64, parts: &[&[u8]]| {
+            let mut mac = <HmacSha256 as Mac>::new_from_slice(&block_hmac_key(&hmac_key, index)).unwrap();
+            parts.iter().for_each(|part| mac.update(part));
+            mac.finalize().into_bytes().to_vec()
+        };
+        let header_hmac = hmac(u64::MAX, &[&data]);
+        data.extend_from_slice(&Sha256::digest(&data));
+        data.extend_from_slice(&header_hmac);
+
+        let mut inner = Vec::new();
+        field(1, &INNER_STREAM_CHACHA20.to_le_bytes(), 4, &mut inner);
+        field(2, &stream_key, 4, &mut inner);
+        field(0, &[], 4, &mut inner);
+        let mut stream = InnerStream::new(INNER_STREAM_CHACHA20, &stream_key).unwrap();
+        // 2020, 2021 and 2022-01-01 in seconds since year 1
+        let time = |unix: i64| BASE64.encode((unix + KDBX4_EPOCH_OFFSET).to_le_bytes());
+        let (first, second, third) = (time(1_577_836_800), time(1_609_459_200), time(1_640_995_200));
+        inner.extend_from_slice(&content(&mut stream, [&first, &second, &third]));
+        chacha20::ChaCha20::new_from_slices(&key, &iv).unwrap().apply_keystream(&mut inner);
+
+        for (index, block) in [inner.as_slice(), &[]].iter().enumerate() {
+            let length = (block.len() as u32).to_le_bytes();
+            data.extend_from_slice(&hmac(index as u64, &[&(index as u64).to_le_bytes(), &length, block]));
+            data.extend_from_slice(&length);
+            data.extend_from_slice(block);
+        }
+        data
+    }
+
+    fn check_entries(rows: Vec<Result<ImportedEntry, String>>) {
+        assert_eq!(rows.len(), 4, "the recycle bin is skipped");
+
+        let forum = rows[0].as_ref().unwrap();
+        assert_eq!((forum.name.as_str(), forum.password.as_str()), ("Forum", "pw & more"));
+        assert_eq!(forum.username.as_deref(), Some("me & co"));
+        assert_eq!(forum.notes.as_deref(), Some("line one\nline two\nRecovery: 1234"));
+        assert_eq!(forum.tags, vec!["forum", "old-stuff"]);
+        assert_eq!(rows[1], Err("Forum: the protected field PIN, notes are not encrypted".to_string()));
+
+        let mail = rows[2].as_ref().unwrap();
+        assert_eq!((mail.name.as_str(), mail.password.as_str()), ("Internet/Mail", "third"));
+        let history: Vec<_> = mail.history.iter().map(|previous| previous.password.as_str()).collect();
+        assert_eq!(history, vec!["first", "second"]);
+        assert_eq!(mail.history[0].set_at, DateTime::from_timestamp(1_577_836_800, 0));
+        assert_eq!(mail.updated_at, DateTime::from_timestamp(1_640_995_200, 0));
+
+        assert_eq!(rows[3], Err("shop.example.com: no password".to_string()));
+    }
+
+    #[test]
+    fn test_read_kdbx3() {
+        let data = write_kdbx3("secret");
+        check_entries(read_kdbx(&data, "secret", None).unwrap());
+        assert_eq!(read_kdbx(&data, "wrong", None).err().as_deref(), Some(WRONG_KEY));
+    }
+
+    #[test]
+    fn test_read_kdbx4() {
+        let key_file = "ab".repeat(32);
+        let data = write_kdbx4("secret", Some(key_file.as_bytes()));
+        check_entries(read_kdbx(&data, "secret", Some(key_file.as_bytes())).unwrap());
+        assert_eq!(read_kdbx(&data, "secret", None).err().as_deref(), Some(WRONG_KEY));
+        assert!(read_kdbx(b"not a database", "secret", None).is_err());
+    }
+
+    #[test]
+    fn test_key_file() {
+        let key = [7u8; 32];
+        let v1 = format!("<?xml version=\"1.0\"?><KeyFile><Meta><Version>1.00</Version></Meta><Key><Data>{}</Data></Key></KeyFile>", BASE64.encode(key));
+        let v2 = format!(
+            "<KeyFile><Meta><Version>2.0</Version></Meta><Key><Data Hash=\"00000000\">\n{} {}\n</Data></Key></KeyFile>",
+            hex::encode(&key[..16]).to_uppercase(),
+            hex::encode(&key[16..])
+        );
+        assert_eq!(key_file_key(v1.as_bytes()), key);
+        assert_eq!(key_file_key(v2.as_bytes()), key);
+        assert_eq!(key_file_key(&key), key);
+        assert_eq!(key_file_key(b"any file"), <[u8; 32]>::from(Sha256::digest(b"any file")));
+    }
+}
@@ -0,0 +1,320 @@
+//! Entries brought in from other password managers, see `kofl import`.
+
+pub mod csv;
+pub mod kdbx;
+
+use crate::context::Context;
+use crate::crypto::{encrypt_secret, secret_mac};
+use crate::db::Db::Entry;
+use crate::organize::{parse_tag, validate_entry_name, FOLDER_SEPARATOR};
+use crate::policy::load_policy;
+use chrono::{DateTime, Utc};
+use std::fmt;
+use std::str::FromStr;
+
+pub use self::csv::CsvFormat;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    Csv(CsvFormat),
+    /// KeePass database, KDBX 3.1 or 4.
+    Kdbx,
+    /// Written by `kofl export`, encrypted or not.
+    Json,
+    /// Directory in the password-store layout, see `export::pass`.
+    Pass,
+}
+
+impl FromStr for ImportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "kdbx" => Ok(ImportFormat::Kdbx),
+            "json" => Ok(ImportFormat::Json),
+            "pass" => Ok(ImportFormat::Pass),
+            format => match format.strip_suffix("-csv") {
+                Some(name) => name.parse().map(ImportFormat::Csv),
+                None => Err(format!(
+                    "Unknown format `{}`, use json, pass, kdbx, bitwarden-csv, lastpass-csv, 1password-csv, chrome-csv, keepass-csv or generic-csv",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+/// What to do with an entry whose name is already in the vault.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Collision {
+    Skip,
+    Overwrite,
+    /// Import under the first free name among `name (2)`, `name (3)`...
+    Rename,
+}
+
+impl FromStr for Collision {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "skip" => Ok(Collision::Skip),
+            "overwrite" => Ok(Collision::Overwrite),
+            "rename" => Ok(Collision::Rename),
+            other => Err(format!("Unknown collision handling `{}`, use skip, overwrite or rename", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ImportedEntry {
+    pub name: String,
+    pub password: String,
+    pub username: Option<String>,
+    pub url: Option<String>,
+    pub notes: Option<String>,
+    pub tags: Vec<String>,
+    /// The oldest known time of the entry when unknown.
+    pub created_at: Option<DateTime<Utc>>,
+    /// When the secret was last changed, the time of the import when unknown.
+    pub updated_at: Option<DateTime<Utc>>,
+    pub policy: Option<String>,
+    pub max_age_days: Option<u32>,
+    /// Previous secrets, the oldest first.
+    pub history: Vec<PreviousSecret>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreviousSecret {
+    pub password: String,
+    pub set_at: Option<DateTime<Utc>>,
+    /// When the next secret was set when unknown.
+    pub replaced_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportSummary {
+    pub added: usize,
+    pub overwritten: usize,
+    /// Imported under another name, as (original, new) names.
+    pub renamed: Vec<(String, String)>,
+    pub skipped: Vec<String>,
+    /// Policies unknown to this vault, left out of the entries naming them.
+    pub cleared_policies: Vec<String>,
+}
+
+impl fmt::Display for ImportSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} added, {} overwritten, {} renamed, {} skipped",
+            self.added,
+            self.overwritten,
+            self.renamed.len(),
+            self.skipped.len()
+        )
+    }
+}
+
+/// Entry name from the folder path and title of another password manager,
+/// `/` in a title would create folders so it is replaced.
+pub fn entry_name(folder: Option<&str>, title: &str) -> Result<String, String> {
+    let title = title.trim().replace(FOLDER_SEPARATOR, "-");
+    let mut segments: Vec<String> = folder
+        .unwrap_or_default()
+        .split(['/', '\\'])
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty() && *segment != "." && *segment != "..")
+        .map(String::from)
+        .collect();
+    segments.push(title);
+
+    let name = segments.join(&FOLDER_SEPARATOR.to_string());
+    validate_entry_name(&name)?;
+    Ok(name)
+}
+
+/// Entry name for logins saved without a title, as Chrome does.
+pub(crate) fn host_of(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.split(['/', '?', '#']).next().unwrap_or_default().to_string()
+}
+
+/// Tags separated by `,` or `;`, spaces inside a tag become `-`.
+pub(crate) fn split_tags(tags: &str) -> Vec<String> {
+    tags.split([',', ';'])
+        .map(|tag| tag.split_whitespace().collect::<Vec<_>>().join("-"))
+        .filter_map(|tag| parse_tag(&tag).ok())
+        .collect()
+}
+
+/// Adds the entries in a single transaction, nothing is written when one fails.
+pub fn import_entries(context: &Context, entries: Vec<ImportedEntry>, collision: Collision) -> Result<ImportSummary, String> {
+    let master_key_hash = context.kgc.borrow().get_hashed_pwd();
+    let history_limit = context.settings.borrow_mut().get_history_limit(context);
+    let db_error = |e: rusqlite::Error| format!("Database error: {}", e);
+
+    let tx = context.db.transaction().map_err(db_error)?;
+    let mut summary = ImportSummary::default();
+    for mut imported in entries {
+        // another vault may have had policies this one does not know
+        if let Some(Err(e)) = imported.policy.as_deref().map(|policy| load_policy(Some(&context.db), policy)) {
+            summary.cleared_policies.push(format!("{}: {}, the default policy applies", imported.name, e));
+            imported.policy = None;
+        }
+        let encrypted = encrypt_secret(&master_key_hash, &imported.password).map_err(|e| e.to_string())?;
+        let mac = secret_mac(&master_key_hash, &imported.password).map_err(|e| e.to_string())?;
+        let updated_at = imported.updated_at.unwrap_or_else(Utc::now);
+
+        let current = match context.db.get_entry_by_name(&imported.name) {
+            Ok(current) => Some(current),
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(e) => return Err(db_error(e)),
+        };
+
+        let mut name = imported.name.clone();
+        match (current, collision) {
+            (None, _) => summary.added += 1,
+            (Some(_), Collision::Skip) => {
+                summary.skipped.push(imported.name);
+                continue;
+            }
+            (Some(current), Collision::Overwrite) => {
+                let entry = Entry {
+                    password_hash: encrypted,
+                    updated_at: Utc::now(),
+                    username: imported.username.or(current.username.clone()),
+                    url: imported.url.or(current.url.clone()),
+                    notes: imported.notes.or(current.notes.clone()),
+                    policy: imported.policy.or(current.policy.clone()),
+                    max_age_days: imported.max_age_days.or(current.max_age_days),
+                    secret_mac: Some(mac),
+                    ..current.clone()
+                };
+                context.db.update_entry_with_history(&current, entry, history_limit).map_err(db_error)?;
+                for tag in &imported.tags {
+                    context.db.add_tag(current.id, tag).map_err(db_error)?;
+                }
+                add_history(context, current.id, &imported.history, updated_at, history_limit)?;
+                summary.overwritten += 1;
+                continue;
+            }
+            (Some(_), Collision::Rename) => {
+                name = free_name(context, &imported.name).map_err(db_error)?;
+                summary.renamed.push((imported.name.clone(), name.clone()));
+            }
+        }
+
+        let mut entry = Entry::new(0, name, encrypted);
+        entry.created_at = imported.created_at.unwrap_or_else(|| {
+            imported.history.iter().find_map(|previous| previous.set_at).unwrap_or(updated_at).min(updated_at)
+        });
+        entry.updated_at = updated_at;
+        entry.username = imported.username;
+        entry.url = imported.url;
+        entry.notes = imported.notes;
+        entry.policy = imported.policy;
+        entry.max_age_days = imported.max_age_days;
+        entry.secret_mac = Some(mac);
+        let id = context.db.add_entry_with_tags(entry, &imported.tags).map_err(db_error)?;
+        add_history(context, id, &imported.history, updated_at, history_limit)?;
+    }
+
+    tx.commit().map_err(db_error)?;
+    Ok(summary)
+}
+
+// each previous secret was replaced when the next one was set
+fn add_history(context: &Context, id: u32, history: &[PreviousSecret], updated_at: DateTime<Utc>, keep: u32) -> Result<(), String> {
+    let master_key_hash = context.kgc.borrow().get_hashed_pwd();
+    for (index, previous) in history.iter().enumerate() {
+        let replaced_at = previous.replaced_at.unwrap_or_else(|| {
+            history[index + 1..].iter().find_map(|next| next.set_at).unwrap_or(updated_at)
+        });
+        let set_at = previous.set_at.unwrap_or(replaced_at);
+        let encrypted = encrypt_secret(&master_key_hash, &previous.password).map_err(|e| e.to_string())?;
+        context
+            .db
+            .add_history(id, &encrypted, set_at, replaced_at, keep)
+            .map_err(|e| format!("Database error: {}", e))?;
+    }
+    Ok(())
+}
+
+fn free_name(context: &Context, name: &str) -> Result<String, rusqlite::Error> {
+    let mut suffix = 2;
+    loop {
+        let candidate = format!("{} ({})", name, suffix);
+        if !context.db.entry_exist(candidate.clone())? {
+            return Ok(candidate);
+        }
+        suffix += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::decrypt_secret;
+    use serial_test::serial;
+    use crate::test_support::isolated_context;
+    use tempfile::TempDir;
+
+    fn imported(name: &str, password: &str) -> ImportedEntry {
+        ImportedEntry { name: name.to_string(), password: password.to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn test_entry_name() {
+        assert_eq!(entry_name(Some("Work/AWS"), "prod").unwrap(), "Work/AWS/prod");
+        assert_eq!(entry_name(Some("Root\\Mail"), " a/b ").unwrap(), "Root/Mail/a-b");
+        assert_eq!(entry_name(None, "github").unwrap(), "github");
+        assert_eq!(entry_name(Some(""), "github").unwrap(), "github");
+        assert!(entry_name(Some("work"), " ").is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_import_collisions() {
+        let temp_dir = TempDir::new().unwrap();
+        let (_env, context) = isolated_context(&temp_dir);
+        let master_key_hash = "ab".repeat(32);
+        context.kgc.borrow_mut().set_master_key_hash(master_key_hash.clone());
+        let secret = |name: &str| decrypt_secret(&master_key_hash, &context.db.get_entry_by_name(name).unwrap().password_hash).ok();
+
+        let summary = import_entries(&context, vec![imported("mail", "v1"), imported("bank", "v1")], Collision::Skip).unwrap();
+        assert_eq!(summary.added, 2);
+
+        let again = vec![imported("mail", "v2"), imported("shop", "v2")];
+        let summary = import_entries(&context, again.clone(), Collision::Skip).unwrap();
+        assert_eq!((summary.added, summary.skipped), (1, vec!["mail".to_string()]));
+        assert_eq!(secret("mail").as_deref(), Some("v1"));
+
+        import_entries(&context, again.clone(), Collision::Overwrite).unwrap();
+        assert_eq!(secret("mail").as_deref(), Some("v2"));
+        assert_eq!(context.db.list_history(context.db.get_entry_by_name("mail").unwrap().id).unwrap().len(), 1);
+
+        let summary = import_entries(&context, vec![imported("mail", "v3"), imported("mail", "v4")], Collision::Rename).unwrap();
+        assert_eq!(summary.renamed.iter().map(|(_, new)| new.as_str()).collect::<Vec<_>>(), vec!["mail (2)", "mail (3)"]);
+        assert_eq!(secret("mail (3)").as_deref(), Some("v4"));
+        assert_eq!(context.db.list_entries().unwrap().len(), 5);
+    }
+
+    #[test]
+    #[serial]
+    fn test_unknown_policy_is_cleared() {
+        let temp_dir = TempDir::new().unwrap();
+        let (_env, context) = isolated_context(&temp_dir);
+        context.kgc.borrow_mut().set_master_key_hash("ab".repeat(32));
+
+        let strict = ImportedEntry { policy: Some("strict".to_string()), ..imported("mail", "v1") };
+        let unknown = ImportedEntry { policy: Some("from-work".to_string()), ..imported("bank", "v1") };
+        let summary = import_entries(&context, vec![strict, unknown], Collision::Skip).unwrap();
+
+        assert_eq!(summary.added, 2);
+        assert_eq!(summary.cleared_policies.len(), 1);
+        assert!(summary.cleared_policies[0].starts_with("bank: No password policy named from-work"));
+        assert_eq!(context.db.get_entry_by_name("mail").unwrap().policy.as_deref(), Some("strict"));
+        assert_eq!(context.db.get_entry_by_name("bank").unwrap().policy, None);
+    }
+}
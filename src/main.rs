@@ -10,25 +10,46 @@ mod session;
 mod utils;
 mod validator;
 mod settings;
-mod constants;
+mod vault;
+mod crypto;
+mod expiry;
+mod organize;
+mod search;
+mod generator;
+mod policy;
+mod strength;
+mod breach;
+mod audit;
+mod import;
+mod export;
+#[cfg(test)]
+mod test_support;
 
 
 // Updated imports for the commands
 use clap::{Parser, Subcommand};
 // Import commands from the new location
-use cli::commands::{AddCmd, DestroyCmd, GetCmd, InitCmd, LogInCmd, UpdateCmd, SettingsCmd}; // Updated path
-use cli::Command; // Import the Command trait from cli module
+use cli::commands::{AddCmd, DestroyCmd, GetCmd, InitCmd, LogInCmd, UpdateCmd, SettingsCmd, RestoreCmd, DoctorCmd, VaultCmd, HistoryCmd, RollbackCmd, ListCmd, AuditCmd, TagCmd, SearchCmd, GenerateCmd, GeneratorOverrides, ImportCmd, ExportCmd}; // Updated path
+use cli::{Command, StandaloneCommand}; // Import the Command traits from cli module
+use cli::output::{render, render_error, CommandError, OutputFormat};
+use cli::secret::{Secret, SecretSource};
 use colored::*;
+use generator::passphrase::{Capitalization, PassphraseOptions};
 use context::Context;
 use env_logger::{Env, Target};
 use log::{debug, error, info, warn};
 use std::f32::consts::E;
 use std::io::Write;
+use std::path::PathBuf;
 use std::process;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
+    #[arg(long, global = true, help = "Vault name or directory to use, overrides KOFL_HOME")]
+    vault: Option<String>,
+    #[arg(long, global = true, default_value = "text", help = "text, or json for a single JSON document on stdout with logs on stderr")]
+    output: OutputFormat,
     #[command(subcommand)]
     command: Commands,
 }
@@ -36,32 +57,254 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     #[command(about = "Initialize the password manager by providing a Master Key")]
-    Init {},
+    Init {
+        #[arg(long, conflicts_with = "password_file", help = "Read the master password from the first line of stdin")]
+        password_stdin: bool,
+        #[arg(long, help = "Read the master password from the first line of a file not readable by every user")]
+        password_file: Option<PathBuf>,
+    },
     #[command(about = "Login to the password manager using the Master Key")]    
-    Login {},
+    Login {
+        #[arg(long, conflicts_with = "password_file", help = "Read the master password from the first line of stdin")]
+        password_stdin: bool,
+        #[arg(long, help = "Read the master password from the first line of a file not readable by every user")]
+        password_file: Option<PathBuf>,
+    },
     #[command(about = "Destroy the password manager data (configuration + database)")]
-    Destroy {},
+    Destroy {
+        #[arg(long, conflicts_with = "password_file", help = "Read the master password from the first line of stdin")]
+        password_stdin: bool,
+        #[arg(long, help = "Read the master password from the first line of a file not readable by every user")]
+        password_file: Option<PathBuf>,
+    },
     #[command(about = "Add a new entry use --suggest flag to suggest a password")]
     Add {
+        #[arg(help = "Entry name, use / for folders as in work/aws/prod")]
         name: String,
         #[arg(short, long,)]
-        suggest: bool
+        suggest: bool,
+        #[arg(long, requires = "suggest", help = "Generator profile used with --suggest, see `kofl generate --list-profiles`")]
+        profile: Option<String>,
+        #[arg(long, requires = "suggest", conflicts_with = "profile", help = "Suggest a passphrase of random words instead")]
+        passphrase: bool,
+        #[arg(long, requires = "suggest", help = "Print the suggested password on stderr and save it without asking, required without a terminal")]
+        print: bool,
+        #[arg(long, conflicts_with_all = ["suggest", "password_file"], help = "Read the password from the first line of stdin")]
+        password_stdin: bool,
+        #[arg(long, conflicts_with = "suggest", help = "Read the password from the first line of a file not readable by every user")]
+        password_file: Option<PathBuf>,
+        #[arg(long, help = "Password policy the entry is checked against, see `kofl settings list`")]
+        policy: Option<String>,
+        #[arg(long, value_parser = clap::value_parser!(u32).range(0..=settings::MAX_AGE_LIMIT_DAYS as i64), help = "Days before the password should be rotated, 0 never expires")]
+        max_age: Option<u32>,
+        #[arg(long, help = "Username or email used with this password")]
+        username: Option<String>,
+        #[arg(long, help = "Website or service the password is for")]
+        url: Option<String>,
+        #[arg(long, help = "Free-form notes")]
+        notes: Option<String>,
+        #[arg(long = "tag", value_parser = organize::parse_tag, help = "Tag the entry, can be repeated")]
+        tags: Vec<String>,
     },
     #[command(about = "Get the password of on entry by name")]
-    Get { ent_name: String },
+    Get {
+        ent_name: String,
+        #[arg(long, help = "Get a previous version listed by `kofl history`")]
+        version: Option<u32>,
+        #[arg(long, conflicts_with = "version", help = "Show when the entry was created, updated and last accessed")]
+        info: bool,
+    },
+    #[command(about = "List the entries with their timestamps")]
+    List {
+        #[arg(long, help = "Only list the entries due for rotation")]
+        stale: bool,
+        #[arg(long, value_parser = organize::parse_tag, help = "Only list the entries with this tag")]
+        tag: Option<String>,
+        #[arg(long, value_parser = organize::parse_folder, help = "Only list the entries in this folder and its sub folders")]
+        folder: Option<String>,
+    },
+    #[command(about = "Search entries by name, username, URL and tags")]
+    Search { query: String },
+    #[command(about = "Add or remove tags on an entry")]
+    Tag {
+        #[command(subcommand)]
+        action: TagCommands,
+    },
+    #[command(about = "Report reused, weak, breached and old passwords and incomplete entries")]
+    Audit {
+        #[arg(long, help = "Print the report as JSON, same as --output json")]
+        json: bool,
+        #[arg(long, default_value_t = 0, help = "Exit with an error when more issues than this are found")]
+        max_issues: usize,
+    },
+    #[command(about = "Import the entries exported by another password manager")]
+    Import {
+        #[arg(long, help = "json or pass (from `kofl export`), kdbx, bitwarden-csv, lastpass-csv, 1password-csv, chrome-csv, keepass-csv or generic-csv")]
+        format: import::ImportFormat,
+        #[arg(long, default_value = "skip", help = "When an entry already exists: skip, overwrite or rename")]
+        on_conflict: import::Collision,
+        #[arg(long, help = "Key file of the KeePass database")]
+        key_file: Option<PathBuf>,
+        #[arg(long, conflicts_with = "passphrase_file", help = "Read the passphrase, or the KeePass password, from the first line of stdin")]
+        passphrase_stdin: bool,
+        #[arg(long, help = "Read the passphrase, or the KeePass password, from the first line of a file not readable by every user")]
+        passphrase_file: Option<PathBuf>,
+        file: PathBuf,
+    },
+    #[command(about = "Export the vault, encrypted with a passphrase")]
+    Export {
+        #[arg(long, default_value = "json", help = "json (a file) or pass (a directory in the password-store layout)")]
+        format: export::ExportFormat,
+        #[arg(help = "File, or directory for the pass format")]
+        file: PathBuf,
+        #[arg(long, requires = "i_understand", help = "Write the passwords in clear, requires --i-understand")]
+        unencrypted: bool,
+        #[arg(long, requires = "unencrypted", help = "Confirm that anyone who can read the file can read every password")]
+        i_understand: bool,
+        #[arg(long, conflicts_with_all = ["passphrase_file", "unencrypted"], help = "Read the passphrase from the first line of stdin")]
+        passphrase_stdin: bool,
+        #[arg(long, conflicts_with = "unencrypted", help = "Read the passphrase from the first line of a file not readable by every user")]
+        passphrase_file: Option<PathBuf>,
+    },
+    #[command(about = "List the previous versions of an entry password")]
+    History { ent_name: String },
+    #[command(about = "Restore a previous version of an entry password")]
+    Rollback {
+        ent_name: String,
+        #[arg(help = "Version listed by `kofl history`")]
+        version: u32,
+    },
     #[command(about = "Update the password of an existing entry")]
     Update {
         ent_name: String,
         #[arg(short, long)]
-        suggest: bool
+        suggest: bool,
+        #[arg(long, requires = "suggest", help = "Generator profile used with --suggest, see `kofl generate --list-profiles`")]
+        profile: Option<String>,
+        #[arg(long, requires = "suggest", conflicts_with = "profile", help = "Suggest a passphrase of random words instead")]
+        passphrase: bool,
+        #[arg(long, requires = "suggest", help = "Print the suggested password on stderr and save it without asking, required without a terminal")]
+        print: bool,
+        #[arg(long, conflicts_with_all = ["suggest", "password_file"], help = "Read the password from the first line of stdin")]
+        password_stdin: bool,
+        #[arg(long, conflicts_with = "suggest", help = "Read the password from the first line of a file not readable by every user")]
+        password_file: Option<PathBuf>,
+        #[arg(long, help = "Password policy the entry is checked against, see `kofl settings list`")]
+        policy: Option<String>,
+        #[arg(long, value_parser = clap::value_parser!(u32).range(0..=settings::MAX_AGE_LIMIT_DAYS as i64), help = "Days before the password should be rotated, 0 never expires")]
+        max_age: Option<u32>,
+        #[arg(long, help = "Username or email used with this password")]
+        username: Option<String>,
+        #[arg(long, help = "Website or service the password is for")]
+        url: Option<String>,
+        #[arg(long, help = "Free-form notes")]
+        notes: Option<String>,
     },
     #[command(about = "Manage application settings")]
     Settings {
         #[command(subcommand)]
         action: Option<SettingsCommands>,
+    },
+    #[command(about = "Restore the config from a backup after a failed integrity check")]
+    Restore {
+        #[arg(long, help = "Backup directory to restore, defaults to the last one")]
+        backup: Option<PathBuf>,
+        #[arg(long, help = "Also restore the database from the backup")]
+        with_data: bool,
+        #[arg(long, conflicts_with_all = ["backup", "with_data"], help = "Trust the current config again after entering the master password")]
+        reseal: bool,
+        #[arg(long, requires = "reseal", conflicts_with = "password_file", help = "Read the master password from the first line of stdin")]
+        password_stdin: bool,
+        #[arg(long, requires = "reseal", help = "Read the master password from the first line of a file not readable by every user")]
+        password_file: Option<PathBuf>,
+    },
+    #[command(about = "Check the vault for problems, use --fix to repair what can be repaired")]
+    Doctor {
+        #[arg(long, help = "Apply the available fixes")]
+        fix: bool,
+        #[arg(long, conflicts_with = "password_file", help = "Read the master password from the first line of stdin")]
+        password_stdin: bool,
+        #[arg(long, help = "Read the master password from the first line of a file not readable by every user")]
+        password_file: Option<PathBuf>,
+    },
+    #[command(about = "Generate a password, works without a vault")]
+    Generate {
+        #[arg(long, help = "Generator profile, defaults to the default_generator_profile setting")]
+        profile: Option<String>,
+        #[arg(short, long)]
+        length: Option<usize>,
+        #[arg(long, value_delimiter = ',', help = "Character classes to use: lower,upper,digits,symbols")]
+        charset: Option<Vec<generator::CharClass>>,
+        #[arg(long, help = "Symbols allowed when the charset includes symbols")]
+        symbols: Option<String>,
+        #[arg(long, help = "Leave out characters such as l, 1, O and 0")]
+        exclude_ambiguous: bool,
+        #[arg(long, value_delimiter = ',', help = "Classes that must appear, all classes of the charset by default")]
+        require: Option<Vec<generator::CharClass>>,
+        #[arg(short, long, default_value_t = 1, help = "Number of passwords to generate")]
+        count: usize,
+        #[arg(long, value_name = "NAME", help = "Save the resulting options as a profile of the vault")]
+        save_profile: Option<String>,
+        #[arg(long, conflicts_with_all = ["profile", "length", "charset", "symbols", "exclude_ambiguous", "require", "save_profile"], help = "Generate a passphrase of random words")]
+        passphrase: bool,
+        #[arg(long, requires = "passphrase", help = "Number of words, by default as many as six words of the EFF large list: 6 from that list, 7 from the embedded one")]
+        words: Option<usize>,
+        #[arg(long, requires = "passphrase", help = "Text between the words, - by default")]
+        separator: Option<String>,
+        #[arg(long, requires = "passphrase", help = "Capitalize the words: none, first or random")]
+        capitalize: Option<Capitalization>,
+        #[arg(long, requires = "passphrase", help = "Append a digit to one of the words")]
+        digit: bool,
+        #[arg(long, requires = "passphrase", help = "Append a symbol to one of the words")]
+        symbol: bool,
+        #[arg(long, requires = "passphrase", help = "Word list file, one word per line (EFF dice lists work as is)")]
+        wordlist: Option<PathBuf>,
+        #[arg(long, exclusive = true, help = "List the available profiles")]
+        list_profiles: bool,
+    },
+    #[command(about = "Manage named vaults")]
+    Vault {
+        #[command(subcommand)]
+        action: VaultCommands,
     }
 }
 #[derive(Subcommand)]
+enum TagCommands {
+    #[command(about = "Add tags to an entry")]
+    Add {
+        ent_name: String,
+        #[arg(required = true, value_parser = organize::parse_tag)]
+        tags: Vec<String>,
+    },
+
+    #[command(about = "Remove tags from an entry")]
+    Remove {
+        ent_name: String,
+        #[arg(required = true, value_parser = organize::parse_tag)]
+        tags: Vec<String>,
+    },
+}
+#[derive(Subcommand)]
+enum VaultCommands {
+    #[command(about = "List the known vaults")]
+    List {},
+
+    #[command(about = "Create a new named vault")]
+    Create {
+        #[arg(help = "Name of the vault")]
+        name: String,
+
+        #[arg(long, help = "Keep the vault in this directory instead of the XDG directories")]
+        path: Option<PathBuf>,
+    },
+
+    #[command(about = "Use a vault by default")]
+    Use {
+        #[arg(help = "Name of the vault")]
+        name: String,
+    },
+}
+#[derive(Subcommand)]
 enum SettingsCommands {
     #[command(about = "List all available settings")]
     List {},
@@ -82,7 +325,7 @@ enum SettingsCommands {
     },
 }
 
-fn init_logger() {
+fn init_logger(target: Target) {
     #[cfg(feature = "prod")]
     {
         env_logger::Builder::from_env(Env::default().default_filter_or("info"))
@@ -94,7 +337,7 @@ fn init_logger() {
                     record.args()
                 )
             })
-            .target(Target::Stdout)
+            .target(target)
             .init();
     }
 
@@ -119,93 +362,258 @@ fn init_logger() {
                     record.args()
                 )
             })
-            .target(Target::Stdout)
+            .target(target)
             .init();
     }
 }
 
-fn execute_command<T: Command>(cmd: &T, context: &Context) {
-    if cmd.validate(context) {
-        if cmd.execute(context) {
-            cmd.display();
-        } else {
-            debug!("Error during execution");
-        }
+fn execute_command<T: Command>(cmd: &T, context: &Context, output: OutputFormat) -> bool {
+    let result = cmd
+        .validate(context)
+        .map_err(CommandError::validation)
+        .and_then(|_| cmd.execute(context).map_err(CommandError::execution));
+    match &result {
+        Ok(_) => cmd.display(),
+        Err(e) => debug!("Error during {:?}", e.kind),
+    }
+    render(&result, output)
+}
+
+fn secret_source(suggest: bool, profile: &Option<String>, passphrase: bool, print: bool, policy: Option<String>) -> SecretSource {
+    if suggest {
+        SecretSource::Generate { profile: profile.clone(), passphrase, print, policy }
     } else {
-        debug!("Error during validation");
+        SecretSource::Prompt
     }
 }
 
+// the secret is known before validation, so validators only check it
+fn resolve_secret(source: &SecretSource, context: &Context) -> Result<Secret, CommandError> {
+    match source.resolve(context) {
+        Ok(Some(secret)) => Ok(secret),
+        Ok(None) => Err(CommandError::cancelled("Nothing was saved".to_string())),
+        Err(e) => Err(CommandError::validation(e)),
+    }
+}
+
+fn execute_standalone_command<T: StandaloneCommand>(cmd: &T, output: OutputFormat) -> bool {
+    let result = cmd
+        .validate()
+        .map_err(CommandError::validation)
+        .and_then(|_| cmd.execute().map_err(CommandError::execution));
+    match &result {
+        Ok(_) => cmd.display(),
+        Err(e) => debug!("Error during {:?}", e.kind),
+    }
+    render(&result, output)
+}
+
 fn main() {
-    // Initialize the logger
-    init_logger();
+    let mut cli = Cli::parse();
+    if let Commands::Audit { json: true, .. } = cli.command {
+        cli.output = OutputFormat::Json;
+    }
+    let output = cli.output;
+
+    // stdout only carries the JSON document
+    init_logger(match output {
+        OutputFormat::Text => Target::Stdout,
+        OutputFormat::Json => Target::Stderr,
+    });
 
-    let context = Context::new().unwrap_or_else(|err| {
-        error!("Program terminated due to setup issues: {}", err);
+    if let Some(vault) = &cli.vault {
+        if let Err(e) = vault::select_vault(vault) {
+            render_error(&CommandError::validation(e), output);
+            process::exit(1);
+        }
+    }
+
+    if let Commands::Generate { profile, length, charset, symbols, exclude_ambiguous, require, count, save_profile, passphrase, words, separator, capitalize, digit, symbol, wordlist, list_profiles } = &cli.command {
+        let cmd = if *list_profiles {
+            GenerateCmd::new_list_profiles()
+        } else if *passphrase {
+            let defaults = PassphraseOptions::default();
+            let options = PassphraseOptions {
+                words: words.or(defaults.words),
+                separator: separator.clone().unwrap_or(defaults.separator),
+                capitalize: capitalize.unwrap_or(defaults.capitalize),
+                digit: *digit,
+                symbol: *symbol,
+            };
+            GenerateCmd::new_passphrase(options, wordlist.clone(), *count)
+        } else {
+            let overrides = GeneratorOverrides {
+                length: *length,
+                charset: charset.clone(),
+                symbols: symbols.clone(),
+                exclude_ambiguous: *exclude_ambiguous,
+                required: require.clone(),
+            };
+            GenerateCmd::new(profile.clone(), overrides, *count, save_profile.clone())
+        };
+        if !execute_standalone_command(&cmd, output) {
+            process::exit(1);
+        }
+        return;
+    }
+
+    // these do not open a vault
+    if let Commands::Vault { action } = &cli.command {
+        let cmd = match action {
+            VaultCommands::List {} => VaultCmd::new_list(),
+            VaultCommands::Create { name, path } => VaultCmd::new_create(name.clone(), path.clone()),
+            VaultCommands::Use { name } => VaultCmd::new_use(name.clone()),
+        };
+        if !execute_standalone_command(&cmd, output) {
+            process::exit(1);
+        }
+        return;
+    }
+
+    // recovery commands must be able to open a vault failing the integrity check
+    let context = match &cli.command {
+        Commands::Restore { .. } | Commands::Doctor { .. } => Context::new_unchecked(),
+        _ => Context::new(),
+    }
+    .unwrap_or_else(|err| {
+        render_error(&CommandError::execution(format!("Program terminated due to setup issues: {}", err)), output);
         process::exit(1);
     });
 
     debug!("{:?}", context.kgc);
     debug!("{:?}", context.ss);
 
-    let cli = Cli::parse();
-
-    match &cli.command {
-        Commands::Init {} => {
-            let init_command = InitCmd::new();
-            execute_command(&init_command, &context);
+    let succeeded = match &cli.command {
+        Commands::Init { password_stdin, password_file } => {
+            let init_command = InitCmd::new().with_master_password(SecretSource::master_password(*password_stdin, password_file));
+            execute_command(&init_command, &context, output)
         }
-        Commands::Add { name, suggest   } => {
+        Commands::Add { name, suggest, profile, passphrase, print, password_stdin, password_file, policy, max_age, username, url, notes, tags } => {
             //info!("add commend with name {} and suggest flag is set to {}", name , suggest);
-            if (*suggest) {
-                let add_command = AddCmd::new(name.to_string(), String::from(""), *suggest);
-                execute_command(&add_command, &context);
-            }else {
-                let pwd = rpassword::prompt_password("Enter the password for the entry ===> ").unwrap();
-                let add_command = AddCmd::new(name.to_string(), pwd, *suggest);
-                execute_command(&add_command, &context);
+            let source = SecretSource::from_flags(*password_stdin, password_file)
+                .unwrap_or_else(|| secret_source(*suggest, profile, *passphrase, *print, policy.clone()));
+            match resolve_secret(&source, &context) {
+                Ok(secret) => {
+                    let add_command = AddCmd::new(name.to_string(), secret.value, secret.generated)
+                        .with_policy(policy.clone())
+                        .with_max_age(*max_age)
+                        .with_metadata(username.clone(), url.clone(), notes.clone())
+                        .with_tags(tags.clone());
+                    execute_command(&add_command, &context, output)
+                }
+                Err(e) => {
+                    render_error(&e, output);
+                    false
+                }
             }
         }
-        Commands::Update { ent_name, suggest} => {
-
-            if (*suggest) {
-                let update_command = UpdateCmd::new(ent_name.to_string(), String::from(""), *suggest);
-                execute_command(&update_command, &context);
-            }else {
-                let pwd = rpassword::prompt_password("Enter the password for the entry ===> ").unwrap();
-                let update_command = UpdateCmd::new(ent_name.to_string(), pwd, *suggest);
-                execute_command(&update_command, &context);
+        Commands::Update { ent_name, suggest, profile, passphrase, print, password_stdin, password_file, policy, max_age, username, url, notes } => {
+            // edits of a suggestion are checked against the policy the entry keeps
+            let entry_policy = policy.clone().or_else(|| context.db.get_entry_by_name(ent_name).ok().and_then(|entry| entry.policy));
+            let source = SecretSource::from_flags(*password_stdin, password_file)
+                .unwrap_or_else(|| secret_source(*suggest, profile, *passphrase, *print, entry_policy));
+            match resolve_secret(&source, &context) {
+                Ok(secret) => {
+                    let update_command = UpdateCmd::new(ent_name.to_string(), secret.value, secret.generated)
+                        .with_policy(policy.clone())
+                        .with_max_age(*max_age)
+                        .with_metadata(username.clone(), url.clone(), notes.clone());
+                    execute_command(&update_command, &context, output)
+                }
+                Err(e) => {
+                    render_error(&e, output);
+                    false
+                }
             }
         }
-        Commands::Get { ent_name } => {
-            let get_command = GetCmd::new(ent_name.to_string());
-            execute_command(&get_command, &context);
+        Commands::Get { ent_name, version, info } => {
+            let get_command = GetCmd::new(ent_name.to_string(), *version, *info);
+            execute_command(&get_command, &context, output)
+        }
+        Commands::List { stale, tag, folder } => {
+            let list_command = ListCmd::new(*stale, tag.clone(), folder.clone());
+            execute_command(&list_command, &context, output)
+        }
+        Commands::Search { query } => {
+            let search_command = SearchCmd::new(query.clone());
+            execute_command(&search_command, &context, output)
+        }
+        Commands::Tag { action } => {
+            let tag_command = match action {
+                TagCommands::Add { ent_name, tags } => TagCmd::new_add(ent_name.clone(), tags.clone()),
+                TagCommands::Remove { ent_name, tags } => TagCmd::new_remove(ent_name.clone(), tags.clone()),
+            };
+            execute_command(&tag_command, &context, output)
+        }
+        Commands::Import { format, on_conflict, key_file, passphrase_stdin, passphrase_file, file } => {
+            let import_command = ImportCmd::new(file.clone(), *format, *on_conflict)
+                .with_key_file(key_file.clone())
+                .with_passphrase(SecretSource::from_flags(*passphrase_stdin, passphrase_file).unwrap_or(SecretSource::Prompt));
+            execute_command(&import_command, &context, output)
+        }
+        Commands::Export { format, file, unencrypted, passphrase_stdin, passphrase_file, .. } => {
+            let export_command = ExportCmd::new(file.clone(), *format, *unencrypted)
+                .with_passphrase(SecretSource::from_flags(*passphrase_stdin, passphrase_file).unwrap_or(SecretSource::Prompt));
+            execute_command(&export_command, &context, output)
         }
-        Commands::Login {} => {
-            let login_command = LogInCmd::new();
-            execute_command(&login_command, &context);
+        Commands::Audit { max_issues, .. } => {
+            let audit_command = AuditCmd::new(*max_issues);
+            execute_command(&audit_command, &context, output)
+        }
+        Commands::History { ent_name } => {
+            let history_command = HistoryCmd::new(ent_name.to_string());
+            execute_command(&history_command, &context, output)
+        }
+        Commands::Rollback { ent_name, version } => {
+            let rollback_command = RollbackCmd::new(ent_name.to_string(), *version);
+            execute_command(&rollback_command, &context, output)
+        }
+        Commands::Login { password_stdin, password_file } => {
+            let login_command = LogInCmd::new().with_master_password(SecretSource::master_password(*password_stdin, password_file));
+            execute_command(&login_command, &context, output)
         },
         Commands::Settings { action } => {
             match action {
                 Some(SettingsCommands::List {}) => {
                     let cmd = SettingsCmd::new_list();
-                    execute_command(&cmd, &context);
+                    execute_command(&cmd, &context, output)
                 },
                 Some(SettingsCommands::Get { name }) => {
                     let cmd = SettingsCmd::new_get(name.clone());
-                    execute_command(&cmd, &context);
+                    execute_command(&cmd, &context, output)
                 },
                 Some(SettingsCommands::Set { name, value }) => {
                     let cmd = SettingsCmd::new_set(name.clone(), value.clone());
-                    execute_command(&cmd, &context);
+                    execute_command(&cmd, &context, output)
                 },
                 None => {
+                    true
                 }
             }
         },
-        Commands::Destroy {  } => {
-            let destroy_command = DestroyCmd::new();
-            execute_command(&destroy_command, &context);
+        Commands::Destroy { password_stdin, password_file } => {
+            let destroy_command = DestroyCmd::new().with_master_password(SecretSource::master_password(*password_stdin, password_file));
+            execute_command(&destroy_command, &context, output)
+        }
+        Commands::Restore { backup, with_data, reseal, password_stdin, password_file } => {
+            let restore_command = RestoreCmd::new(backup.clone(), *with_data, *reseal)
+                .with_master_password(SecretSource::master_password(*password_stdin, password_file));
+            execute_command(&restore_command, &context, output)
+        }
+        Commands::Doctor { fix, password_stdin, password_file } => {
+            let doctor_command = DoctorCmd::new(*fix).with_master_password(SecretSource::master_password(*password_stdin, password_file));
+            execute_command(&doctor_command, &context, output)
         }
+        Commands::Vault { .. } | Commands::Generate { .. } => unreachable!("handled before opening the vault"),
+    };
+
+    // these already report the stale entries or leave no vault behind
+    match &cli.command {
+        Commands::List { stale: true, .. } | Commands::Audit { .. } | Commands::Destroy { .. } => (),
+        _ => expiry::warn_if_stale(&context),
+    }
+
+    if !succeeded {
+        process::exit(1);
     }
 }
@@ -0,0 +1,78 @@
+//! Folder-qualified entry names and tags.
+//!
+//! An entry named `work/aws/prod` is the entry `prod` in the folder `work/aws`,
+//! folders only exist through the entries they contain.
+
+pub const FOLDER_SEPARATOR: char = '/';
+
+pub fn validate_entry_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Entry name cannot be empty".to_string());
+    }
+    if name.starts_with(FOLDER_SEPARATOR) || name.ends_with(FOLDER_SEPARATOR) {
+        return Err(format!("Entry name `{}` cannot start or end with `{}`", name, FOLDER_SEPARATOR));
+    }
+    for segment in name.split(FOLDER_SEPARATOR) {
+        if segment.trim().is_empty() {
+            return Err(format!("Entry name `{}` has an empty folder", name));
+        }
+        if segment == "." || segment == ".." {
+            return Err(format!("Entry name `{}` cannot contain `{}` as a folder", name, segment));
+        }
+    }
+    Ok(())
+}
+
+/// Folder part of an entry name, `None` for entries at the top level.
+pub fn folder_of(name: &str) -> Option<&str> {
+    name.rsplit_once(FOLDER_SEPARATOR).map(|(folder, _)| folder)
+}
+
+/// Used as a clap value parser so bad tags are rejected before the vault is opened.
+pub fn parse_tag(tag: &str) -> Result<String, String> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Err("Tag cannot be empty".to_string());
+    }
+    if tag.chars().any(|c| c.is_whitespace() || c == ',') {
+        return Err(format!("Tag `{}` cannot contain spaces or commas", tag));
+    }
+    Ok(tag.to_string())
+}
+
+/// Used as a clap value parser for `--folder`, a trailing separator is accepted.
+pub fn parse_folder(folder: &str) -> Result<String, String> {
+    let folder = folder.trim_end_matches(FOLDER_SEPARATOR);
+    validate_entry_name(folder)?;
+    Ok(folder.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_entry_name() {
+        assert!(validate_entry_name("github").is_ok());
+        assert!(validate_entry_name("work/aws/prod").is_ok());
+        assert!(validate_entry_name("").is_err());
+        assert!(validate_entry_name("/work").is_err());
+        assert!(validate_entry_name("work/").is_err());
+        assert!(validate_entry_name("work//prod").is_err());
+        assert!(validate_entry_name("work/../prod").is_err());
+    }
+
+    #[test]
+    fn test_folder_of() {
+        assert_eq!(folder_of("work/aws/prod"), Some("work/aws"));
+        assert_eq!(folder_of("github"), None);
+    }
+
+    #[test]
+    fn test_parse_tag() {
+        assert_eq!(parse_tag(" cloud ").unwrap(), "cloud");
+        assert!(parse_tag("two words").is_err());
+        assert!(parse_tag("a,b").is_err());
+        assert!(parse_tag("").is_err());
+    }
+}
@@ -0,0 +1,371 @@
+//! Rules typed passwords are checked against.
+//!
+//! Like generator profiles, a policy is stored in the settings table under
+//! `password_policy.<name>` as a spec such as
+//! `min=12 max=16 require=lower,upper,digits symbols=!@#$ max-repeats=2 forbid=1234,qwerty min-score=3`.
+
+use crate::db::Db::Database;
+use crate::generator::profile::is_valid_profile_name;
+use crate::generator::passphrase::{Capitalization, PassphraseOptions};
+use crate::generator::{CharClass, GeneratorOptions};
+use crate::strength::Strength;
+
+pub const POLICY_KEY_PREFIX: &str = "password_policy.";
+
+/// Every printable ASCII character that is not a letter, a digit or a space.
+pub const ASCII_SYMBOLS: &str = "!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: Option<usize>,
+    pub required: Vec<CharClass>,
+    /// Symbols allowed in the password, other characters than letters, digits and spaces are rejected.
+    pub symbols: String,
+    /// Longest run of the same character, unlimited when `None`.
+    pub max_repeats: Option<usize>,
+    /// Sequences the password must not contain, compared case insensitively.
+    pub forbidden: Vec<String>,
+    /// Lowest strength score accepted, from 0 to 4.
+    pub min_score: Option<u8>,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        PasswordPolicy {
+            min_length: 8,
+            max_length: None,
+            required: CharClass::all(),
+            symbols: ASCII_SYMBOLS.to_string(),
+            max_repeats: Some(3),
+            forbidden: Vec::new(),
+            min_score: None,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    fn class_matches(&self, class: CharClass, c: char) -> bool {
+        match class {
+            CharClass::Lower => c.is_lowercase(),
+            CharClass::Upper => c.is_uppercase(),
+            CharClass::Digits => c.is_ascii_digit(),
+            CharClass::Symbols => self.symbols.contains(c),
+        }
+    }
+
+    fn longest_run(password: &str) -> (usize, String) {
+        let chars: Vec<char> = password.chars().collect();
+        let mut longest = (0, String::new());
+        let mut start = 0;
+        for end in 1..=chars.len() {
+            if end == chars.len() || chars[end] != chars[start] {
+                if end - start > longest.0 {
+                    longest = (end - start, chars[start..end].iter().collect());
+                }
+                start = end;
+            }
+        }
+        longest
+    }
+
+    /// Every rule the password breaks, empty when it complies.
+    pub fn violations(&self, password: &str) -> Vec<String> {
+        let mut violations = Vec::new();
+        let length = password.chars().count();
+
+        if length < self.min_length {
+            violations.push(format!("Minimum length is {} but the password is {} characters long", self.min_length, length));
+        }
+        if let Some(max_length) = self.max_length {
+            if length > max_length {
+                violations.push(format!("Maximum length is {} but the password is {} characters long", max_length, length));
+            }
+        }
+
+        for class in &self.required {
+            if !password.chars().any(|c| self.class_matches(*class, c)) {
+                let message = match class {
+                    CharClass::Lower => "At least one lowercase letter (a-z) is required".to_string(),
+                    CharClass::Upper => "At least one uppercase letter (A-Z) is required".to_string(),
+                    CharClass::Digits => "At least one digit (0-9) is required".to_string(),
+                    CharClass::Symbols => format!("At least one special character ({}) is required", self.symbols),
+                };
+                violations.push(message);
+            }
+        }
+
+        let mut rejected: Vec<char> = password
+            .chars()
+            .filter(|c| !c.is_alphanumeric() && *c != ' ' && !self.symbols.contains(*c))
+            .collect();
+        rejected.sort_unstable();
+        rejected.dedup();
+        if !rejected.is_empty() {
+            violations.push(format!("Characters not allowed by the policy: {}", rejected.into_iter().collect::<String>()));
+        }
+
+        if let Some(max_repeats) = self.max_repeats {
+            let (run, sequence) = Self::longest_run(password);
+            if run > max_repeats {
+                violations.push(format!("At most {} repeated characters in a row are allowed, found `{}`", max_repeats, sequence));
+            }
+        }
+
+        let lowered = password.to_lowercase();
+        for sequence in &self.forbidden {
+            if lowered.contains(&sequence.to_lowercase()) {
+                violations.push(format!("The password contains the forbidden sequence `{}`", sequence));
+            }
+        }
+
+        violations
+    }
+
+    pub fn strength_violation(&self, strength: &Strength) -> Option<String> {
+        match self.min_score {
+            Some(min_score) if strength.score < min_score => {
+                Some(format!("The password is too weak ({}), the policy needs a score of {}", strength, min_score))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn check(&self) -> Result<(), String> {
+        if self.min_length == 0 {
+            return Err("The minimum length must be at least 1".to_string());
+        }
+        if let Some(max_length) = self.max_length {
+            if max_length < self.min_length {
+                return Err(format!("The maximum length {} is below the minimum length {}", max_length, self.min_length));
+            }
+        }
+        if self.required.contains(&CharClass::Symbols) && self.symbols.is_empty() {
+            return Err("Symbols are required but none is allowed".to_string());
+        }
+        if self.max_repeats == Some(0) {
+            return Err("max-repeats must be at least 1".to_string());
+        }
+        if self.min_score.map_or(false, |score| score > 4) {
+            return Err("min-score must be between 0 and 4".to_string());
+        }
+        Ok(())
+    }
+
+    /// Narrows a generator profile to the lengths and characters the policy accepts.
+    pub fn fit_generator(&self, mut options: GeneratorOptions) -> GeneratorOptions {
+        if let Some(max_length) = self.max_length {
+            options.length = options.length.min(max_length);
+        }
+        options.length = options.length.max(self.min_length);
+
+        let allowed: String = options.symbols.chars().filter(|c| self.symbols.contains(*c)).collect();
+        options.symbols = if allowed.is_empty() { self.symbols.clone() } else { allowed };
+        if options.symbols.is_empty() {
+            options.charset.retain(|class| *class != CharClass::Symbols);
+            if let Some(required) = &mut options.required {
+                required.retain(|class| *class != CharClass::Symbols);
+            }
+        }
+
+        for class in &self.required {
+            if !options.charset.contains(class) {
+                options.charset.push(*class);
+            }
+            if let Some(required) = &mut options.required {
+                if !required.contains(class) {
+                    required.push(*class);
+                }
+            }
+        }
+        options
+    }
+
+    /// Adds what the policy requires to passphrase options, the length still depends on the words drawn.
+    pub fn fit_passphrase(&self, mut options: PassphraseOptions) -> PassphraseOptions {
+        if options.separator.chars().any(|c| !c.is_alphanumeric() && c != ' ' && !self.symbols.contains(c)) {
+            options.separator = " ".to_string();
+        }
+        if self.required.contains(&CharClass::Upper) && options.capitalize == Capitalization::None {
+            options.capitalize = Capitalization::First;
+        }
+        if self.required.contains(&CharClass::Digits) {
+            options.digit = true;
+        }
+        if self.required.contains(&CharClass::Symbols) && !options.separator.chars().any(|c| self.symbols.contains(c)) {
+            options.symbol = true;
+        }
+        options
+    }
+}
+
+pub fn builtin_policies() -> Vec<(&'static str, PasswordPolicy)> {
+    vec![
+        ("default", PasswordPolicy::default()),
+        (
+            "strict",
+            PasswordPolicy {
+                min_length: 14,
+                max_repeats: Some(2),
+                forbidden: ["1234", "abcd", "qwerty", "azerty", "password"].iter().map(|s| s.to_string()).collect(),
+                min_score: Some(3),
+                ..PasswordPolicy::default()
+            },
+        ),
+    ]
+}
+
+fn join(values: &[String]) -> String {
+    values.join(",")
+}
+
+fn parse_list(value: &str) -> Vec<String> {
+    value.split(',').filter(|part| !part.is_empty()).map(str::to_string).collect()
+}
+
+pub fn to_spec(policy: &PasswordPolicy) -> String {
+    let required: Vec<String> = policy.required.iter().map(|class| class.to_string()).collect();
+    let mut spec = format!("min={}", policy.min_length);
+    if let Some(max_length) = policy.max_length {
+        spec.push_str(&format!(" max={}", max_length));
+    }
+    spec.push_str(&format!(" require={} symbols={}", join(&required), policy.symbols));
+    match policy.max_repeats {
+        Some(max_repeats) => spec.push_str(&format!(" max-repeats={}", max_repeats)),
+        None => spec.push_str(" max-repeats=none"),
+    }
+    if !policy.forbidden.is_empty() {
+        spec.push_str(&format!(" forbid={}", join(&policy.forbidden)));
+    }
+    if let Some(min_score) = policy.min_score {
+        spec.push_str(&format!(" min-score={}", min_score));
+    }
+    spec
+}
+
+pub fn parse_spec(spec: &str) -> Result<PasswordPolicy, String> {
+    let mut policy = PasswordPolicy::default();
+    let number = |value: &str| value.parse::<usize>().map_err(|_| format!("Invalid number `{}`", value));
+
+    for part in spec.split_whitespace() {
+        match part.split_once('=') {
+            Some(("min", value)) => policy.min_length = number(value)?,
+            Some(("max", value)) => policy.max_length = Some(number(value)?),
+            Some(("require", value)) => {
+                policy.required = parse_list(value).iter().map(|class| class.parse()).collect::<Result<_, _>>()?
+            }
+            Some(("symbols", value)) => policy.symbols = value.to_string(),
+            Some(("max-repeats", "none")) => policy.max_repeats = None,
+            Some(("max-repeats", value)) => policy.max_repeats = Some(number(value)?),
+            Some(("forbid", value)) => policy.forbidden = parse_list(value),
+            Some(("min-score", value)) => policy.min_score = Some(number(value)?.min(u8::MAX as usize) as u8),
+            _ => return Err(format!("Unknown policy option `{}`", part)),
+        }
+    }
+
+    policy.check()?;
+    Ok(policy)
+}
+
+/// Looks the policy up in the vault first, then in the built-in ones.
+pub fn load_policy(db: Option<&Database>, name: &str) -> Result<PasswordPolicy, String> {
+    if let Some(db) = db {
+        match db.get_setting_value(&format!("{}{}", POLICY_KEY_PREFIX, name)) {
+            Ok(Some(spec)) => return parse_spec(&spec).map_err(|e| format!("Policy {} is invalid: {}", name, e)),
+            Ok(None) | Err(rusqlite::Error::QueryReturnedNoRows) => (),
+            Err(e) => return Err(format!("Database error: {}", e)),
+        }
+    }
+
+    builtin_policies()
+        .into_iter()
+        .find(|(builtin, _)| *builtin == name)
+        .map(|(_, policy)| policy)
+        .ok_or_else(|| format!("No password policy named {}", name))
+}
+
+pub fn save_policy(db: &Database, name: &str, spec: &str) -> Result<(), String> {
+    if !is_valid_profile_name(name) {
+        return Err("Policy names may only contain letters, digits, '-' and '_'".to_string());
+    }
+    let policy = parse_spec(spec)?;
+    db.set_setting_value(&format!("{}{}", POLICY_KEY_PREFIX, name), &to_spec(&policy))
+        .map_err(|e| format!("Database error: {}", e))
+}
+
+/// Built-in policies followed by the vault ones, a vault policy replaces the built-in of the same name.
+pub fn list_policies(db: Option<&Database>) -> Vec<(String, Result<PasswordPolicy, String>)> {
+    let mut policies: Vec<(String, Result<PasswordPolicy, String>)> = builtin_policies()
+        .into_iter()
+        .map(|(name, policy)| (name.to_string(), Ok(policy)))
+        .collect();
+
+    let stored = db.and_then(|db| db.list_settings().ok()).unwrap_or_default();
+    for setting in stored {
+        if let Some(name) = setting.key.strip_prefix(POLICY_KEY_PREFIX) {
+            let policy = parse_spec(&setting.value);
+            match policies.iter_mut().find(|(existing, _)| existing == name) {
+                Some(existing) => existing.1 = policy,
+                None => policies.push((name.to_string(), policy)),
+            }
+        }
+    }
+    policies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_default_policy() {
+        let policy = PasswordPolicy::default();
+        assert!(policy.violations("Str0ng!Passw").is_empty());
+        assert!(policy.violations("Str0ng-Passw").is_empty());
+        assert_eq!(policy.violations("Aaaaa1!xyz").len(), 1);
+        // every broken rule is reported, not only the first one
+        assert_eq!(policy.violations("aaaa").len(), 5);
+    }
+
+    #[test]
+    fn test_custom_policy() {
+        let policy = parse_spec("min=6 max=10 require=digits symbols=-_ max-repeats=2 forbid=1234,Bank").unwrap();
+        assert!(policy.violations("abc-567").is_empty());
+        assert!(policy.violations("abc-1234").iter().any(|v| v.contains("1234")));
+        assert!(policy.violations("mybank-99").iter().any(|v| v.contains("Bank")));
+        assert!(policy.violations("abc!567").iter().any(|v| v.contains("not allowed")));
+        assert!(policy.violations("abc-5677777").len() >= 2);
+
+        assert!(parse_spec("min=10 max=8").is_err());
+        assert!(parse_spec("min-score=5").is_err());
+        assert!(parse_spec("colour=blue").is_err());
+    }
+
+    #[test]
+    fn test_spec_round_trip() {
+        for (_, policy) in builtin_policies() {
+            assert_eq!(parse_spec(&to_spec(&policy)).unwrap(), policy);
+        }
+        let unlimited = PasswordPolicy { max_repeats: None, ..PasswordPolicy::default() };
+        assert_eq!(parse_spec(&to_spec(&unlimited)).unwrap(), unlimited);
+    }
+
+    #[test]
+    fn test_vault_policies() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::new(&temp_dir.path().join("kofl.sqlite")).unwrap();
+        db.initialize().unwrap();
+
+        assert!(load_policy(Some(&db), "bank").is_err());
+        save_policy(&db, "bank", "min=8 max=16 require=lower,upper,digits symbols=").unwrap();
+        let bank = load_policy(Some(&db), "bank").unwrap();
+        assert_eq!(bank.max_length, Some(16));
+        assert!(bank.violations("Abcdef12").is_empty());
+        assert!(!bank.violations("Abcdef12!").is_empty());
+
+        assert!(save_policy(&db, "my bank", "min=8").is_err());
+        let names: Vec<String> = list_policies(Some(&db)).into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["default", "strict", "bank"]);
+    }
+}
@@ -0,0 +1,226 @@
+//! Fuzzy lookup of entries by name, username, URL and tags.
+
+use crate::db::Db::{Database, Entry};
+use crate::organize::FOLDER_SEPARATOR;
+
+const MIN_SCORE: u32 = 20;
+
+pub struct Match {
+    pub entry: Entry,
+    pub tags: Vec<String>,
+    pub score: u32,
+}
+
+/// How a name given on the command line maps to an entry.
+pub enum Resolution {
+    Exact(Entry),
+    /// The name is the prefix of a single entry name (or of its last folder segment).
+    UniquePrefix(Entry),
+    NotFound { suggestions: Vec<String> },
+}
+
+fn tokens(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| c == FOLDER_SEPARATOR || c == '.' || c == '-' || c == '_' || c == '@' || c == ':' || c.is_whitespace())
+        .filter(|token| !token.is_empty())
+}
+
+// optimal string alignment distance, enough to catch typos and swapped letters
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut rows = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for j in 0..=b.len() {
+        rows[0][j] = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            rows[i][j] = (rows[i - 1][j] + 1).min(rows[i][j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                rows[i][j] = rows[i][j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+// number of skipped characters when the query is a subsequence of the text
+fn subsequence_gaps(query: &str, text: &str) -> Option<usize> {
+    let mut chars = text.chars();
+    let mut gaps = 0;
+    for q in query.chars() {
+        loop {
+            match chars.next() {
+                Some(c) if c == q => break,
+                Some(_) => gaps += 1,
+                None => return None,
+            }
+        }
+    }
+    Some(gaps)
+}
+
+/// Scores how well `query` matches `text`, from 100 for an exact match down to
+/// a few points for a typo, `None` when they are unrelated. Case insensitive.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let query = query.trim().to_lowercase();
+    let text = text.to_lowercase();
+    if query.is_empty() || text.is_empty() {
+        return None;
+    }
+
+    if text == query {
+        return Some(100);
+    }
+    if text.starts_with(&query) {
+        return Some(90);
+    }
+    if tokens(&text).any(|token| token == query) {
+        return Some(88);
+    }
+    if tokens(&text).any(|token| token.starts_with(&query)) {
+        return Some(85);
+    }
+    if let Some(position) = text.find(&query) {
+        return Some(80 - position.min(10) as u32);
+    }
+    if query.chars().count() >= 2 {
+        if let Some(gaps) = subsequence_gaps(&query, &text) {
+            return Some(60u32.saturating_sub(2 * gaps as u32).max(MIN_SCORE + 10));
+        }
+    }
+
+    let query_chars: Vec<char> = query.chars().collect();
+    let max_typos = if query_chars.len() <= 4 { 1 } else { 2 };
+    let best_distance = std::iter::once(text.as_str())
+        .chain(tokens(&text))
+        .map(|token| edit_distance(&query_chars, &token.chars().collect::<Vec<char>>()))
+        .min()?;
+    if best_distance <= max_typos {
+        return Some(50 - 10 * best_distance as u32);
+    }
+    None
+}
+
+/// Best score over the searchable fields, the name weighs more than the rest.
+pub fn score_entry(query: &str, entry: &Entry, tags: &[String]) -> Option<u32> {
+    let weighted = |text: Option<&str>, percent: u32| text.and_then(|t| fuzzy_score(query, t)).map(|s| s * percent / 100);
+
+    [
+        weighted(Some(&entry.ent_name), 100),
+        tags.iter().filter_map(|tag| weighted(Some(tag), 90)).max(),
+        weighted(entry.username.as_deref(), 80),
+        weighted(entry.url.as_deref(), 70),
+    ]
+    .into_iter()
+    .flatten()
+    .max()
+    .filter(|score| *score >= MIN_SCORE)
+}
+
+/// Entries matching the query, best match first.
+pub fn search(db: &Database, query: &str) -> Result<Vec<Match>, rusqlite::Error> {
+    let mut matches = Vec::new();
+    for entry in db.list_entries()? {
+        let tags = db.list_tags(entry.id)?;
+        if let Some(score) = score_entry(query, &entry, &tags) {
+            matches.push(Match { entry, tags, score });
+        }
+    }
+    matches.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.entry.ent_name.cmp(&b.entry.ent_name)));
+    Ok(matches)
+}
+
+fn is_prefix_of(name: &str, entry_name: &str) -> bool {
+    let name = name.to_lowercase();
+    let entry_name = entry_name.to_lowercase();
+    let leaf = entry_name.rsplit(FOLDER_SEPARATOR).next().unwrap_or(&entry_name);
+    entry_name.starts_with(&name) || leaf.starts_with(&name)
+}
+
+/// Looks an entry up by exact name, then by unique prefix, otherwise returns
+/// the closest names to suggest.
+pub fn resolve_entry(db: &Database, name: &str) -> Result<Resolution, rusqlite::Error> {
+    match db.get_entry_by_name(name) {
+        Ok(entry) => return Ok(Resolution::Exact(entry)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => (),
+        Err(e) => return Err(e),
+    }
+
+    let mut prefixed: Vec<Entry> = db
+        .list_entries()?
+        .into_iter()
+        .filter(|entry| is_prefix_of(name, &entry.ent_name))
+        .collect();
+    if prefixed.len() == 1 {
+        return Ok(Resolution::UniquePrefix(prefixed.remove(0)));
+    }
+
+    let suggestions = search(db, name)?.into_iter().take(5).map(|m| m.entry.ent_name).collect();
+    Ok(Resolution::NotFound { suggestions })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn open_test_db(temp_dir: &TempDir) -> Database {
+        let db = Database::new(&temp_dir.path().join("kofl.sqlite")).unwrap();
+        db.initialize().unwrap();
+        for name in ["github", "gitlab", "work/aws/prod", "work/aws/staging", "personal/mail"] {
+            let mut entry = Entry::new(0, name.to_string(), "secret".to_string());
+            if name == "personal/mail" {
+                entry.username = Some("jane@example.org".to_string());
+                entry.url = Some("https://mail.example.org".to_string());
+            }
+            db.add_entry(entry).unwrap();
+        }
+        db
+    }
+
+    #[test]
+    fn test_fuzzy_score_order() {
+        let exact = fuzzy_score("github", "github").unwrap();
+        let prefix = fuzzy_score("git", "github").unwrap();
+        let typo = fuzzy_score("githbu", "github").unwrap();
+        assert!(exact > prefix && prefix > typo);
+        assert!(fuzzy_score("GitHub", "github").is_some());
+        assert_eq!(fuzzy_score("zzz", "github"), None);
+    }
+
+    #[test]
+    fn test_search_metadata() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = open_test_db(&temp_dir);
+
+        let names: Vec<String> = search(&db, "jane").unwrap().into_iter().map(|m| m.entry.ent_name).collect();
+        assert_eq!(names, vec!["personal/mail"]);
+
+        let names: Vec<String> = search(&db, "gihtub").unwrap().into_iter().map(|m| m.entry.ent_name).collect();
+        assert_eq!(names.first().map(String::as_str), Some("github"));
+    }
+
+    #[test]
+    fn test_resolve_entry() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = open_test_db(&temp_dir);
+
+        assert!(matches!(resolve_entry(&db, "github").unwrap(), Resolution::Exact(_)));
+        match resolve_entry(&db, "work/aws/p").unwrap() {
+            Resolution::UniquePrefix(entry) => assert_eq!(entry.ent_name, "work/aws/prod"),
+            _ => panic!("expected a unique prefix"),
+        }
+        match resolve_entry(&db, "stag").unwrap() {
+            Resolution::UniquePrefix(entry) => assert_eq!(entry.ent_name, "work/aws/staging"),
+            _ => panic!("expected a unique prefix"),
+        }
+        match resolve_entry(&db, "git").unwrap() {
+            Resolution::NotFound { suggestions } => {
+                assert_eq!(suggestions[..2], ["github".to_string(), "gitlab".to_string()])
+            }
+            _ => panic!("git is ambiguous"),
+        }
+    }
+}
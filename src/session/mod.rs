@@ -1,6 +1,5 @@
-use std::env::home_dir;
 use std::fmt::Debug;
-use crate::utils::Utils::{check_existing_session_config, get_home_dir};
+use crate::vault::VaultPaths;
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::fs;
@@ -49,9 +48,8 @@ pub struct Session {
 impl Session {
     pub fn new(user_login: String, status: bool) -> Self {
         let now = Utc::now();
-        let home_dir = get_home_dir().expect("Home directory not found");
         Session {
-            session_path: home_dir.join(".kofl_session"),
+            session_path: VaultPaths::resolve().session_path,
             session_id: thread_rng()
                 .sample_iter(&Alphanumeric)
                 .take(32)
@@ -70,11 +68,17 @@ impl Session {
        &self.session_path
     }
 
+    pub fn get_expires_at(&self) -> DateTime<Utc> {
+        self.expires_at
+    }
+
     pub fn load(&mut self) -> Result<(), SessionError> {
-        if check_existing_session_config() {
+        if self.session_path.exists() {
             match self.read_config_from_toml_file() {
                 Ok(config) => {
+                    let session_path = self.session_path.clone();
                     *self = config; // mutating the self with Session  serialized
+                    self.session_path = session_path;
                     if self.check_if_expired() {
                         return Err(SessionError::ExpiredSession);
                     }
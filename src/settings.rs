@@ -1,13 +1,26 @@
 use crate::context::Context;
+use crate::generator::passphrase::load_wordlist;
+use crate::generator::profile::is_valid_profile_name;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use log::{debug, warn};
 
+/// Longest rotation period in days, for the setting and for `--max-age`.
+pub const MAX_AGE_LIMIT_DAYS: u32 = 3650;
+
 #[derive(Debug, Clone, Copy)]
 pub enum Setting {
     SessionDuration,
     ClipboardTimeout,
-    DefaultTimeout,
+    DefaultMaxAge,
     EncryptionIterations,
+    HistoryLimit,
+    DefaultGeneratorProfile,
+    PassphraseWordlist,
+    DefaultPasswordPolicy,
+    BreachHashFile,
+    BreachAction,
+    ForbidReuse,
     // Add other settings as needed
 }
 
@@ -16,8 +29,15 @@ impl Setting {
         match self {
             Setting::SessionDuration => "session_duration",
             Setting::ClipboardTimeout => "clipboard_timeout", 
-            Setting::DefaultTimeout => "default_timeout",
+            Setting::DefaultMaxAge => "default_max_age",
             Setting::EncryptionIterations => "encryption_iterations",
+            Setting::HistoryLimit => "history_limit",
+            Setting::DefaultGeneratorProfile => "default_generator_profile",
+            Setting::PassphraseWordlist => "passphrase_wordlist",
+            Setting::DefaultPasswordPolicy => "default_password_policy",
+            Setting::BreachHashFile => "breach_hash_file",
+            Setting::BreachAction => "breach_action",
+            Setting::ForbidReuse => "forbid_reuse",
         }
     }
     
@@ -25,8 +45,15 @@ impl Setting {
         match self {
             Setting::SessionDuration => "30",
             Setting::ClipboardTimeout => "10",
-            Setting::DefaultTimeout => "120",
+            Setting::DefaultMaxAge => "180",
             Setting::EncryptionIterations => "100000",
+            Setting::HistoryLimit => "10",
+            Setting::DefaultGeneratorProfile => "strong",
+            Setting::PassphraseWordlist => "",
+            Setting::DefaultPasswordPolicy => "default",
+            Setting::BreachHashFile => "",
+            Setting::BreachAction => "fail",
+            Setting::ForbidReuse => "false",
         }
     }
     
@@ -34,8 +61,15 @@ impl Setting {
         match self {
             Setting::SessionDuration => "Duration of session in minutes",
             Setting::ClipboardTimeout => "Time in seconds before clipboard is cleared",
-            Setting::DefaultTimeout => "Default timeout for entries in seconds",
+            Setting::DefaultMaxAge => "Days before an entry password should be rotated, 0 disables expiry",
             Setting::EncryptionIterations => "Number of iterations for key derivation",
+            Setting::HistoryLimit => "Number of previous passwords kept for each entry",
+            Setting::DefaultGeneratorProfile => "Generator profile used by --suggest and kofl generate",
+            Setting::PassphraseWordlist => "Word list file for passphrases, empty for the embedded one",
+            Setting::DefaultPasswordPolicy => "Password policy for the entries without one, see password_policy.<name>",
+            Setting::BreachHashFile => "Local HIBP SHA-1 hash file or range directory, empty to only check common passwords",
+            Setting::BreachAction => "What to do with a breached password: fail or warn",
+            Setting::ForbidReuse => "Refuse a password already used by another entry instead of warning",
         }
     }
     
@@ -48,6 +82,52 @@ impl Setting {
                     Err(_) => Err("Value must be a positive integer".to_string()),
                 }
             },
+            Setting::DefaultMaxAge => {
+                match value.parse::<u32>() {
+                    Ok(days) if days <= MAX_AGE_LIMIT_DAYS => Ok(()),
+                    Ok(_) => Err(format!("Maximum age must be between 0 and {} days", MAX_AGE_LIMIT_DAYS)),
+                    Err(_) => Err("Value must be a positive integer".to_string()),
+                }
+            },
+            Setting::HistoryLimit => {
+                match value.parse::<u32>() {
+                    Ok(versions) if versions <= 100 => Ok(()),
+                    Ok(_) => Err("History limit must be between 0 and 100 versions".to_string()),
+                    Err(_) => Err("Value must be a positive integer".to_string()),
+                }
+            },
+            Setting::DefaultGeneratorProfile | Setting::DefaultPasswordPolicy => {
+                if is_valid_profile_name(value) {
+                    Ok(())
+                } else {
+                    Err("Names may only contain letters, digits, '-' and '_'".to_string())
+                }
+            },
+            Setting::PassphraseWordlist => {
+                if value.is_empty() {
+                    return Ok(());
+                }
+                load_wordlist(Some(Path::new(value))).map(|_| ())
+            },
+            Setting::BreachHashFile => {
+                if value.is_empty() || Path::new(value).exists() {
+                    Ok(())
+                } else {
+                    Err(format!("{} does not exist", value))
+                }
+            },
+            Setting::BreachAction => {
+                match value {
+                    "fail" | "warn" => Ok(()),
+                    _ => Err("Value must be fail or warn".to_string()),
+                }
+            },
+            Setting::ForbidReuse => {
+                match value {
+                    "true" | "false" => Ok(()),
+                    _ => Err("Value must be true or false".to_string()),
+                }
+            },
             // Add validation for other settings
             _ => Ok(()),
         }
@@ -70,18 +150,36 @@ impl SettingsManager {
         vec![
             Setting::SessionDuration,
             Setting::ClipboardTimeout,
-            Setting::DefaultTimeout,
-            Setting::EncryptionIterations
+            Setting::DefaultMaxAge,
+            Setting::EncryptionIterations,
+            Setting::HistoryLimit,
+            Setting::DefaultGeneratorProfile,
+            Setting::PassphraseWordlist,
+            Setting::DefaultPasswordPolicy,
+            Setting::BreachHashFile,
+            Setting::BreachAction,
+            Setting::ForbidReuse,
         ]
     }
 
+    pub fn find(&self, key: &str) -> Option<Setting> {
+        self.list_settings().into_iter().find(|setting| setting.key() == key)
+    }
+
     // Initialize default settings in the database
     pub fn initialize_defaults(&self, context: &Context) -> Result<(), String> {
         let settings = [
             Setting::SessionDuration,
             Setting::ClipboardTimeout,
-            Setting::DefaultTimeout,
+            Setting::DefaultMaxAge,
             Setting::EncryptionIterations,
+            Setting::HistoryLimit,
+            Setting::DefaultGeneratorProfile,
+            Setting::PassphraseWordlist,
+            Setting::DefaultPasswordPolicy,
+            Setting::BreachHashFile,
+            Setting::BreachAction,
+            Setting::ForbidReuse,
         ];
 
         for setting in settings {
@@ -136,6 +234,14 @@ impl SettingsManager {
         Ok(())
     }
 
+    // Falls back to the default when the setting was never stored
+    pub fn get_u32_or_default(&mut self, context: &Context, setting: Setting) -> u32 {
+        match self.get_u32(context, setting) {
+            Ok(value) => value,
+            Err(_) => setting.default_value().parse().unwrap_or_default(),
+        }
+    }
+
     // Convenience methods for specific settings
     pub fn get_session_duration(&mut self, context: &Context) -> Result<u32, String> {
         self.get_u32(context, Setting::SessionDuration)
@@ -144,4 +250,49 @@ impl SettingsManager {
     pub fn set_session_duration(&mut self, context: &Context, minutes: u32) -> Result<(), String> {
         self.set(context, Setting::SessionDuration, &minutes.to_string())
     }
-}
\ No newline at end of file
+
+    pub fn get_history_limit(&mut self, context: &Context) -> u32 {
+        self.get_u32_or_default(context, Setting::HistoryLimit)
+    }
+
+    pub fn get_default_max_age(&mut self, context: &Context) -> u32 {
+        self.get_u32_or_default(context, Setting::DefaultMaxAge)
+    }
+
+    pub fn get_default_generator_profile(&mut self, context: &Context) -> String {
+        self.get_string(context, Setting::DefaultGeneratorProfile)
+            .unwrap_or_else(|_| Setting::DefaultGeneratorProfile.default_value().to_string())
+    }
+
+    pub fn get_passphrase_wordlist(&mut self, context: &Context) -> Option<PathBuf> {
+        self.get_string(context, Setting::PassphraseWordlist)
+            .ok()
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+    }
+
+    pub fn get_default_password_policy(&mut self, context: &Context) -> String {
+        self.get_string(context, Setting::DefaultPasswordPolicy)
+            .unwrap_or_else(|_| Setting::DefaultPasswordPolicy.default_value().to_string())
+    }
+
+    pub fn get_breach_hash_file(&mut self, context: &Context) -> Option<PathBuf> {
+        self.get_string(context, Setting::BreachHashFile)
+            .ok()
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+    }
+
+    /// Whether a breached password fails the validation, or only warns.
+    pub fn breach_fails(&mut self, context: &Context) -> bool {
+        self.get_string(context, Setting::BreachAction)
+            .map(|action| action != "warn")
+            .unwrap_or(true)
+    }
+
+    pub fn get_forbid_reuse(&mut self, context: &Context) -> bool {
+        self.get_string(context, Setting::ForbidReuse)
+            .map(|value| value == "true")
+            .unwrap_or(false)
+    }
+}
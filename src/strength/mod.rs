@@ -0,0 +1,595 @@
+//! Password strength estimation in the spirit of zxcvbn.
+//!
+//! The password is split into the cheapest sequence of patterns an attacker
+//! would try (common passwords, words, keyboard walks, sequences, repeats,
+//! dates) and the guesses needed for each part are multiplied together.
+
+use crate::generator::passphrase::load_wordlist;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+
+pub(crate) const COMMON_PASSWORDS: &str = include_str!("common_passwords.txt");
+
+/// Longer passwords are cut, their tail does not make them weaker.
+const MAX_ANALYZED_LENGTH: usize = 100;
+const BRUTEFORCE_CARDINALITY: f64 = 10.0;
+const MIN_YEAR_SPACE: f64 = 20.0;
+const REFERENCE_YEAR: f64 = 2026.0;
+/// Guesses per second against a slow password hash.
+const OFFLINE_SLOW_HASH_RATE: f64 = 1e4;
+
+const KEYBOARD_ROWS: [&str; 4] = ["`1234567890-=", "qwertyuiop[]\\", "asdfghjkl;'", "zxcvbnm,./"];
+const SHIFTED_ROWS: [&str; 4] = ["~!@#$%^&*()_+", "QWERTYUIOP{}|", "ASDFGHJKL:\"", "ZXCVBNM<>?"];
+const KEYBOARD_STARTS: f64 = 47.0;
+const KEYBOARD_AVERAGE_DEGREE: f64 = 4.6;
+
+const LEET: [(char, &str); 10] = [
+    ('4', "a"),
+    ('@', "a"),
+    ('3', "e"),
+    ('1', "il"),
+    ('!', "i"),
+    ('0', "o"),
+    ('$', "s"),
+    ('5', "s"),
+    ('7', "t"),
+    ('+', "t"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pattern {
+    CommonPassword,
+    Word,
+    UserInput,
+    Keyboard,
+    Sequence,
+    Repeat,
+    Date,
+}
+
+#[derive(Debug, Clone)]
+struct Match {
+    start: usize,
+    end: usize,
+    pattern: Pattern,
+    guesses: f64,
+    leet: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Strength {
+    pub guesses: f64,
+    /// From 0 (too guessable) to 4 (very unguessable), as in zxcvbn.
+    pub score: u8,
+    pub crack_time_seconds: f64,
+    /// Why the password is weak, empty for strong ones.
+    pub feedback: Vec<String>,
+}
+
+impl Strength {
+    pub fn crack_time(&self) -> String {
+        display_duration(self.crack_time_seconds)
+    }
+}
+
+impl fmt::Display for Strength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "score {}/4, about {} to crack offline", self.score, self.crack_time())
+    }
+}
+
+fn display_duration(seconds: f64) -> String {
+    const MINUTE: f64 = 60.0;
+    const HOUR: f64 = MINUTE * 60.0;
+    const DAY: f64 = HOUR * 24.0;
+    const MONTH: f64 = DAY * 31.0;
+    const YEAR: f64 = MONTH * 12.0;
+    const CENTURY: f64 = YEAR * 100.0;
+
+    let (amount, unit) = match seconds {
+        s if s < 1.0 => return "less than a second".to_string(),
+        s if s < MINUTE => (s, "second"),
+        s if s < HOUR => (s / MINUTE, "minute"),
+        s if s < DAY => (s / HOUR, "hour"),
+        s if s < MONTH => (s / DAY, "day"),
+        s if s < YEAR => (s / MONTH, "month"),
+        s if s < CENTURY => (s / YEAR, "year"),
+        _ => return "centuries".to_string(),
+    };
+    let amount = amount.round() as u64;
+    format!("{} {}{}", amount, unit, if amount == 1 { "" } else { "s" })
+}
+
+fn ranked(words: impl Iterator<Item = String>) -> HashMap<String, usize> {
+    let mut ranks = HashMap::new();
+    for (index, word) in words.enumerate() {
+        ranks.entry(word).or_insert(index + 1);
+    }
+    ranks
+}
+
+/// Ranked dictionaries, the rank is the number of guesses to reach a word.
+struct Dictionaries {
+    common: HashMap<String, usize>,
+    words: HashMap<String, usize>,
+    user_inputs: HashMap<String, usize>,
+}
+
+impl Dictionaries {
+    fn new(user_inputs: &[&str]) -> Self {
+        let common = ranked(COMMON_PASSWORDS.lines().map(str::trim).filter(|l| !l.is_empty()).map(str::to_lowercase));
+        // the list is not ordered by frequency, every word costs the same
+        let wordlist = load_wordlist(None).unwrap_or_default();
+        let words = wordlist.iter().map(|word| (word.clone(), wordlist.len() / 2)).collect();
+        let user_inputs = ranked(
+            user_inputs
+                .iter()
+                .flat_map(|input| input.split(|c: char| !c.is_alphanumeric()))
+                .filter(|token| token.chars().count() >= 3)
+                .map(str::to_lowercase),
+        );
+        Dictionaries { common, words, user_inputs }
+    }
+
+    fn lookup(&self, word: &str) -> Option<(Pattern, usize)> {
+        [
+            (Pattern::UserInput, &self.user_inputs),
+            (Pattern::CommonPassword, &self.common),
+            (Pattern::Word, &self.words),
+        ]
+        .into_iter()
+        .filter_map(|(pattern, dictionary)| dictionary.get(word).map(|rank| (pattern, *rank)))
+        .min_by_key(|(_, rank)| *rank)
+    }
+}
+
+// guesses multiplier for the capitalization of a word
+fn uppercase_variations(word: &[char]) -> f64 {
+    let upper = word.iter().filter(|c| c.is_uppercase()).count();
+    let lower = word.iter().filter(|c| c.is_lowercase()).count();
+    if upper == 0 {
+        return 1.0;
+    }
+    let first_only = upper == 1 && word.first().map_or(false, |c| c.is_uppercase());
+    let last_only = upper == 1 && word.last().map_or(false, |c| c.is_uppercase());
+    if lower == 0 || first_only || last_only {
+        return 2.0;
+    }
+    (1..=upper.min(lower)).map(|k| binomial(upper + lower, k)).sum::<f64>().max(1.0)
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+// every reading of the l33t characters, '1' can be an i or an l
+fn unleet(word: &[char]) -> Vec<String> {
+    let mut readings = vec![String::new()];
+    for c in word {
+        let lower = c.to_lowercase().next().unwrap_or(*c);
+        match LEET.iter().find(|(leet, _)| *leet == lower) {
+            Some((_, letters)) if readings.len() < 16 => {
+                readings = readings
+                    .iter()
+                    .flat_map(|reading| letters.chars().map(move |letter| format!("{}{}", reading, letter)))
+                    .collect();
+            }
+            _ => readings.iter_mut().for_each(|reading| reading.push(lower)),
+        }
+    }
+    readings
+}
+
+fn dictionary_matches(chars: &[char], dictionaries: &Dictionaries) -> Vec<Match> {
+    let mut matches = Vec::new();
+    for start in 0..chars.len() {
+        for end in start + 3..=chars.len() {
+            let word = &chars[start..end];
+            let lowered: String = word.iter().flat_map(|c| c.to_lowercase()).collect();
+            let plain = dictionaries.lookup(&lowered);
+            let leet = if plain.is_none() && word.iter().any(|c| LEET.iter().any(|(leet, _)| leet == c)) {
+                unleet(word).iter().filter_map(|reading| dictionaries.lookup(reading)).min_by_key(|(_, rank)| *rank)
+            } else {
+                None
+            };
+
+            if let Some((pattern, rank)) = plain.or(leet) {
+                let substitutions = if leet.is_some() { 2.0 } else { 1.0 };
+                matches.push(Match {
+                    start,
+                    end,
+                    pattern,
+                    guesses: rank as f64 * uppercase_variations(word) * substitutions,
+                    leet: leet.is_some(),
+                });
+            }
+        }
+    }
+    matches
+}
+
+fn key_position(c: char) -> Option<(i32, i32, bool)> {
+    for (row, (keys, shifted)) in KEYBOARD_ROWS.iter().zip(SHIFTED_ROWS.iter()).enumerate() {
+        if let Some(col) = keys.chars().position(|k| k == c) {
+            return Some((row as i32, col as i32, false));
+        }
+        if let Some(col) = shifted.chars().position(|k| k == c) {
+            return Some((row as i32, col as i32, true));
+        }
+    }
+    None
+}
+
+// direction to the next key when it is adjacent on a staggered qwerty keyboard
+fn key_step(from: char, to: char) -> Option<(i32, i32)> {
+    let (r1, c1, _) = key_position(from)?;
+    let (r2, c2, _) = key_position(to)?;
+    let step = (r2 - r1, c2 - c1);
+    match step {
+        (0, -1) | (0, 1) | (-1, 0) | (-1, 1) | (1, -1) | (1, 0) => Some(step),
+        _ => None,
+    }
+}
+
+fn keyboard_guesses(length: usize, turns: usize, shifted: usize) -> f64 {
+    let mut guesses = 0.0;
+    for i in 2..=length {
+        for j in 1..=turns.min(i - 1) {
+            guesses += binomial(i - 1, j - 1) * KEYBOARD_STARTS * KEYBOARD_AVERAGE_DEGREE.powi(j as i32);
+        }
+    }
+    if shifted > 0 {
+        let unshifted = length - shifted;
+        guesses *= if unshifted == 0 { 2.0 } else { (1..=shifted.min(unshifted)).map(|k| binomial(length, k)).sum() };
+    }
+    guesses
+}
+
+fn keyboard_matches(chars: &[char]) -> Vec<Match> {
+    let mut matches = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        let mut end = start + 1;
+        let mut turns = 0;
+        let mut direction = None;
+        while end < chars.len() {
+            match key_step(chars[end - 1], chars[end]) {
+                Some(step) => {
+                    if direction != Some(step) {
+                        turns += 1;
+                        direction = Some(step);
+                    }
+                    end += 1;
+                }
+                None => break,
+            }
+        }
+        if end - start >= 3 {
+            let shifted = chars[start..end].iter().filter(|c| key_position(**c).map_or(false, |(_, _, s)| s)).count();
+            matches.push(Match {
+                start,
+                end,
+                pattern: Pattern::Keyboard,
+                guesses: keyboard_guesses(end - start, turns, shifted),
+                leet: false,
+            });
+        }
+        start = if end - start >= 3 { end } else { start + 1 };
+    }
+    matches
+}
+
+fn sequence_matches(chars: &[char]) -> Vec<Match> {
+    let same_class = |a: char, b: char| {
+        (a.is_ascii_lowercase() && b.is_ascii_lowercase())
+            || (a.is_ascii_uppercase() && b.is_ascii_uppercase())
+            || (a.is_ascii_digit() && b.is_ascii_digit())
+    };
+
+    let mut matches = Vec::new();
+    let mut start = 0;
+    while start + 1 < chars.len() {
+        let delta = chars[start + 1] as i32 - chars[start] as i32;
+        let mut end = start + 1;
+        while end < chars.len()
+            && (delta == 1 || delta == -1)
+            && chars[end] as i32 - chars[end - 1] as i32 == delta
+            && same_class(chars[end - 1], chars[end])
+        {
+            end += 1;
+        }
+        if end - start >= 3 {
+            let first = chars[start];
+            let base = if "aAzZ01".contains(first) {
+                4.0
+            } else if first.is_ascii_digit() {
+                10.0
+            } else {
+                26.0
+            };
+            let direction = if delta < 0 { 2.0 } else { 1.0 };
+            matches.push(Match {
+                start,
+                end,
+                pattern: Pattern::Sequence,
+                guesses: base * (end - start) as f64 * direction,
+                leet: false,
+            });
+            start = end;
+        } else {
+            start += 1;
+        }
+    }
+    matches
+}
+
+// the repeated unit is estimated once, however many times and places it repeats
+fn repeat_matches(chars: &[char], dictionaries: &Dictionaries, unit_guesses: &mut HashMap<Vec<char>, f64>) -> Vec<Match> {
+    let mut matches = Vec::new();
+    for start in 0..chars.len() {
+        for unit in 1..=(chars.len() - start) / 2 {
+            let mut end = start + unit;
+            while end + unit <= chars.len() && chars[end..end + unit] == chars[start..start + unit] {
+                end += unit;
+            }
+            let count = (end - start) / unit;
+            if count >= 2 && end - start >= 3 {
+                let unit_chars = &chars[start..start + unit];
+                let base = match unit_guesses.get(unit_chars) {
+                    Some(guesses) => *guesses,
+                    None => {
+                        let guesses = estimate(unit_chars, dictionaries, unit_guesses).0;
+                        unit_guesses.insert(unit_chars.to_vec(), guesses);
+                        guesses
+                    }
+                };
+                matches.push(Match { start, end, pattern: Pattern::Repeat, guesses: base * count as f64, leet: false });
+            }
+        }
+    }
+    matches
+}
+
+fn year_guesses(year: u32) -> f64 {
+    (year as f64 - REFERENCE_YEAR).abs().max(MIN_YEAR_SPACE)
+}
+
+fn two_digit_year(year: u32) -> u32 {
+    if year > 50 { 1900 + year } else { 2000 + year }
+}
+
+// dates such as 1990, 19900423, 23.04.1990 or 230490
+fn date_guesses(text: &[char]) -> Option<f64> {
+    let separators: Vec<char> = text.iter().filter(|c| !c.is_ascii_digit()).copied().collect();
+    if separators.iter().any(|c| !"/-._ ".contains(*c)) {
+        return None;
+    }
+    if !separators.is_empty() && (separators.len() != 2 || separators[0] != separators[1]) {
+        return None;
+    }
+    let digits: String = text.iter().filter(|c| c.is_ascii_digit()).collect();
+    let number = |range: std::ops::Range<usize>| digits[range].parse::<u32>().ok();
+    let valid = |day: u32, month: u32| (1..=31).contains(&day) && (1..=12).contains(&month);
+    let separator_factor = if separators.is_empty() { 1.0 } else { 4.0 };
+
+    let year = match digits.len() {
+        4 if separators.is_empty() => number(0..4).filter(|y| (1900..=2050).contains(y)),
+        6 => {
+            let (a, b, c) = (number(0..2)?, number(2..4)?, number(4..6)?);
+            if valid(a, b) || valid(b, a) {
+                Some(two_digit_year(c))
+            } else if valid(c, b) {
+                Some(two_digit_year(a))
+            } else {
+                None
+            }
+        }
+        8 => {
+            let (head, tail) = (number(0..4)?, number(4..8)?);
+            let (a, b) = (number(4..6)?, number(6..8)?);
+            let (c, d) = (number(0..2)?, number(2..4)?);
+            if (1900..=2050).contains(&head) && (valid(a, b) || valid(b, a)) {
+                Some(head)
+            } else if (1900..=2050).contains(&tail) && (valid(c, d) || valid(d, c)) {
+                Some(tail)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }?;
+
+    let days = if digits.len() == 4 { 1.0 } else { 365.0 };
+    Some(year_guesses(year) * days * separator_factor)
+}
+
+fn date_matches(chars: &[char]) -> Vec<Match> {
+    let mut matches = Vec::new();
+    for start in 0..chars.len() {
+        for end in start + 4..=(start + 10).min(chars.len()) {
+            if !chars[start].is_ascii_digit() || !chars[end - 1].is_ascii_digit() {
+                continue;
+            }
+            if let Some(guesses) = date_guesses(&chars[start..end]) {
+                matches.push(Match { start, end, pattern: Pattern::Date, guesses, leet: false });
+            }
+        }
+    }
+    matches
+}
+
+fn factorial(n: usize) -> f64 {
+    (1..=n).map(|i| i as f64).product()
+}
+
+/// The cheapest way to cover the password with matches and brute-forced
+/// characters, with a penalty for stitching many matches together.
+fn estimate(chars: &[char], dictionaries: &Dictionaries, unit_guesses: &mut HashMap<Vec<char>, f64>) -> (f64, Vec<Match>) {
+    let n = chars.len();
+    if n == 0 {
+        return (1.0, Vec::new());
+    }
+
+    let mut matches = dictionary_matches(chars, dictionaries);
+    matches.extend(keyboard_matches(chars));
+    matches.extend(sequence_matches(chars));
+    matches.extend(date_matches(chars));
+    matches.extend(repeat_matches(chars, dictionaries, unit_guesses));
+
+    // best[end][count]: fewest guesses for chars[..end] using `count` matches
+    let mut best = vec![vec![f64::INFINITY; n + 1]; n + 1];
+    let mut previous: Vec<Vec<Option<(usize, usize, Option<usize>)>>> = vec![vec![None; n + 1]; n + 1];
+    best[0][0] = 1.0;
+    for end in 1..=n {
+        for count in 0..=n {
+            // one more brute-forced character
+            let guesses = best[end - 1][count] * BRUTEFORCE_CARDINALITY;
+            if guesses < best[end][count] {
+                best[end][count] = guesses;
+                previous[end][count] = Some((end - 1, count, None));
+            }
+        }
+        for (index, m) in matches.iter().enumerate().filter(|(_, m)| m.end == end) {
+            for count in 0..n {
+                let guesses = best[m.start][count] * m.guesses.max(1.0);
+                if guesses < best[end][count + 1] {
+                    best[end][count + 1] = guesses;
+                    previous[end][count + 1] = Some((m.start, count, Some(index)));
+                }
+            }
+        }
+    }
+
+    let (count, guesses) = (0..=n)
+        .map(|count| (count, best[n][count] * factorial(count)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap(); // n + 1 candidates
+
+    let mut path = Vec::new();
+    let (mut end, mut count) = (n, count);
+    while let Some((start, previous_count, index)) = previous[end][count] {
+        if let Some(index) = index {
+            path.push(matches[index].clone());
+        }
+        end = start;
+        count = previous_count;
+    }
+    path.reverse();
+    (guesses, path)
+}
+
+fn score(guesses: f64) -> u8 {
+    match guesses {
+        g if g < 1e3 + 5.0 => 0,
+        g if g < 1e6 + 5.0 => 1,
+        g if g < 1e8 + 5.0 => 2,
+        g if g < 1e10 + 5.0 => 3,
+        _ => 4,
+    }
+}
+
+fn feedback(path: &[Match], score: u8) -> Vec<String> {
+    if score >= 3 {
+        return Vec::new();
+    }
+
+    let mut feedback = Vec::new();
+    let mut add = |message: &str| {
+        if !feedback.iter().any(|existing| existing == message) {
+            feedback.push(message.to_string());
+        }
+    };
+    for m in path {
+        match m.pattern {
+            Pattern::CommonPassword => add("This is similar to a commonly used password"),
+            Pattern::Word => add("A word by itself is easy to guess"),
+            Pattern::UserInput => add("Avoid the entry name, username or website in the password"),
+            Pattern::Keyboard => add("Straight rows or patterns of keys are easy to guess"),
+            Pattern::Sequence => add("Sequences like abc or 6543 are easy to guess"),
+            Pattern::Repeat => add("Repeats like aaa or abcabc are easy to guess"),
+            Pattern::Date => add("Dates are often easy to guess"),
+        }
+        if m.leet {
+            add("Predictable substitutions like '@' instead of 'a' do not help much");
+        }
+    }
+    add("Add more words or characters, or use `--suggest`");
+    feedback
+}
+
+/// Estimates how hard the password is to guess. `user_inputs` are strings an
+/// attacker would try first, such as the entry name or the username.
+pub fn estimate_strength(password: &str, user_inputs: &[&str]) -> Strength {
+    let chars: Vec<char> = password.chars().take(MAX_ANALYZED_LENGTH).collect();
+    let dictionaries = Dictionaries::new(user_inputs);
+    let (guesses, path) = estimate(&chars, &dictionaries, &mut HashMap::new());
+    let score = score(guesses);
+    Strength {
+        guesses,
+        score,
+        crack_time_seconds: guesses / OFFLINE_SLOW_HASH_RATE,
+        feedback: feedback(&path, score),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score_of(password: &str) -> u8 {
+        estimate_strength(password, &[]).score
+    }
+
+    #[test]
+    fn test_weak_passwords() {
+        for password in ["password", "Password1!", "P@ssw0rd", "qwertyuiop", "abcdefgh", "aaaaaaaaaa", "19900423", "abcabcabcabc"] {
+            assert!(score_of(password) <= 1, "{} scored {}", password, score_of(password));
+        }
+        let strength = estimate_strength("Password1!", &[]);
+        assert!(strength.feedback.iter().any(|f| f.contains("commonly used")));
+    }
+
+    #[test]
+    fn test_strong_passwords() {
+        for password in ["xK9#mQ2$vL7!pR4z", "correct-horse-battery-staple-river", "Tq8!wz#4Lm0p"] {
+            assert!(score_of(password) >= 3, "{} scored {}", password, score_of(password));
+        }
+        assert!(estimate_strength("xK9#mQ2$vL7!pR4z", &[]).feedback.is_empty());
+    }
+
+    #[test]
+    fn test_patterns() {
+        let chars: Vec<char> = "zxcvbn".chars().collect();
+        assert_eq!(keyboard_matches(&chars).len(), 1);
+        let chars: Vec<char> = "x6789y".chars().collect();
+        assert_eq!(sequence_matches(&chars)[0].end, 5);
+        assert!(date_guesses(&"23.04.1990".chars().collect::<Vec<_>>()).is_some());
+        assert!(date_guesses(&"99999999".chars().collect::<Vec<_>>()).is_none());
+        assert!(unleet(&"p4$$".chars().collect::<Vec<_>>()).contains(&"pass".to_string()));
+    }
+
+    #[test]
+    fn test_long_repeats() {
+        // used to re-estimate the unit at every position, taking over a minute
+        let started = std::time::Instant::now();
+        for password in ["a".repeat(100), "aB".repeat(50), "x7#".repeat(27)] {
+            assert!(score_of(&password) <= 1, "{} scored {}", password, score_of(&password));
+        }
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_user_inputs() {
+        let alone = estimate_strength("Mybank2024!", &[]).guesses;
+        let with_inputs = estimate_strength("Mybank2024!", &["mybank"]).guesses;
+        assert!(with_inputs < alone);
+    }
+
+    #[test]
+    fn test_crack_time() {
+        assert_eq!(display_duration(0.5), "less than a second");
+        assert_eq!(display_duration(7200.0), "2 hours");
+        assert_eq!(display_duration(1e12), "centuries");
+    }
+}
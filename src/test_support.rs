@@ -0,0 +1,85 @@
+//! Environment shared by the test modules, so each test works on a vault of
+//! its own in a temporary home and leaves the real one alone.
+
+use crate::cli::secret::MASTER_PASSWORD_FILE_ENV;
+use crate::context::Context;
+use std::env;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Restores an environment variable to what it was when dropped.
+pub struct EnvGuard {
+    key: &'static str,
+    original: Option<String>,
+}
+
+impl EnvGuard {
+    pub fn new(key: &'static str) -> Self {
+        let original = env::var(key).ok();
+        Self { key, original }
+    }
+
+    pub fn set_var(&self, value: &str) {
+        env::set_var(self.key, value);
+    }
+
+    pub fn remove_var(&self) {
+        env::remove_var(self.key);
+    }
+}
+
+impl Drop for EnvGuard {
+    fn drop(&mut self) {
+        match &self.original {
+            Some(original) => env::set_var(self.key, original),
+            None => env::remove_var(self.key),
+        }
+    }
+}
+
+/// Every directory kofl reads from the environment, pointed into a temporary
+/// root. `KOFL_HOME` is unset so the XDG layout is used.
+pub struct IsolatedEnv {
+    guards: Vec<EnvGuard>,
+}
+
+impl IsolatedEnv {
+    pub fn new(root: &Path) -> Self {
+        let dirs = [
+            ("HOME", root.to_path_buf()),
+            ("XDG_CONFIG_HOME", root.join("config")),
+            ("XDG_DATA_HOME", root.join("data")),
+            ("XDG_STATE_HOME", root.join("state")),
+            ("XDG_RUNTIME_DIR", root.join("run")),
+        ];
+        let mut guards = Vec::new();
+        for (key, dir) in dirs {
+            let guard = EnvGuard::new(key);
+            guard.set_var(dir.to_str().unwrap());
+            guards.push(guard);
+        }
+        for key in ["KOFL_HOME", MASTER_PASSWORD_FILE_ENV] {
+            let guard = EnvGuard::new(key);
+            guard.remove_var();
+            guards.push(guard);
+        }
+        IsolatedEnv { guards }
+    }
+
+    /// Changes one of the isolated variables, it is restored with the others.
+    pub fn set_var(&self, key: &str, value: &str) {
+        self.guards
+            .iter()
+            .find(|guard| guard.key == key)
+            .unwrap_or_else(|| panic!("{} is not isolated", key))
+            .set_var(value);
+    }
+}
+
+/// A fresh vault in `<temp_dir>/vault`, keep the environment alive as long as the context.
+pub fn isolated_context(temp_dir: &TempDir) -> (IsolatedEnv, Context) {
+    let env = IsolatedEnv::new(temp_dir.path());
+    env.set_var("KOFL_HOME", temp_dir.path().join("vault").to_str().unwrap());
+    let context = Context::new().unwrap();
+    (env, context)
+}
@@ -1,6 +1,8 @@
 
 pub mod Utils {
 
+    use crate::vault::VaultPaths;
+    use chrono::{DateTime, Local, Utc};
     use std::env;
     use std::path::PathBuf;
     use std::fs;
@@ -8,12 +10,54 @@ pub mod Utils {
     /// Utility function to get the user's home directory.
     /// Returns a PathBuf representing the home directory path.
     pub fn get_home_dir() -> Option<PathBuf> {
-        env::home_dir()
+        #[cfg(windows)]
+        let var = "USERPROFILE";
+        #[cfg(not(windows))]
+        let var = "HOME";
+
+        env::var_os(var)
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+    }
+
+    // XDG base directory from the environment, ignored unless absolute as the spec requires
+    fn get_xdg_dir(var: &str, fallback: &str) -> PathBuf {
+        match env::var(var) {
+            Ok(dir) if PathBuf::from(&dir).is_absolute() => PathBuf::from(dir),
+            _ => get_home_dir().expect("Home directory not found").join(fallback),
+        }
+    }
+
+    /// Formats a stored timestamp in the local timezone for display.
+    pub fn format_timestamp(timestamp: &DateTime<Utc>) -> String {
+        timestamp.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string()
+    }
+
+    /// `$XDG_CONFIG_HOME`, defaults to `~/.config`.
+    pub fn get_config_home() -> PathBuf {
+        get_xdg_dir("XDG_CONFIG_HOME", ".config")
+    }
+
+    /// `$XDG_DATA_HOME`, defaults to `~/.local/share`.
+    pub fn get_data_home() -> PathBuf {
+        get_xdg_dir("XDG_DATA_HOME", ".local/share")
+    }
+
+    /// `$XDG_STATE_HOME`, defaults to `~/.local/state`.
+    pub fn get_state_home() -> PathBuf {
+        get_xdg_dir("XDG_STATE_HOME", ".local/state")
+    }
+
+    /// `$XDG_RUNTIME_DIR`, there is no default, the caller picks a fallback.
+    pub fn get_runtime_dir() -> Option<PathBuf> {
+        env::var("XDG_RUNTIME_DIR")
+            .ok()
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
     }
 
     pub fn get_backup_dir() -> Option<PathBuf> {
-        let home_dir = get_home_dir().expect("Could not find home directory");
-        let back_dir = home_dir.join(".kofl_backups");
+        let back_dir = VaultPaths::resolve().backup_dir;
     
         if back_dir.is_dir() {
             Some(back_dir)
@@ -29,6 +73,18 @@ pub mod Utils {
         }
     }
     
+    /// Directory where files failing an integrity check are set aside.
+    pub fn get_quarantine_dir() -> Option<PathBuf> {
+        let quarantine_dir = VaultPaths::resolve().quarantine_dir;
+        match std::fs::create_dir_all(&quarantine_dir) {
+            Ok(_) => Some(quarantine_dir),
+            Err(e) => {
+                eprintln!("Failed to create quarantine directory: {}", e);
+                None
+            }
+        }
+    }
+
     /// Utility function to create a config path in the user's home directory.
     /// Takes a filename as a parameter and returns the full path.
     pub fn get_config_path(filename: &str) -> Option<PathBuf> {
@@ -41,18 +97,10 @@ pub mod Utils {
     }
 
     pub fn check_existing_config() -> bool {
-        if let Some(home_dir) = get_home_dir() {
-            // println!("home dir = {:?}", home_dir.join(".kofl"));
-            return fs::exists(home_dir.join(".kofl")).unwrap();
-        }
-        false
+        VaultPaths::resolve().config_path.exists()
     }
 
     pub fn check_existing_session_config() -> bool {
-        if let Some(home_dir) = get_home_dir() {
-            // println!("home dir = {:?}", home_dir.join(".kofl"));
-            return fs::exists(home_dir.join(".kofl_session")).unwrap();
-        }
-        false
+        VaultPaths::resolve().session_path.exists()
     }
 }
\ No newline at end of file
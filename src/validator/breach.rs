@@ -0,0 +1,41 @@
+use crate::validator::core::{Validator, ValidationResult};
+use crate::context::Context;
+use crate::cli::commands::{AddCmd, UpdateCmd};
+use crate::breach::check_password;
+
+pub struct BreachValidator {}
+
+impl BreachValidator {
+    fn check(context: &Context, password: &str, generated: bool) -> ValidationResult {
+        // a fresh random password cannot be in a breach
+        if generated {
+            return ValidationResult::Success;
+        }
+
+        let (hash_file, fails) = {
+            let mut settings = context.settings.borrow_mut();
+            (settings.get_breach_hash_file(context), settings.breach_fails(context))
+        };
+        match check_password(password, hash_file.as_deref()) {
+            Ok(None) => ValidationResult::Success,
+            Ok(Some(breach)) if fails => ValidationResult::Failure(format!("Breached password: {}", breach)),
+            Ok(Some(breach)) => ValidationResult::Warning(format!("Breached password: {}", breach)),
+            // a missing dump should not block every add
+            Err(e) => ValidationResult::Warning(e),
+        }
+    }
+}
+
+impl Validator<AddCmd> for BreachValidator {
+    fn validate(&self, context: &Context, cmd: &AddCmd) -> ValidationResult {
+        log::debug!("Running BreachValidator");
+        BreachValidator::check(context, &cmd.password, cmd.suggest_flag)
+    }
+}
+
+impl Validator<UpdateCmd> for BreachValidator {
+    fn validate(&self, context: &Context, cmd: &UpdateCmd) -> ValidationResult {
+        log::debug!("Running BreachValidator for UpdateCmd");
+        BreachValidator::check(context, &cmd.password, cmd.suggest_flag)
+    }
+}
@@ -8,6 +8,7 @@ pub enum ValidationType {
     EntryExistsCheck,
     DuplicateEntryCheck,
     PasswordRequirementCheck,
+    BreachCheck,
 }
 
 pub enum CommandType {
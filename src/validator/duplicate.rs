@@ -1,12 +1,115 @@
-
 use crate::validator::core::{Validator, ValidationResult};
 use crate::context::Context;
+use crate::cli::commands::{AddCmd, UpdateCmd};
+use crate::crypto::secret_mac;
+use std::collections::BTreeMap;
 
 pub struct DuplicateEntryValidator {}
 
-impl<T> Validator<T> for DuplicateEntryValidator {
-    fn validate(&self, _context: &Context, _cmd: &T) -> ValidationResult {
+impl DuplicateEntryValidator {
+    /// Names of the entries sharing a secret, one group per reused secret.
+    pub(crate) fn reused<'a>(secrets: &[(&'a str, &str)]) -> Vec<Vec<&'a str>> {
+        let mut by_secret: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (name, secret) in secrets {
+            by_secret.entry(secret).or_default().push(name);
+        }
+
+        let mut groups: Vec<Vec<&str>> = by_secret.into_values().filter(|names| names.len() > 1).collect();
+        for group in &mut groups {
+            group.sort();
+        }
+        groups.sort();
+        groups
+    }
+
+    /// Looks the secret up by its keyed hash, `name` being the entry it is for.
+    fn check(context: &Context, name: &str, password: &str) -> ValidationResult {
+        let master_key_hash = context.kgc.borrow().get_hashed_pwd();
+        let mac = match secret_mac(&master_key_hash, password) {
+            Ok(mac) => mac,
+            Err(e) => return ValidationResult::Failure(e.to_string()),
+        };
+
+        let others: Vec<String> = match context.db.find_entries_by_secret_mac(&mac) {
+            Ok(names) => names.into_iter().filter(|other| other != name).collect(),
+            Err(e) => return ValidationResult::Failure(format!("Database error: {}", e)),
+        };
+        if let Ok(count) = context.db.count_entries_without_secret_mac() {
+            if count > 0 {
+                log::debug!("{} entries have no keyed hash yet, `kofl audit` adds them", count);
+            }
+        }
+        if others.is_empty() {
+            return ValidationResult::Success;
+        }
+
+        let message = format!("The password is already used by {}", others.join(", "));
+        if context.settings.borrow_mut().get_forbid_reuse(context) {
+            ValidationResult::Failure(message)
+        } else {
+            ValidationResult::Warning(message)
+        }
+    }
+}
+
+impl Validator<AddCmd> for DuplicateEntryValidator {
+    fn validate(&self, context: &Context, cmd: &AddCmd) -> ValidationResult {
         log::debug!("Running DuplicateEntryValidator");
-        ValidationResult::Success
+        DuplicateEntryValidator::check(context, &cmd.name, &cmd.password)
+    }
+}
+
+impl Validator<UpdateCmd> for DuplicateEntryValidator {
+    fn validate(&self, context: &Context, cmd: &UpdateCmd) -> ValidationResult {
+        log::debug!("Running DuplicateEntryValidator for UpdateCmd");
+        DuplicateEntryValidator::check(context, &cmd.name, &cmd.password)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Db::Entry;
+    use serial_test::serial;
+    use crate::test_support::isolated_context;
+    use tempfile::TempDir;
+
+    #[test]
+    #[serial]
+    fn test_reuse_detection() {
+        let temp_dir = TempDir::new().unwrap();
+        let (_env, context) = isolated_context(&temp_dir);
+        let master_key_hash = "ab".repeat(32);
+        context.kgc.borrow_mut().set_master_key_hash(master_key_hash.clone());
+
+        let mut mail = Entry::new(0, "mail".to_string(), String::new());
+        mail.secret_mac = secret_mac(&master_key_hash, "xK9#mQ2$vL7!pR4z").ok();
+        context.db.add_entry(mail).unwrap();
+
+        let validator = DuplicateEntryValidator {};
+        let reused = AddCmd::new("forum".to_string(), "xK9#mQ2$vL7!pR4z".to_string(), false);
+        match validator.validate(&context, &reused) {
+            ValidationResult::Warning(message) => assert!(message.contains("mail")),
+            other => panic!("expected a warning, got {:?}", other),
+        }
+        let fresh = AddCmd::new("forum".to_string(), "another-Secret-42".to_string(), false);
+        assert!(matches!(validator.validate(&context, &fresh), ValidationResult::Success));
+
+        // the entry itself does not count as a reuse
+        let same = UpdateCmd::new("mail".to_string(), "xK9#mQ2$vL7!pR4z".to_string(), false);
+        assert!(matches!(validator.validate(&context, &same), ValidationResult::Success));
+
+        context.db.set_setting_value("forbid_reuse", "true").unwrap();
+        assert!(matches!(validator.validate(&context, &reused), ValidationResult::Failure(_)));
+    }
+
+    #[test]
+    fn test_reused() {
+        let secrets = [("mail", "a"), ("bank", "b"), ("forum", "a"), ("shop", "c"), ("blog", "a"), ("work", "c")];
+        assert_eq!(
+            DuplicateEntryValidator::reused(&secrets),
+            vec![vec!["blog", "forum", "mail"], vec!["shop", "work"]]
+        );
+        assert!(DuplicateEntryValidator::reused(&[("mail", "a"), ("bank", "b")]).is_empty());
     }
 }
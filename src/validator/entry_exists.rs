@@ -1,31 +1,72 @@
-
 use crate::validator::core::{Validator, ValidationResult};
 use crate::context::Context;
-use crate::cli::commands::{AddCmd, GetCmd, UpdateCmd};
+use crate::cli::commands::{AddCmd, GetCmd, HistoryCmd, RollbackCmd, TagCmd, UpdateCmd};
+use crate::organize::validate_entry_name;
+use crate::search::{resolve_entry, Resolution};
 
 pub struct EntryExistsValidator {}
 
-// For GetCmd: succeed if the entry exists.
+impl EntryExistsValidator {
+    // a folder name gets a hint instead of the generic failure
+    fn folder_failure(&self, context: &Context, ent_name: &str) -> Option<ValidationResult> {
+        match context.db.list_entries_in_folder(ent_name) {
+            Ok(entries) if !entries.is_empty() => Some(ValidationResult::Failure(format!(
+                "{} is a folder with {} entries, see `kofl list --folder {}` ⛔",
+                ent_name,
+                entries.len(),
+                ent_name
+            ))),
+            _ => None,
+        }
+    }
+
+    // succeed if the entry exists
+    fn require_entry(&self, context: &Context, ent_name: &str) -> ValidationResult {
+        match context.db.entry_exist(ent_name.to_string()) {
+            Ok(true) => ValidationResult::Success,
+            Ok(false) => self
+                .folder_failure(context, ent_name)
+                .unwrap_or_else(|| ValidationResult::Failure("No entry found with similar name ⛔".to_string())),
+            Err(_) => ValidationResult::Failure("Error during DB check ⛔".to_string()),
+        }
+    }
+}
+
+// For GetCmd: succeed if the entry exists, a unique prefix is accepted with a warning
+// and otherwise the closest names are suggested.
 impl Validator<GetCmd> for EntryExistsValidator {
     fn validate(&self, context: &Context, cmd: &GetCmd) -> ValidationResult {
         log::debug!("Running EntryExistsValidator for GetCmd");
-        match context.db.entry_exist(cmd.ent_name.clone()) {
-            Ok(exists) => {
-                if exists {
-                    ValidationResult::Success
-                } else {
-                    ValidationResult::Failure("No entry found with similar name ⛔".to_string())
-                }
+        match resolve_entry(&context.db, &cmd.ent_name) {
+            Ok(Resolution::Exact(_)) => ValidationResult::Success,
+            Ok(Resolution::UniquePrefix(entry)) => {
+                ValidationResult::Warning(format!("No entry named {}, using {}", cmd.ent_name, entry.ent_name))
+            }
+            Ok(Resolution::NotFound { suggestions }) => {
+                self.folder_failure(context, &cmd.ent_name).unwrap_or_else(|| {
+                    if suggestions.is_empty() {
+                        ValidationResult::Failure("No entry found with similar name ⛔".to_string())
+                    } else {
+                        ValidationResult::Failure(format!(
+                            "No entry named {}, did you mean: {} ⛔",
+                            cmd.ent_name,
+                            suggestions.join(", ")
+                        ))
+                    }
+                })
             }
             Err(_) => ValidationResult::Failure("Error during DB check ⛔".to_string()),
         }
     }
 }
 
-// For AddCmd: fail if the entry already exists.
+// For AddCmd: fail if the entry already exists or the folder-qualified name is malformed.
 impl Validator<AddCmd> for EntryExistsValidator {
     fn validate(&self, context: &Context, cmd: &AddCmd) -> ValidationResult {
         log::debug!("Running EntryExistsValidator for AddCmd");
+        if let Err(reason) = validate_entry_name(&cmd.name) {
+            return ValidationResult::Failure(format!("{} ⛔", reason));
+        }
         match context.db.entry_exist(cmd.name.clone()) {
             Ok(exists) => {
                 if exists {
@@ -43,15 +84,30 @@ impl Validator<AddCmd> for EntryExistsValidator {
 impl Validator<UpdateCmd> for EntryExistsValidator {
     fn validate(&self, context: &Context, cmd: &UpdateCmd) -> ValidationResult {
         log::debug!("Running EntryExistsValidator for UpdateCmd");
-        match context.db.entry_exist(cmd.name.clone()) {
-            Ok(exists) => {
-                if exists {
-                    ValidationResult::Success
-                } else {
-                    ValidationResult::Failure("Entry already exists ⛔".to_string())
-                }
-            }
-            Err(_) => ValidationResult::Failure("Error during DB check ⛔".to_string()),
-        }
+        self.require_entry(context, &cmd.name)
+    }
+}
+
+// entry has to exist
+impl Validator<HistoryCmd> for EntryExistsValidator {
+    fn validate(&self, context: &Context, cmd: &HistoryCmd) -> ValidationResult {
+        log::debug!("Running EntryExistsValidator for HistoryCmd");
+        self.require_entry(context, &cmd.ent_name)
+    }
+}
+
+// entry has to exist
+impl Validator<RollbackCmd> for EntryExistsValidator {
+    fn validate(&self, context: &Context, cmd: &RollbackCmd) -> ValidationResult {
+        log::debug!("Running EntryExistsValidator for RollbackCmd");
+        self.require_entry(context, &cmd.ent_name)
+    }
+}
+
+// entry has to exist
+impl Validator<TagCmd> for EntryExistsValidator {
+    fn validate(&self, context: &Context, cmd: &TagCmd) -> ValidationResult {
+        log::debug!("Running EntryExistsValidator for TagCmd");
+        self.require_entry(context, &cmd.ent_name)
     }
 }
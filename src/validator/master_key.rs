@@ -1,4 +1,4 @@
-use crate::cli::commands::{AddCmd, DestroyCmd, GetCmd, InitCmd, LogInCmd, UpdateCmd};
+use crate::cli::commands::{AddCmd, AuditCmd, DestroyCmd, ExportCmd, GetCmd, HistoryCmd, ImportCmd, InitCmd, ListCmd, LogInCmd, RollbackCmd, SearchCmd, TagCmd, UpdateCmd};
 use crate::validator::core::{Validator, ValidationResult};
 use crate::context::Context;
 
@@ -68,4 +68,92 @@ impl Validator<UpdateCmd> for MasterKeyValidator {
             ValidationResult::Failure("Master key not provided ⛔".to_string())
         }
     }
-}
\ No newline at end of file
+}
+
+impl Validator<HistoryCmd> for MasterKeyValidator {
+    fn validate(&self, context: &Context, _cmd: &HistoryCmd) -> ValidationResult {
+        log::debug!("Running MasterKeyValidator for HistoryCmd");
+        if context.kgc.borrow().is_master_key_provided() {
+            ValidationResult::Success
+        } else {
+            ValidationResult::Failure("Master key not provided ⛔".to_string())
+        }
+    }
+}
+
+impl Validator<RollbackCmd> for MasterKeyValidator {
+    fn validate(&self, context: &Context, _cmd: &RollbackCmd) -> ValidationResult {
+        log::debug!("Running MasterKeyValidator for RollbackCmd");
+        if context.kgc.borrow().is_master_key_provided() {
+            ValidationResult::Success
+        } else {
+            ValidationResult::Failure("Master key not provided ⛔".to_string())
+        }
+    }
+}
+
+impl Validator<ListCmd> for MasterKeyValidator {
+    fn validate(&self, context: &Context, _cmd: &ListCmd) -> ValidationResult {
+        log::debug!("Running MasterKeyValidator for ListCmd");
+        if context.kgc.borrow().is_master_key_provided() {
+            ValidationResult::Success
+        } else {
+            ValidationResult::Failure("Master key not provided ⛔".to_string())
+        }
+    }
+}
+
+impl Validator<AuditCmd> for MasterKeyValidator {
+    fn validate(&self, context: &Context, _cmd: &AuditCmd) -> ValidationResult {
+        log::debug!("Running MasterKeyValidator for AuditCmd");
+        if context.kgc.borrow().is_master_key_provided() {
+            ValidationResult::Success
+        } else {
+            ValidationResult::Failure("Master key not provided ⛔".to_string())
+        }
+    }
+}
+
+impl Validator<ImportCmd> for MasterKeyValidator {
+    fn validate(&self, context: &Context, _cmd: &ImportCmd) -> ValidationResult {
+        log::debug!("Running MasterKeyValidator for ImportCmd");
+        if context.kgc.borrow().is_master_key_provided() {
+            ValidationResult::Success
+        } else {
+            ValidationResult::Failure("Master key not provided ⛔".to_string())
+        }
+    }
+}
+
+impl Validator<ExportCmd> for MasterKeyValidator {
+    fn validate(&self, context: &Context, _cmd: &ExportCmd) -> ValidationResult {
+        log::debug!("Running MasterKeyValidator for ExportCmd");
+        if context.kgc.borrow().is_master_key_provided() {
+            ValidationResult::Success
+        } else {
+            ValidationResult::Failure("Master key not provided ⛔".to_string())
+        }
+    }
+}
+
+impl Validator<TagCmd> for MasterKeyValidator {
+    fn validate(&self, context: &Context, _cmd: &TagCmd) -> ValidationResult {
+        log::debug!("Running MasterKeyValidator for TagCmd");
+        if context.kgc.borrow().is_master_key_provided() {
+            ValidationResult::Success
+        } else {
+            ValidationResult::Failure("Master key not provided ⛔".to_string())
+        }
+    }
+}
+
+impl Validator<SearchCmd> for MasterKeyValidator {
+    fn validate(&self, context: &Context, _cmd: &SearchCmd) -> ValidationResult {
+        log::debug!("Running MasterKeyValidator for SearchCmd");
+        if context.kgc.borrow().is_master_key_provided() {
+            ValidationResult::Success
+        } else {
+            ValidationResult::Failure("Master key not provided ⛔".to_string())
+        }
+    }
+}
@@ -6,4 +6,5 @@ pub mod rate_limit;
 pub mod entry_exists;
 pub mod duplicate;
 pub mod pwd_req;
+pub mod breach;
 pub mod registry;
@@ -1,165 +1,175 @@
-use crate::constants::CONS;
 use crate::validator::core::{Validator, ValidationResult};
 use crate::context::Context;
 use crate::cli::commands::{AddCmd, UpdateCmd};
-use rand::seq::SliceRandom;
-use regex::Regex;
-use rand::Rng;
+use crate::policy::{load_policy, PasswordPolicy};
+use crate::strength::estimate_strength;
 pub struct PasswordRequirementValidator {}
 
+/// Scores below this are reported as weak even when the policy allows them.
+pub(crate) const WEAK_SCORE: u8 = 3;
+
 impl PasswordRequirementValidator {
-    fn has_repeated_sequence(password: &str, length: usize) -> bool {
-        let chars: Vec<char> = password.chars().collect();
-        for i in 0..=chars.len().saturating_sub(length) {
-            if chars[i..i + length].windows(2).all(|w| w[0] == w[1]) {
-                return true;
-            }
-        }
-        false
+    /// The named policy, the default one from the settings when `None`.
+    pub(crate) fn policy(context: &Context, name: Option<&str>) -> Result<PasswordPolicy, String> {
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => context.settings.borrow_mut().get_default_password_policy(context),
+        };
+        load_policy(Some(&context.db), &name)
     }
 
-    fn generate_suggested_password() -> String {
-        let mut rng = rand::thread_rng();
-        let uppercase: char = rng.gen_range(b'A'..=b'Z') as char;
-        let lowercase: char = rng.gen_range(b'a'..=b'z') as char;
-        let digit: char = rng.gen_range(b'0'..=b'9') as char;
-        let special_chars = ['!', '@', '#', '$', '%', '^', '&', '*'];
-        let special: char = *special_chars.choose(&mut rng).unwrap();
-        let mut password: Vec<char> = vec![uppercase, lowercase, digit, special];
-
-        while password.len() < CONS::MIN_PASSWORD_LENGTH + 16 {
-            let char_type = rng.gen_range(0..4);
-            let next_char = match char_type {
-                0 => rng.gen_range(b'A'..=b'Z') as char,
-                1 => rng.gen_range(b'a'..=b'z') as char,
-                2 => rng.gen_range(b'0'..=b'9') as char,
-                _ => *special_chars.choose(&mut rng).unwrap(),
-            };
-            password.push(next_char);
-        }
-
-        let password: String = password.into_iter().collect();
-        password
+    /// Checks a password typed by the user against the policy, reporting every broken rule.
+    /// `user_inputs` are the entry name, username and URL the password should not be built from.
+    pub(crate) fn check_requirements(context: &Context, policy: Option<&str>, password: &str, user_inputs: &[&str]) -> Result<(), String> {
+        let policy = PasswordRequirementValidator::policy(context, policy)?;
+        PasswordRequirementValidator::check_policy(&policy, password, user_inputs)
     }
 
-}
-
-
-
-impl Validator<UpdateCmd> for PasswordRequirementValidator {
-    fn validate(&self, _context: &Context, cmd: &UpdateCmd) -> ValidationResult {
-        log::debug!("Running PasswordRequirementValidator for UpdateCmd");
-
-        if (cmd.suggest_flag) {
-            let pwd = PasswordRequirementValidator::generate_suggested_password();
-            cmd.suggested_pwd.set(pwd);
-            return ValidationResult::Success
+    /// Same as `check_requirements` with the policy already loaded.
+    pub(crate) fn check_policy(policy: &PasswordPolicy, password: &str, user_inputs: &[&str]) -> Result<(), String> {
+        let mut violations = policy.violations(password);
+        violations.extend(policy.strength_violation(&estimate_strength(password, user_inputs)));
+        if violations.is_empty() {
+            return Ok(());
         }
+        Err(format!("Password requirements failed:\n  - {}", violations.join("\n  - ")))
+    }
 
-        if cmd.password.len() < CONS::MIN_PASSWORD_LENGTH {
-            let message = format!(
-                "Password requirements failed: Minimum length is {} but the provided password is {} characters long",
-                CONS::MIN_PASSWORD_LENGTH,
-                cmd.password.len()
-            );
-            return ValidationResult::Failure(message);
+    fn check(context: &Context, policy: Option<&str>, password: &str, generated: bool, user_inputs: &[&str]) -> ValidationResult {
+        // an unknown policy is an error even when the password is generated
+        if let Err(e) = PasswordRequirementValidator::policy(context, policy) {
+            return ValidationResult::Failure(e);
         }
 
-        let uppercase_regex = Regex::new(CONS::PASSWORD_UPPERCASE_REQ).unwrap();
-        if !uppercase_regex.is_match(&cmd.password) {
-            let message = "Password requirements failed: At least one uppercase letter (A-Z) is required".to_string();
-            // log::error!("{}", message);
+        if let Err(message) = PasswordRequirementValidator::check_requirements(context, policy, password, user_inputs) {
             return ValidationResult::Failure(message);
         }
 
-        let lowercase_regex = Regex::new(CONS::PASSWORD_LOWERCASE_REQ).unwrap();
-        if !lowercase_regex.is_match(&cmd.password) {
-            let message = "Password requirements failed: At least one lowercase letter (a-z) is required".to_string();
-            // log::error!("{}", message);
-            return ValidationResult::Failure(message);
+        // the estimate is meant for passwords people pick, not random ones
+        if generated {
+            return ValidationResult::Success
         }
 
-        let digit_regex = Regex::new(CONS::PASSWORD_DIGIT_REQ).unwrap();
-        if !digit_regex.is_match(&cmd.password) {
-            let message = "Password requirements failed: At least one digit (0-9) is required".to_string();
-            // log::error!("{}", message);
-            return ValidationResult::Failure(message);
+        // allowed by the policy but still easy to guess
+        let strength = estimate_strength(password, user_inputs);
+        if strength.score < WEAK_SCORE {
+            return ValidationResult::Warning(format!("Weak password ({}). {}", strength, strength.feedback.join(". ")));
         }
+        ValidationResult::Success
+    }
+}
 
-        let special_char_regex = Regex::new(CONS::PASSWORD_SPECIAL_CHAR_REQ).unwrap();
-        if !special_char_regex.is_match(&cmd.password) {
-            let message = "Password requirements failed: At least one special character (e.g., !, @, #, $, etc.) is required".to_string();
-            // log::error!("{}", message);
-            return ValidationResult::Failure(message);
-        }
 
-        if PasswordRequirementValidator::has_repeated_sequence(&cmd.password, 4) {
-            let message = "Password requirements failed: Password should not have a sequence of repeated characters (e.g., 'aaaa', '1111')".to_string();
-            // log::error!("{}", message);
-            return ValidationResult::Failure(message);
-        }
 
+impl Validator<UpdateCmd> for PasswordRequirementValidator {
+    fn validate(&self, context: &Context, cmd: &UpdateCmd) -> ValidationResult {
+        log::debug!("Running PasswordRequirementValidator for UpdateCmd");
 
-        ValidationResult::Success
+        // without --policy the entry keeps the one it was added with
+        let policy = cmd.policy.clone().or_else(|| {
+            context.db.get_entry_by_name(&cmd.name).ok().and_then(|entry| entry.policy)
+        });
+        PasswordRequirementValidator::check(context, policy.as_deref(), &cmd.password, cmd.suggest_flag, &cmd.user_inputs())
     }
 }
 
 impl Validator<AddCmd> for PasswordRequirementValidator {
-    fn validate(&self, _context: &Context, cmd: &AddCmd) -> ValidationResult {
+    fn validate(&self, context: &Context, cmd: &AddCmd) -> ValidationResult {
         log::debug!("Running PasswordRequirementValidator");
         // log::debug!("Flag suggest is set to {}", cmd.suggest_flag);
 
-        if (cmd.suggest_flag) {
-            let pwd = PasswordRequirementValidator::generate_suggested_password();
-            cmd.suggested_pwd.set(pwd);
-            return ValidationResult::Success
-        }
-
+        PasswordRequirementValidator::check(context, cmd.policy.as_deref(), &cmd.password, cmd.suggest_flag, &cmd.user_inputs())
+    }
+}
 
-        if cmd.password.len() < CONS::MIN_PASSWORD_LENGTH {
-            let message = format!(
-                "Password requirements failed: Minimum length is {} but the provided password is {} characters long",
-                CONS::MIN_PASSWORD_LENGTH,
-                cmd.password.len()
-            );
-            return ValidationResult::Failure(message);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Db::Entry;
+    use serial_test::serial;
+    use crate::test_support::isolated_context;
+    use tempfile::TempDir;
+
+    fn check<T>(context: &Context, cmd: &T) -> Result<(), String>
+    where
+        PasswordRequirementValidator: Validator<T>,
+    {
+        let validator = PasswordRequirementValidator {};
+        match validator.validate(context, cmd) {
+            ValidationResult::Failure(msg) => Err(msg),
+            _ => Ok(()),
         }
+    }
 
-        let uppercase_regex = Regex::new(CONS::PASSWORD_UPPERCASE_REQ).unwrap();
-        if !uppercase_regex.is_match(&cmd.password) {
-            let message = "Password requirements failed: At least one uppercase letter (A-Z) is required".to_string();
-            // log::error!("{}", message);
-            return ValidationResult::Failure(message);
-        }
+    #[test]
+    #[serial]
+    fn test_validation_is_side_effect_free() {
+        let temp_dir = TempDir::new().unwrap();
+        let (_env, context) = isolated_context(&temp_dir);
 
-        let lowercase_regex = Regex::new(CONS::PASSWORD_LOWERCASE_REQ).unwrap();
-        if !lowercase_regex.is_match(&cmd.password) {
-            let message = "Password requirements failed: At least one lowercase letter (a-z) is required".to_string();
-            // log::error!("{}", message);
-            return ValidationResult::Failure(message);
-        }
+        let weak = AddCmd::new("github".to_string(), "weak".to_string(), false);
+        let first = check(&context, &weak);
+        assert!(first.is_err());
+        assert_eq!(check(&context, &weak), first);
 
-        let digit_regex = Regex::new(CONS::PASSWORD_DIGIT_REQ).unwrap();
-        if !digit_regex.is_match(&cmd.password) {
-            let message = "Password requirements failed: At least one digit (0-9) is required".to_string();
-            // log::error!("{}", message);
-            return ValidationResult::Failure(message);
-        }
+        let generated = AddCmd::new("github".to_string(), "Password1!".to_string(), true);
+        assert!(matches!(PasswordRequirementValidator {}.validate(&context, &generated), ValidationResult::Success));
+        assert!(check(&context, &generated).is_ok());
+        assert_eq!(generated.password, "Password1!");
 
-        let special_char_regex = Regex::new(CONS::PASSWORD_SPECIAL_CHAR_REQ).unwrap();
-        if !special_char_regex.is_match(&cmd.password) {
-            let message = "Password requirements failed: At least one special character (e.g., !, @, #, $, etc.) is required".to_string();
-            // log::error!("{}", message);
-            return ValidationResult::Failure(message);
-        }
+        let update = UpdateCmd::new("github".to_string(), "Str0ng!Passw".to_string(), false);
+        assert!(check(&context, &update).is_ok());
+        assert!(check(&context, &update).is_ok());
+        assert_eq!(update.password, "Str0ng!Passw");
 
-        if PasswordRequirementValidator::has_repeated_sequence(&cmd.password, 4) {
-            let message = "Password requirements failed: Password should not have a sequence of repeated characters (e.g., 'aaaa', '1111')".to_string();
-            // log::error!("{}", message);
-            return ValidationResult::Failure(message);
-        }
+        assert!(context.db.list_entries().unwrap().is_empty());
+    }
 
+    #[test]
+    #[serial]
+    fn test_policy_selection() {
+        let temp_dir = TempDir::new().unwrap();
+        let (_env, context) = isolated_context(&temp_dir);
+        context.db.set_setting_value("password_policy.bank", "min=6 max=10 require=digits symbols=").unwrap();
+
+        let add = AddCmd::new("bank".to_string(), "123456".to_string(), false);
+        let message = check(&context, &add).unwrap_err();
+        // all the broken rules at once
+        assert_eq!(message.matches("\n  - ").count(), 4);
+
+        let add = add.with_policy(Some("bank".to_string()));
+        assert!(check(&context, &add).is_ok());
+        let unknown = AddCmd::new("bank".to_string(), "abc".to_string(), true).with_policy(Some("none".to_string()));
+        assert!(check(&context, &unknown).is_err());
+        // a suggested password is held to the policy as well
+        let generated = AddCmd::new("bank".to_string(), "xK9#mQ2$vL7!pR4z".to_string(), true).with_policy(Some("bank".to_string()));
+        assert!(check(&context, &generated).unwrap_err().contains("Maximum length"));
+
+        // an update follows the policy stored on the entry
+        let mut entry = Entry::new(0, "bank".to_string(), "secret".to_string());
+        entry.policy = Some("bank".to_string());
+        context.db.add_entry(entry).unwrap();
+        let update = UpdateCmd::new("bank".to_string(), "654321".to_string(), false);
+        assert!(check(&context, &update).is_ok());
+        let update = update.with_policy(Some("default".to_string()));
+        assert!(check(&context, &update).is_err());
+    }
 
-        ValidationResult::Success
+    #[test]
+    #[serial]
+    fn test_weak_password_warning() {
+        let temp_dir = TempDir::new().unwrap();
+        let (_env, context) = isolated_context(&temp_dir);
+        let validator = PasswordRequirementValidator {};
+
+        // follows every rule of the default policy but is one of the first guesses
+        let weak = AddCmd::new("github".to_string(), "Password1!".to_string(), false);
+        assert!(matches!(validator.validate(&context, &weak), ValidationResult::Warning(_)));
+        let strong = AddCmd::new("github".to_string(), "xK9#mQ2$vL7!pR4z".to_string(), false);
+        assert!(matches!(validator.validate(&context, &strong), ValidationResult::Success));
+
+        let strict = weak.with_policy(Some("strict".to_string()));
+        let message = check(&context, &strict).unwrap_err();
+        assert!(message.contains("too weak"));
     }
-}
\ No newline at end of file
+}
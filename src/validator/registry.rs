@@ -2,7 +2,7 @@
 
 use std::collections::HashMap;
 use crate::validator::core::{ValidationType, Validator};
-use crate::cli::commands::{AddCmd, DestroyCmd, GetCmd, InitCmd, LogInCmd, UpdateCmd};
+use crate::cli::commands::{AddCmd, AuditCmd, DestroyCmd, ExportCmd, GetCmd, HistoryCmd, ImportCmd, InitCmd, ListCmd, LogInCmd, RollbackCmd, SearchCmd, TagCmd, UpdateCmd};
 
 use crate::validator::master_key::MasterKeyValidator;
 use crate::validator::session::SessionValidator;
@@ -11,6 +11,7 @@ use crate::validator::entry_exists::EntryExistsValidator;
 use crate::validator::duplicate::DuplicateEntryValidator;
 
 use super::pwd_req::PasswordRequirementValidator;
+use super::breach::BreachValidator;
 
 pub struct ValidationRegistry<T> {
     pub validators: HashMap<ValidationType, Box<dyn Validator<T>>>,
@@ -49,7 +50,6 @@ impl ValidationRegistry<GetCmd> {
         validators.insert(ValidationType::SessionCheck, Box::new(SessionValidator {}));
         validators.insert(ValidationType::RateLimitCheck, Box::new(RateLimitValidator {}));
         validators.insert(ValidationType::EntryExistsCheck, Box::new(EntryExistsValidator {}));
-        validators.insert(ValidationType::DuplicateEntryCheck, Box::new(DuplicateEntryValidator {}));
         Self { validators }
     }
 }
@@ -63,6 +63,7 @@ impl ValidationRegistry<AddCmd> {
         validators.insert(ValidationType::EntryExistsCheck, Box::new(EntryExistsValidator {}));
         validators.insert(ValidationType::DuplicateEntryCheck, Box::new(DuplicateEntryValidator {}));
         validators.insert(ValidationType::PasswordRequirementCheck, Box::new(PasswordRequirementValidator {}));
+        validators.insert(ValidationType::BreachCheck, Box::new(BreachValidator {}));
         Self { validators }
     }
 }
@@ -75,8 +76,84 @@ impl ValidationRegistry<UpdateCmd> {
         validators.insert(ValidationType::SessionCheck, Box::new(SessionValidator {}));
         validators.insert(ValidationType::MasterKeyCheck, Box::new(MasterKeyValidator {}));
         validators.insert(ValidationType::EntryExistsCheck, Box::new(EntryExistsValidator {}));
+        validators.insert(ValidationType::DuplicateEntryCheck, Box::new(DuplicateEntryValidator {}));
         validators.insert(ValidationType::PasswordRequirementCheck, Box::new(PasswordRequirementValidator {}));
+        validators.insert(ValidationType::BreachCheck, Box::new(BreachValidator {}));
+        Self { validators }
+    }
+}
+
+impl ValidationRegistry<HistoryCmd> {
+    pub fn new() -> Self {
+        let mut validators: HashMap<ValidationType, Box<dyn Validator<HistoryCmd>>> = HashMap::new();
+        validators.insert(ValidationType::SessionCheck, Box::new(SessionValidator {}));
+        validators.insert(ValidationType::MasterKeyCheck, Box::new(MasterKeyValidator {}));
+        validators.insert(ValidationType::EntryExistsCheck, Box::new(EntryExistsValidator {}));
+        Self { validators }
+    }
+}
+
+impl ValidationRegistry<RollbackCmd> {
+    pub fn new() -> Self {
+        let mut validators: HashMap<ValidationType, Box<dyn Validator<RollbackCmd>>> = HashMap::new();
+        validators.insert(ValidationType::SessionCheck, Box::new(SessionValidator {}));
+        validators.insert(ValidationType::MasterKeyCheck, Box::new(MasterKeyValidator {}));
+        validators.insert(ValidationType::EntryExistsCheck, Box::new(EntryExistsValidator {}));
+        Self { validators }
+    }
+}
+
+impl ValidationRegistry<ListCmd> {
+    pub fn new() -> Self {
+        let mut validators: HashMap<ValidationType, Box<dyn Validator<ListCmd>>> = HashMap::new();
+        validators.insert(ValidationType::SessionCheck, Box::new(SessionValidator {}));
+        validators.insert(ValidationType::MasterKeyCheck, Box::new(MasterKeyValidator {}));
+        Self { validators }
+    }
+}
+
+impl ValidationRegistry<ExportCmd> {
+    pub fn new() -> Self {
+        let mut validators: HashMap<ValidationType, Box<dyn Validator<ExportCmd>>> = HashMap::new();
+        validators.insert(ValidationType::SessionCheck, Box::new(SessionValidator {}));
+        validators.insert(ValidationType::MasterKeyCheck, Box::new(MasterKeyValidator {}));
+        Self { validators }
+    }
+}
+
+impl ValidationRegistry<AuditCmd> {
+    pub fn new() -> Self {
+        let mut validators: HashMap<ValidationType, Box<dyn Validator<AuditCmd>>> = HashMap::new();
+        validators.insert(ValidationType::SessionCheck, Box::new(SessionValidator {}));
+        validators.insert(ValidationType::MasterKeyCheck, Box::new(MasterKeyValidator {}));
+        Self { validators }
+    }
+}
+
+impl ValidationRegistry<TagCmd> {
+    pub fn new() -> Self {
+        let mut validators: HashMap<ValidationType, Box<dyn Validator<TagCmd>>> = HashMap::new();
+        validators.insert(ValidationType::SessionCheck, Box::new(SessionValidator {}));
+        validators.insert(ValidationType::MasterKeyCheck, Box::new(MasterKeyValidator {}));
+        validators.insert(ValidationType::EntryExistsCheck, Box::new(EntryExistsValidator {}));
         Self { validators }
     }
 }
 
+impl ValidationRegistry<SearchCmd> {
+    pub fn new() -> Self {
+        let mut validators: HashMap<ValidationType, Box<dyn Validator<SearchCmd>>> = HashMap::new();
+        validators.insert(ValidationType::SessionCheck, Box::new(SessionValidator {}));
+        validators.insert(ValidationType::MasterKeyCheck, Box::new(MasterKeyValidator {}));
+        Self { validators }
+    }
+}
+
+impl ValidationRegistry<ImportCmd> {
+    pub fn new() -> Self {
+        let mut validators: HashMap<ValidationType, Box<dyn Validator<ImportCmd>>> = HashMap::new();
+        validators.insert(ValidationType::SessionCheck, Box::new(SessionValidator {}));
+        validators.insert(ValidationType::MasterKeyCheck, Box::new(MasterKeyValidator {}));
+        Self { validators }
+    }
+}
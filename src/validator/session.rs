@@ -2,7 +2,7 @@
 
 use crate::validator::core::{Validator, ValidationResult};
 use crate::context::Context;
-use crate::cli::commands::{AddCmd, DestroyCmd, GetCmd, LogInCmd, UpdateCmd};
+use crate::cli::commands::{AddCmd, AuditCmd, DestroyCmd, ExportCmd, GetCmd, HistoryCmd, ImportCmd, ListCmd, LogInCmd, RollbackCmd, SearchCmd, TagCmd, UpdateCmd};
 
 pub struct SessionValidator {}
 
@@ -63,3 +63,91 @@ impl Validator<DestroyCmd> for SessionValidator {
         }
     }
 }
+
+impl Validator<HistoryCmd> for SessionValidator {
+    fn validate(&self, context: &Context, _cmd: &HistoryCmd) -> ValidationResult {
+        log::debug!("Running SessionValidator");
+        if !context.ss.check_if_expired() {
+            ValidationResult::Success
+        } else {
+            ValidationResult::Failure("Session expired ⛔".to_string())
+        }
+    }
+}
+
+impl Validator<RollbackCmd> for SessionValidator {
+    fn validate(&self, context: &Context, _cmd: &RollbackCmd) -> ValidationResult {
+        log::debug!("Running SessionValidator");
+        if !context.ss.check_if_expired() {
+            ValidationResult::Success
+        } else {
+            ValidationResult::Failure("Session expired ⛔".to_string())
+        }
+    }
+}
+
+impl Validator<ListCmd> for SessionValidator {
+    fn validate(&self, context: &Context, _cmd: &ListCmd) -> ValidationResult {
+        log::debug!("Running SessionValidator");
+        if !context.ss.check_if_expired() {
+            ValidationResult::Success
+        } else {
+            ValidationResult::Failure("Session expired ⛔".to_string())
+        }
+    }
+}
+
+impl Validator<AuditCmd> for SessionValidator {
+    fn validate(&self, context: &Context, _cmd: &AuditCmd) -> ValidationResult {
+        log::debug!("Running SessionValidator");
+        if !context.ss.check_if_expired() {
+            ValidationResult::Success
+        } else {
+            ValidationResult::Failure("Session expired ⛔".to_string())
+        }
+    }
+}
+
+impl Validator<ImportCmd> for SessionValidator {
+    fn validate(&self, context: &Context, _cmd: &ImportCmd) -> ValidationResult {
+        log::debug!("Running SessionValidator");
+        if !context.ss.check_if_expired() {
+            ValidationResult::Success
+        } else {
+            ValidationResult::Failure("Session expired ⛔".to_string())
+        }
+    }
+}
+
+impl Validator<ExportCmd> for SessionValidator {
+    fn validate(&self, context: &Context, _cmd: &ExportCmd) -> ValidationResult {
+        log::debug!("Running SessionValidator");
+        if !context.ss.check_if_expired() {
+            ValidationResult::Success
+        } else {
+            ValidationResult::Failure("Session expired ⛔".to_string())
+        }
+    }
+}
+
+impl Validator<TagCmd> for SessionValidator {
+    fn validate(&self, context: &Context, _cmd: &TagCmd) -> ValidationResult {
+        log::debug!("Running SessionValidator");
+        if !context.ss.check_if_expired() {
+            ValidationResult::Success
+        } else {
+            ValidationResult::Failure("Session expired ⛔".to_string())
+        }
+    }
+}
+
+impl Validator<SearchCmd> for SessionValidator {
+    fn validate(&self, context: &Context, _cmd: &SearchCmd) -> ValidationResult {
+        log::debug!("Running SessionValidator");
+        if !context.ss.check_if_expired() {
+            ValidationResult::Success
+        } else {
+            ValidationResult::Failure("Session expired ⛔".to_string())
+        }
+    }
+}
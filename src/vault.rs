@@ -0,0 +1,434 @@
+use crate::utils::Utils::{get_config_home, get_data_home, get_home_dir, get_runtime_dir, get_state_home};
+use chrono::Utc;
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+pub const DEFAULT_VAULT: &str = "default";
+
+// vault picked with `--vault`, takes precedence over the environment
+static SELECTED_VAULT: OnceLock<String> = OnceLock::new();
+
+/// Selects the vault for the rest of the process, used by the global `--vault` flag.
+/// A name must be registered so that a typo does not silently open a new, empty vault.
+pub fn select_vault(vault: &str) -> Result<(), String> {
+    if !looks_like_path(vault) {
+        VaultRegistry::load().check_known(vault)?;
+    }
+    let _ = SELECTED_VAULT.set(vault.to_string());
+    Ok(())
+}
+
+/// Every file that belongs to one vault.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VaultPaths {
+    pub name: String,
+    pub config_path: PathBuf,
+    pub data_path: PathBuf,
+    pub session_path: PathBuf,
+    pub backup_dir: PathBuf,
+    pub quarantine_dir: PathBuf,
+}
+
+impl VaultPaths {
+    /// Layout of a vault kept in a single directory (`KOFL_HOME`, `--vault <path>`).
+    /// Only the session leaves it, for the runtime directory when there is one.
+    pub fn in_dir(name: &str, dir: PathBuf) -> Self {
+        let dir_id = hex::encode(Sha256::digest(dir.to_string_lossy().as_bytes()));
+        let session_path = match get_runtime_dir() {
+            Some(runtime_dir) => runtime_dir.join("kofl").join(format!("dir-{}", &dir_id[..16])).join("session.toml"),
+            None => dir.join("session.toml"),
+        };
+        VaultPaths {
+            name: name.to_string(),
+            config_path: dir.join("config.toml"),
+            data_path: dir.join("kofl.sqlite"),
+            session_path,
+            backup_dir: dir.join("backups"),
+            quarantine_dir: dir.join("quarantine"),
+        }
+    }
+
+    /// XDG layout of a named vault: config, data (the database), state
+    /// (backups, quarantine) and runtime (the session, gone on logout).
+    pub fn named(name: &str) -> Self {
+        let config_dir = get_config_home().join("kofl").join("vaults").join(name);
+        let data_dir = get_data_home().join("kofl").join("vaults").join(name);
+        let state_dir = get_state_home().join("kofl").join("vaults").join(name);
+        let session_path = match get_runtime_dir() {
+            Some(runtime_dir) => runtime_dir.join("kofl").join(name).join("session.toml"),
+            None => state_dir.join("session.toml"),
+        };
+        VaultPaths {
+            name: name.to_string(),
+            config_path: config_dir.join("config.toml"),
+            data_path: data_dir.join("kofl.sqlite"),
+            session_path,
+            backup_dir: state_dir.join("backups"),
+            quarantine_dir: state_dir.join("quarantine"),
+        }
+    }
+
+    /// Dotfiles straight in `$HOME`, as written by the first versions of kofl.
+    pub fn legacy(home_dir: PathBuf) -> Self {
+        VaultPaths {
+            name: DEFAULT_VAULT.to_string(),
+            config_path: home_dir.join(".kofl"),
+            data_path: home_dir.join("kofl.sqlite"),
+            session_path: home_dir.join(".kofl_session"),
+            backup_dir: home_dir.join(".kofl_backups"),
+            quarantine_dir: home_dir.join(".kofl_quarantine"),
+        }
+    }
+
+    /// Resolves the active vault, in order: `--vault`, `KOFL_HOME`, the vault
+    /// chosen with `kofl vault use`, and finally the default vault.
+    pub fn resolve() -> Self {
+        if let Some(vault) = SELECTED_VAULT.get() {
+            return Self::from_name_or_path(vault);
+        }
+
+        if let Ok(kofl_home) = env::var("KOFL_HOME") {
+            if !kofl_home.is_empty() {
+                return Self::in_dir("KOFL_HOME", expand_tilde(&kofl_home));
+            }
+        }
+
+        let registry = VaultRegistry::load();
+        registry.paths_of(&registry.current)
+    }
+
+    pub fn from_name_or_path(vault: &str) -> Self {
+        if looks_like_path(vault) {
+            Self::in_dir(vault, expand_tilde(vault))
+        } else {
+            VaultRegistry::load().paths_of(vault)
+        }
+    }
+
+    /// Creates the directories the vault files live in.
+    pub fn create_dirs(&self) -> std::io::Result<()> {
+        for path in [&self.config_path, &self.data_path, &self.session_path] {
+            if let Some(parent) = path.parent() {
+                create_private_dir(parent)?;
+            }
+        }
+        create_private_dir(&self.backup_dir)
+    }
+
+    pub fn exists(&self) -> bool {
+        self.config_path.exists()
+    }
+}
+
+pub(crate) fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)
+    }
+    #[cfg(not(unix))]
+    fs::create_dir_all(dir)
+}
+
+// rename does not work across file systems, fall back to copy and remove
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to)?;
+    fs::remove_file(from)
+}
+
+fn move_dir_content(from: &Path, to: &Path) -> std::io::Result<()> {
+    if !from.is_dir() {
+        return Ok(());
+    }
+    create_private_dir(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            move_dir_content(&entry.path(), &target)?;
+        } else {
+            move_file(&entry.path(), &target)?;
+        }
+    }
+    fs::remove_dir(from)
+}
+
+/// Moves a vault left in `$HOME` by older versions into the XDG layout of the
+/// default vault. The legacy files are copied aside first, returns where.
+pub fn migrate_legacy_layout() -> std::io::Result<Option<PathBuf>> {
+    let home_dir = match get_home_dir() {
+        Some(home_dir) => home_dir,
+        None => return Ok(None),
+    };
+    let legacy = VaultPaths::legacy(home_dir);
+    let target = VaultRegistry::load().paths_of(DEFAULT_VAULT);
+
+    if !legacy.exists() || target.exists() {
+        return Ok(None);
+    }
+
+    let legacy_checksum = legacy.config_path.with_extension("checksum");
+    let backup_dir = get_state_home()
+        .join("kofl")
+        .join(format!("legacy-backup-{}", Utc::now().format("%Y-%m-%d_%H_%M_%S")));
+    create_private_dir(&backup_dir)?;
+    for file in [&legacy.config_path, &legacy_checksum, &legacy.data_path] {
+        if file.exists() {
+            fs::copy(file, backup_dir.join(file.file_name().unwrap()))?;
+        }
+    }
+
+    target.create_dirs()?;
+    move_file(&legacy.config_path, &target.config_path)?;
+    if legacy_checksum.exists() {
+        move_file(&legacy_checksum, &target.config_path.with_extension("checksum"))?;
+    }
+    if legacy.data_path.exists() {
+        move_file(&legacy.data_path, &target.data_path)?;
+    }
+    move_dir_content(&legacy.backup_dir, &target.backup_dir)?;
+    move_dir_content(&legacy.quarantine_dir, &target.quarantine_dir)?;
+    if legacy.session_path.exists() {
+        // sessions are not worth carrying over, login again
+        fs::remove_file(&legacy.session_path)?;
+    }
+
+    info!("Moved the kofl files from your home directory to {}", target.config_path.parent().unwrap().display());
+    info!("A copy of the old files was kept in {}", backup_dir.display());
+    debug!("legacy vault migrated to {:?}", target);
+    Ok(Some(backup_dir))
+}
+
+fn looks_like_path(vault: &str) -> bool {
+    vault.contains('/') || vault.contains('\\') || vault.starts_with('.') || vault.starts_with('~')
+}
+
+fn expand_tilde(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), get_home_dir()) {
+        (Some(rest), Some(home_dir)) => home_dir.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+pub fn is_valid_vault_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 60
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// List of named vaults and the one currently in use, kept in
+/// `$XDG_CONFIG_HOME/kofl/vaults.toml`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct VaultRegistry {
+    pub current: String,
+    #[serde(default)]
+    pub vaults: BTreeMap<String, VaultEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct VaultEntry {
+    // set for vaults living in a custom directory, others use the XDG layout
+    pub path: Option<PathBuf>,
+}
+
+impl VaultRegistry {
+    pub fn registry_path() -> PathBuf {
+        get_config_home().join("kofl").join("vaults.toml")
+    }
+
+    pub fn load() -> Self {
+        fs::read_to_string(Self::registry_path())
+            .ok()
+            .and_then(|content| toml::from_str(&content).ok())
+            .unwrap_or_else(|| VaultRegistry {
+                current: DEFAULT_VAULT.to_string(),
+                vaults: BTreeMap::new(),
+            })
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = Self::registry_path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = toml::to_string(self).expect("could not serialize vault registry");
+        fs::write(path, content)
+    }
+
+    pub fn paths_of(&self, name: &str) -> VaultPaths {
+        if let Some(VaultEntry { path: Some(dir) }) = self.vaults.get(name) {
+            return VaultPaths::in_dir(name, dir.clone());
+        }
+
+        VaultPaths::named(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        name == DEFAULT_VAULT || self.vaults.contains_key(name)
+    }
+
+    /// Refuses a name that is malformed or not registered.
+    pub fn check_known(&self, name: &str) -> Result<(), String> {
+        if !is_valid_vault_name(name) {
+            return Err("Vault names may only contain letters, digits, '-' and '_' ⛔".to_string());
+        }
+        if !self.contains(name) {
+            return Err(format!("No vault named {}, create it with `kofl vault create {}` ⛔", name, name));
+        }
+        Ok(())
+    }
+
+    /// Registered names, the default vault is always part of them.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.vaults.keys().cloned().collect();
+        if !names.iter().any(|n| n == DEFAULT_VAULT) {
+            names.insert(0, DEFAULT_VAULT.to_string());
+        }
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use crate::test_support::IsolatedEnv;
+    use tempfile::TempDir;
+
+    #[test]
+    #[serial]
+    fn test_default_vault_uses_xdg_dirs() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let _env = IsolatedEnv::new(temp_dir.path());
+
+        let paths = VaultPaths::resolve();
+
+        assert_eq!(paths.name, DEFAULT_VAULT);
+        assert_eq!(
+            paths.config_path,
+            temp_dir.path().join("config/kofl/vaults/default/config.toml")
+        );
+        assert_eq!(
+            paths.data_path,
+            temp_dir.path().join("data/kofl/vaults/default/kofl.sqlite")
+        );
+        assert_eq!(
+            paths.backup_dir,
+            temp_dir.path().join("state/kofl/vaults/default/backups")
+        );
+        assert_eq!(
+            paths.session_path,
+            temp_dir.path().join("run/kofl/default/session.toml")
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_kofl_home_overrides_registry() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let env = IsolatedEnv::new(temp_dir.path());
+        env.set_var("KOFL_HOME", temp_dir.path().join("secure").to_str().unwrap());
+
+        let paths = VaultPaths::resolve();
+
+        assert_eq!(paths.config_path, temp_dir.path().join("secure/config.toml"));
+        assert_eq!(paths.data_path, temp_dir.path().join("secure/kofl.sqlite"));
+        assert!(paths.session_path.starts_with(temp_dir.path().join("run/kofl")));
+    }
+
+    #[test]
+    #[serial]
+    fn test_registry_selects_current_vault() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let _env = IsolatedEnv::new(temp_dir.path());
+
+        let mut registry = VaultRegistry::load();
+        registry.vaults.insert("work".to_string(), VaultEntry::default());
+        registry.vaults.insert(
+            "usb".to_string(),
+            VaultEntry { path: Some(temp_dir.path().join("usb")) },
+        );
+        registry.current = "work".to_string();
+        registry.save().unwrap();
+
+        let registry = VaultRegistry::load();
+        assert_eq!(registry.names(), vec!["default", "usb", "work"]);
+        assert_eq!(VaultPaths::resolve().name, "work");
+        assert_eq!(
+            registry.paths_of("usb").config_path,
+            temp_dir.path().join("usb/config.toml")
+        );
+
+        // each vault gets its own session
+        assert_ne!(registry.paths_of("usb").session_path, registry.paths_of("work").session_path);
+    }
+
+    #[test]
+    #[serial]
+    fn test_legacy_layout_is_migrated() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let _env = IsolatedEnv::new(temp_dir.path());
+        let home = temp_dir.path();
+        fs::write(home.join(".kofl"), "config content").unwrap();
+        fs::write(home.join(".kofl.checksum"), "checksum content").unwrap();
+        fs::write(home.join("kofl.sqlite"), "database content").unwrap();
+        fs::write(home.join(".kofl_session"), "session content").unwrap();
+        fs::create_dir_all(home.join(".kofl_backups/2025-01-01_00_00_00")).unwrap();
+        fs::write(home.join(".kofl_backups/2025-01-01_00_00_00/.kofl"), "old config").unwrap();
+
+        let backup_dir = migrate_legacy_layout().unwrap().expect("legacy layout should be migrated");
+
+        let paths = VaultPaths::resolve();
+        assert_eq!(fs::read_to_string(&paths.config_path).unwrap(), "config content");
+        assert_eq!(
+            fs::read_to_string(paths.config_path.with_extension("checksum")).unwrap(),
+            "checksum content"
+        );
+        assert_eq!(fs::read_to_string(&paths.data_path).unwrap(), "database content");
+        assert!(paths.backup_dir.join("2025-01-01_00_00_00/.kofl").exists());
+
+        // nothing left behind in the home directory, but a copy was kept
+        for legacy_file in [".kofl", ".kofl.checksum", "kofl.sqlite", ".kofl_session", ".kofl_backups"] {
+            assert!(!home.join(legacy_file).exists(), "{} should be gone", legacy_file);
+        }
+        assert_eq!(fs::read_to_string(backup_dir.join("kofl.sqlite")).unwrap(), "database content");
+
+        // running it again is a no-op
+        assert!(migrate_legacy_layout().unwrap().is_none());
+    }
+
+    #[test]
+    #[serial]
+    fn test_unknown_vault_is_refused() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let _env = IsolatedEnv::new(temp_dir.path());
+
+        let mut registry = VaultRegistry::load();
+        registry.vaults.insert("work".to_string(), VaultEntry::default());
+
+        assert!(registry.check_known("work").is_ok());
+        assert!(registry.check_known(DEFAULT_VAULT).is_ok());
+        assert!(registry.check_known("wrok").unwrap_err().contains("No vault named wrok"));
+        assert!(registry.check_known("work!").unwrap_err().contains("Vault names may only contain"));
+    }
+
+    #[test]
+    fn test_vault_names() {
+        assert!(is_valid_vault_name("work"));
+        assert!(is_valid_vault_name("personal_2"));
+        assert!(!is_valid_vault_name(""));
+        assert!(!is_valid_vault_name("../etc"));
+        assert!(looks_like_path("./vault"));
+        assert!(looks_like_path("/mnt/secure/kofl"));
+        assert!(!looks_like_path("work"));
+    }
+}